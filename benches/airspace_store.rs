@@ -0,0 +1,128 @@
+//! Throughput of `AirspaceStore::step` on a burst of pending aircraft, applying one
+//! aircraft per step as the store used to, every pending aircraft at once, or a budget.
+//!
+//! Uses a synthetic peak second by default. Set `FLIGHTS_BENCH_AIRCRAFT` to an aircraft
+//! recording made with `[parser] write_path = "....jsonl"` to replay real traffic instead.
+//! Before measuring, the batched modes are checked to drain the traffic in fewer ticks
+//! than the traffic took to arrive.
+
+use criterion::{BatchSize, Criterion, Throughput, criterion_group, criterion_main};
+use flights::core::airspace::AirspaceStore;
+use flights::core::parser::{AddressType, Aircraft, AircraftType, GpsAccuracy};
+use flights::core::thread_manager::SteppableTask;
+use ogn_aprs_parser::ICAOAddress;
+
+/// Roughly the worldwide OGN feed at its busiest.
+const PEAK_AIRCRAFT: u32 = 5_000;
+const PEAK_UPDATES_PER_SECOND: u32 = 4_000;
+/// How often the pipeline steps the store.
+const TICK: std::time::Duration = std::time::Duration::from_micros(16667);
+
+fn synthetic_peak_second() -> Vec<Aircraft> {
+    let start = chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+    (0..PEAK_UPDATES_PER_SECOND)
+        .map(|update| Aircraft {
+            callsign: format!("FLR{:06X}", update % PEAK_AIRCRAFT),
+            icao_address: ICAOAddress::new(update % PEAK_AIRCRAFT).ok(),
+            datetime: start
+                + chrono::TimeDelta::microseconds(
+                    i64::from(update) * 1_000_000 / i64::from(PEAK_UPDATES_PER_SECOND),
+                ),
+            latitude: 45.0 + f64::from(update % 90) / 10.0,
+            longitude: 5.0 + f64::from(update % 70) / 10.0,
+            ground_track: Some(f64::from(update % 360)),
+            ground_speed_knots: Some(30.0),
+            gps_altitude_feet: Some(1500.0),
+            receiver: String::from("LFNW"),
+            aircraft_type: AircraftType::Glider,
+            address_type: AddressType::Flarm,
+            stealth: false,
+            no_tracking: false,
+            climb_rate_metres_per_second: Some(1.5),
+            turn_rate_degrees_per_second: Some(0.0),
+            signal_quality_db: Some(8.8),
+            frequency_offset_khz: Some(-4.3),
+            gps_accuracy: Some(GpsAccuracy {
+                horizontal_metres: 2,
+                vertical_metres: 3,
+            }),
+        })
+        .collect()
+}
+
+fn recorded_aircraft(path: &str) -> Vec<Aircraft> {
+    std::fs::read_to_string(path)
+        .unwrap_or_else(|err| panic!("Failed to read {path}: {err}"))
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).expect("not an aircraft JSON line"))
+        .collect()
+}
+
+/// Steps a store until `aircraft` are all applied, returning how many steps that took.
+fn drain(aircraft: &[Aircraft], batch_budget: Option<usize>) -> impl FnMut() -> usize {
+    let (sender, receiver) = crossbeam_channel::unbounded();
+    for aircraft in aircraft {
+        sender.send(aircraft.clone()).unwrap();
+    }
+    let mut store = AirspaceStore::new(receiver, chrono::TimeDelta::seconds(60));
+    if let Some(batch_budget) = batch_budget {
+        store = store.with_batch_budget(batch_budget);
+    }
+    move || {
+        let mut steps = 0;
+        while !sender.is_empty() {
+            store.step();
+            steps += 1;
+        }
+        steps
+    }
+}
+
+/// How many ticks of the pipeline pass while `aircraft` arrive, at least one second's.
+fn arrival_ticks(aircraft: &[Aircraft]) -> usize {
+    let first = aircraft.iter().map(|aircraft| aircraft.datetime).min();
+    let last = aircraft.iter().map(|aircraft| aircraft.datetime).max();
+    let span = first
+        .zip(last)
+        .and_then(|(first, last)| (last - first).to_std().ok())
+        .unwrap_or_default()
+        .max(std::time::Duration::from_secs(1));
+    usize::try_from(span.as_micros() / TICK.as_micros()).unwrap_or(usize::MAX)
+}
+
+fn airspace_store_throughput(criterion: &mut Criterion) {
+    let (label, aircraft) = match std::env::var("FLIGHTS_BENCH_AIRCRAFT") {
+        Ok(path) => ("recorded", recorded_aircraft(&path)),
+        Err(_) => ("synthetic_peak_second", synthetic_peak_second()),
+    };
+
+    let arrival_ticks = arrival_ticks(&aircraft);
+
+    let mut group = criterion.benchmark_group("airspace_store");
+    group.throughput(Throughput::Elements(aircraft.len() as u64));
+    for (name, batch_budget) in [
+        ("one_per_step", Some(1)),
+        ("drain_all", None),
+        ("budget_1000", Some(1000)),
+    ] {
+        if batch_budget != Some(1) {
+            let steps = drain(&aircraft, batch_budget)();
+            assert!(
+                steps <= arrival_ticks,
+                "{name} needs {steps} ticks for traffic that arrived over {arrival_ticks}"
+            );
+        }
+        group.bench_function(format!("{label}/{name}"), |bencher| {
+            bencher.iter_batched(
+                || drain(&aircraft, batch_budget),
+                |mut drain| drain(),
+                BatchSize::LargeInput,
+            );
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, airspace_store_throughput);
+criterion_main!(benches);
@@ -0,0 +1,142 @@
+//! Throughput of the parser as its worker count grows.
+//!
+//! For each worker count, a dispatcher and `workers` parsers are started once on their
+//! own threads. Each run then feeds them a burst of synthetic worldwide traffic, several
+//! beacons per sender, and times until every aircraft comes out, checking that each
+//! sender's aircraft kept their order. Throughput stops growing once the workers and the
+//! dispatcher take every core.
+
+use criterion::{Criterion, Throughput, criterion_group, criterion_main};
+use flights::core::ingestor::AprsPacket;
+use flights::core::parser::{Aircraft, AircraftParser, ParserDispatcher};
+use flights::core::thread_manager::ThreadManager;
+
+/// Roughly the worldwide OGN feed at its busiest.
+const PEAK_AIRCRAFT: u32 = 5_000;
+const PEAK_UPDATES_PER_SECOND: u32 = 4_000;
+/// Seconds of traffic per burst, so that every sender is heard several times.
+const BURST_SECONDS: u32 = 10;
+/// 2024-06-01T16:31:50Z, just after the beacons' 16:31:48.
+const RECEIVED_SECONDS: u64 = 1_717_259_510;
+/// A burst not parsed within this long means some synthetic line fails to parse.
+const BURST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// A burst of traffic in which the `n`th beacon of each sender reports a track of `n`
+/// degrees, so that the order of a sender's aircraft can be checked.
+fn synthetic_peak_burst() -> Vec<AprsPacket> {
+    let received = std::time::UNIX_EPOCH + std::time::Duration::from_secs(RECEIVED_SECONDS);
+    (0..PEAK_UPDATES_PER_SECOND * BURST_SECONDS)
+        .map(|update| {
+            let address = update % PEAK_AIRCRAFT;
+            let sequence = update / PEAK_AIRCRAFT;
+            AprsPacket {
+                timestamp: received,
+                message: format!(
+                    "FLR{address:06X}>OGFLR,qAS,LFNW:/163148h{:02}{:02}.85N/{:03}{:02}.19E'{sequence:03}/012/A=001345 !W06! id06{address:06X} -454fpm -1.1rot 8.8dB 0e -4.3kHz gps2x3",
+                    45 + update % 10,
+                    update % 60,
+                    update % 180,
+                    update % 60,
+                )
+                .into(),
+                source: "bench".into(),
+            }
+        })
+        .collect()
+}
+
+/// A dispatcher and its parsers, running until dropped.
+struct ParserPool {
+    thread_manager: ThreadManager,
+    packet_sender: crossbeam_channel::Sender<AprsPacket>,
+    aircraft_receiver: crossbeam_channel::Receiver<Aircraft>,
+}
+impl ParserPool {
+    fn start(workers: usize) -> Self {
+        let (packet_sender, packet_receiver) = crossbeam_channel::unbounded();
+        let (aircraft_sender, aircraft_receiver) = crossbeam_channel::unbounded();
+        let (shard_senders, shard_receivers): (Vec<_>, Vec<_>) =
+            (0..workers).map(|_| crossbeam_channel::unbounded()).unzip();
+        let mut thread_manager = ThreadManager::new();
+        thread_manager.add_task(
+            ParserDispatcher::new(packet_receiver, shard_senders),
+            std::time::Duration::ZERO,
+        );
+        for shard_receiver in shard_receivers {
+            thread_manager.add_task(
+                AircraftParser::new(shard_receiver, aircraft_sender.clone()),
+                std::time::Duration::ZERO,
+            );
+        }
+        Self {
+            thread_manager,
+            packet_sender,
+            aircraft_receiver,
+        }
+    }
+
+    /// Parses `packets`, returning how long until all were parsed and the aircraft in
+    /// the order they came out.
+    fn parse(&self, packets: Vec<AprsPacket>) -> (std::time::Duration, Vec<Aircraft>) {
+        let count = packets.len();
+        let mut aircraft = Vec::with_capacity(count);
+        let started = std::time::Instant::now();
+        for packet in packets {
+            self.packet_sender.send(packet).unwrap();
+        }
+        while aircraft.len() < count {
+            aircraft.push(
+                self.aircraft_receiver
+                    .recv_timeout(BURST_TIMEOUT)
+                    .expect("a synthetic beacon failed to parse"),
+            );
+        }
+        (started.elapsed(), aircraft)
+    }
+}
+impl Drop for ParserPool {
+    fn drop(&mut self) {
+        self.thread_manager.stop_all_tasks();
+        self.thread_manager.wait_on_all_tasks();
+    }
+}
+
+/// Panics unless every sender's aircraft came out in the order of their tracks.
+fn assert_ordered_per_sender(aircraft: &[Aircraft]) {
+    let mut last_tracks = std::collections::HashMap::new();
+    for aircraft in aircraft {
+        let last_track = last_tracks.insert(&aircraft.callsign, aircraft.ground_track);
+        assert!(
+            last_track.is_none_or(|last_track| last_track < aircraft.ground_track),
+            "{} came out of order",
+            aircraft.callsign
+        );
+    }
+}
+
+fn parser_pool_scaling(criterion: &mut Criterion) {
+    let packets = synthetic_peak_burst();
+    let mut group = criterion.benchmark_group("parser_pool");
+    group.throughput(Throughput::Elements(packets.len() as u64));
+    for workers in [1, 2, 4, 8] {
+        let pool = ParserPool::start(workers);
+        group.bench_function(
+            format!("synthetic_peak_burst/{workers}_workers"),
+            |bencher| {
+                bencher.iter_custom(|iterations| {
+                    (0..iterations)
+                        .map(|_| {
+                            let (elapsed, aircraft) = pool.parse(packets.clone());
+                            assert_ordered_per_sender(&aircraft);
+                            elapsed
+                        })
+                        .sum()
+                });
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, parser_pool_scaling);
+criterion_main!(benches);
@@ -1,7 +1,7 @@
 use clap::Parser;
 
 #[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
+#[command(version, about, long_about = None, subcommand_negates_reqs = true)]
 pub struct Cli {
     #[arg(long)]
     pub duration: Option<u64>,
@@ -12,6 +12,22 @@ pub struct Cli {
     #[arg(short, long, default_value_t = log::LevelFilter::Info)]
     pub logging_level: log::LevelFilter,
 
-    #[arg(long)]
-    pub config_file: std::path::PathBuf,
+    #[arg(long, required = true)]
+    pub config_file: Option<std::path::PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(clap::Subcommand, Debug)]
+pub enum Command {
+    /// Rebuilds the time index sidecar of a `.pb` recording, e.g. one recorded before
+    /// indexes were written.
+    RebuildIndex {
+        recording: std::path::PathBuf,
+
+        /// Minimum number of seconds between index entries.
+        #[arg(long, default_value_t = 10)]
+        interval_seconds: u64,
+    },
 }
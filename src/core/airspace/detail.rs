@@ -1,13 +1,29 @@
 use ogn_aprs_parser::ICAOAddress;
 
-use crate::core::parser::Aircraft;
+use crate::core::parser::{AddressType, Aircraft};
+
+/// What the history of an aircraft is kept under. Addresses are only unique within their
+/// type, and trackers without an address are told apart by callsign.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TrackKey {
+    Address(AddressType, ICAOAddress),
+    Callsign(String),
+}
+impl From<&Aircraft> for TrackKey {
+    fn from(aircraft: &Aircraft) -> Self {
+        match aircraft.icao_address {
+            Some(icao_address) => Self::Address(aircraft.address_type, icao_address),
+            None => Self::Callsign(aircraft.callsign.clone()),
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct Airspace {
     buffer_duration: chrono::Duration,
     datetime: chrono::DateTime<chrono::Utc>,
-    icao_to_aircraft_map:
-        std::collections::HashMap<ICAOAddress, std::collections::VecDeque<Aircraft>>,
+    track_to_aircraft_map:
+        std::collections::HashMap<TrackKey, std::collections::VecDeque<Aircraft>>,
 }
 impl Airspace {
     #[must_use]
@@ -15,7 +31,7 @@ impl Airspace {
         Airspace {
             buffer_duration,
             datetime: chrono::DateTime::<chrono::Utc>::MIN_UTC,
-            icao_to_aircraft_map: std::collections::HashMap::new(),
+            track_to_aircraft_map: std::collections::HashMap::new(),
         }
     }
 
@@ -34,7 +50,7 @@ impl Airspace {
                 continue;
             }
 
-            let history = self.get_history_or_create_empty_history(aircraft.icao_address);
+            let history = self.get_history_or_create_empty_history(TrackKey::from(&aircraft));
 
             // We expect that the new data is normally most recent data, so we check that we can push
             // back into the end of the VecDeque
@@ -61,11 +77,8 @@ impl Airspace {
     }
 
     #[must_use]
-    pub fn get_history(
-        &self,
-        icao_address: ICAOAddress,
-    ) -> Option<&std::collections::VecDeque<Aircraft>> {
-        self.icao_to_aircraft_map.get(&icao_address)
+    pub fn get_history(&self, key: &TrackKey) -> Option<&std::collections::VecDeque<Aircraft>> {
+        self.track_to_aircraft_map.get(key)
     }
 
     #[must_use]
@@ -74,10 +87,10 @@ impl Airspace {
     }
 
     #[must_use]
-    pub fn icao_to_aircraft_mapping(
+    pub fn track_to_aircraft_mapping(
         &self,
-    ) -> &std::collections::HashMap<ICAOAddress, std::collections::VecDeque<Aircraft>> {
-        &self.icao_to_aircraft_map
+    ) -> &std::collections::HashMap<TrackKey, std::collections::VecDeque<Aircraft>> {
+        &self.track_to_aircraft_map
     }
 
     fn prune(&mut self) {
@@ -86,7 +99,7 @@ impl Airspace {
             .checked_sub_signed(self.buffer_duration)
             .unwrap_or(chrono::DateTime::<chrono::Utc>::MIN_UTC);
 
-        for aircraft_history in self.icao_to_aircraft_map.values_mut() {
+        for aircraft_history in self.track_to_aircraft_map.values_mut() {
             while let Some(aircraft) = aircraft_history.front() {
                 if aircraft.datetime < cutoff_time {
                     aircraft_history.pop_front();
@@ -97,12 +110,12 @@ impl Airspace {
         }
     }
 
-    // method to get history of an address, but populates a default empty VecDeque if icao_address does not exist
+    // method to get history of a track, but populates a default empty VecDeque if the key does not exist
     fn get_history_or_create_empty_history(
         &mut self,
-        icao_address: ICAOAddress,
+        key: TrackKey,
     ) -> &mut std::collections::VecDeque<Aircraft> {
-        self.icao_to_aircraft_map.entry(icao_address).or_default()
+        self.track_to_aircraft_map.entry(key).or_default()
     }
 }
 
@@ -111,9 +124,14 @@ mod tests {
 
     use ogn_aprs_parser::ICAOAddress;
 
-    use crate::core::airspace::detail::Airspace;
+    use crate::core::airspace::detail::{Airspace, TrackKey};
+    use crate::core::parser::{AddressType, Aircraft};
     use crate::test_utilities::create_dummy_aircraft_at_time;
 
+    fn dummy_key(icao_address: ICAOAddress) -> TrackKey {
+        TrackKey::Address(AddressType::default(), icao_address)
+    }
+
     fn to_datetime(time_string: &str) -> chrono::DateTime<chrono::Utc> {
         let today = chrono::Utc::now().date_naive();
         let time = chrono::NaiveTime::parse_from_str(time_string, "%H:%M:%S")
@@ -127,7 +145,7 @@ mod tests {
         let mut airspace = Airspace {
             buffer_duration: chrono::TimeDelta::seconds(5),
             datetime: now_datetime,
-            icao_to_aircraft_map: std::collections::HashMap::new(),
+            track_to_aircraft_map: std::collections::HashMap::new(),
         };
 
         let expected_aircraft_1_icao_address = ICAOAddress::new(0).unwrap();
@@ -144,20 +162,20 @@ mod tests {
 
         airspace.update(aircrafts);
 
-        assert_eq!(airspace.icao_to_aircraft_map.len(), 2);
+        assert_eq!(airspace.track_to_aircraft_map.len(), 2);
 
         // check aircraft 1 inserted
         let aircraft_1_history = airspace
-            .icao_to_aircraft_map
-            .get(&expected_aircraft_1_icao_address)
+            .track_to_aircraft_map
+            .get(&dummy_key(expected_aircraft_1_icao_address))
             .expect("expected a VecDeque for aircraft 1");
         assert_eq!(aircraft_1_history.len(), 1);
         assert_eq!(aircraft_1_history[0].datetime, expected_aircraft_1_datetime);
 
         // check aircraft 2 inserted
         let aircraft_2_history = airspace
-            .icao_to_aircraft_map
-            .get(&expected_aircraft_2_icao_address)
+            .track_to_aircraft_map
+            .get(&dummy_key(expected_aircraft_2_icao_address))
             .expect("expected a VecDeque for aircraft 1");
         assert_eq!(aircraft_2_history.len(), 1);
         assert_eq!(aircraft_2_history[0].datetime, expected_aircraft_2_datetime);
@@ -183,6 +201,40 @@ mod tests {
         assert_eq!(airspace.datetime, now_datetime);
     }
 
+    #[test]
+    fn given_trackers_sharing_an_address_when_added_then_they_keep_separate_histories() {
+        let now = chrono::Utc::now();
+        let address = ICAOAddress::new(0x00DD_A5BA).unwrap();
+        let flarm = Aircraft {
+            address_type: AddressType::Flarm,
+            ..create_dummy_aircraft_at_time(now, address)
+        };
+        let icao = Aircraft {
+            address_type: AddressType::Icao,
+            ..create_dummy_aircraft_at_time(now, address)
+        };
+        let unaddressed = |callsign: &str| Aircraft {
+            callsign: callsign.into(),
+            icao_address: None,
+            ..create_dummy_aircraft_at_time(now, address)
+        };
+        let mut airspace = Airspace::new(chrono::TimeDelta::seconds(5));
+
+        airspace.update(vec![
+            flarm,
+            icao,
+            unaddressed("N0CALL-9"),
+            unaddressed("N0CALL-11"),
+        ]);
+
+        assert_eq!(airspace.track_to_aircraft_map.len(), 4);
+        assert!(
+            airspace
+                .get_history(&TrackKey::Callsign("N0CALL-9".into()))
+                .is_some()
+        );
+    }
+
     #[cfg(test)]
     mod when_adding_aircrafts_to_existing_entries {
         use super::*;
@@ -199,7 +251,7 @@ mod tests {
             let time_c = now - chrono::TimeDelta::seconds(1);
 
             let existing_order_mapping = [(
-                aircraft_icao_address,
+                dummy_key(aircraft_icao_address),
                 std::collections::VecDeque::from([
                     create_dummy_aircraft_at_time(time_a, aircraft_icao_address),
                     create_dummy_aircraft_at_time(time_b, aircraft_icao_address),
@@ -209,7 +261,7 @@ mod tests {
             let mut airspace = Airspace {
                 buffer_duration: chrono::TimeDelta::seconds(5),
                 datetime: to_datetime("00:01:00"),
-                icao_to_aircraft_map: existing_order_mapping.into_iter().collect(),
+                track_to_aircraft_map: existing_order_mapping.into_iter().collect(),
             };
             dbg!(&airspace);
             let new_data = vec![create_dummy_aircraft_at_time(time_c, aircraft_icao_address)];
@@ -217,7 +269,7 @@ mod tests {
             airspace.update(new_data);
 
             let history = airspace
-                .get_history(aircraft_icao_address)
+                .get_history(&dummy_key(aircraft_icao_address))
                 .expect("expected to have history");
             dbg!(&airspace);
 
@@ -237,7 +289,7 @@ mod tests {
             let time_c = now;
 
             let existing_order_mapping = [(
-                aircraft_icao_address,
+                dummy_key(aircraft_icao_address),
                 std::collections::VecDeque::from([
                     create_dummy_aircraft_at_time(time_b, aircraft_icao_address),
                     create_dummy_aircraft_at_time(time_c, aircraft_icao_address),
@@ -247,14 +299,14 @@ mod tests {
             let mut airspace = Airspace {
                 buffer_duration: chrono::TimeDelta::seconds(5),
                 datetime: to_datetime("00:01:00"),
-                icao_to_aircraft_map: existing_order_mapping.into_iter().collect(),
+                track_to_aircraft_map: existing_order_mapping.into_iter().collect(),
             };
             let new_data = vec![create_dummy_aircraft_at_time(time_a, aircraft_icao_address)];
 
             airspace.update(new_data);
 
             let history = airspace
-                .get_history(aircraft_icao_address)
+                .get_history(&dummy_key(aircraft_icao_address))
                 .expect("expected to have history");
 
             assert_eq!(history.len(), 3);
@@ -275,7 +327,7 @@ mod tests {
             let time_d = now;
 
             let existing_order_mapping = [(
-                aircraft_icao_address,
+                dummy_key(aircraft_icao_address),
                 std::collections::VecDeque::from([
                     create_dummy_aircraft_at_time(time_a, aircraft_icao_address),
                     create_dummy_aircraft_at_time(time_b, aircraft_icao_address),
@@ -286,14 +338,14 @@ mod tests {
             let mut airspace = Airspace {
                 buffer_duration: chrono::TimeDelta::seconds(5),
                 datetime: to_datetime("00:01:00"),
-                icao_to_aircraft_map: existing_order_mapping.into_iter().collect(),
+                track_to_aircraft_map: existing_order_mapping.into_iter().collect(),
             };
             let new_data = vec![create_dummy_aircraft_at_time(time_c, aircraft_icao_address)];
 
             airspace.update(new_data);
 
             let history = airspace
-                .get_history(aircraft_icao_address)
+                .get_history(&dummy_key(aircraft_icao_address))
                 .expect("expected to have history");
 
             assert_eq!(history.len(), 4);
@@ -1,5 +1,12 @@
 mod detail;
+pub mod protobuf;
+mod snapshot;
 mod task;
 
-pub use detail::Airspace;
+// Generated `airspace` protobuf code refers to the imported `parser` package as `super::parser`.
+use crate::core::parser::protobuf as parser;
+
+pub use detail::{Airspace, TrackKey};
+pub use protobuf::PbAirspaceSnapshot;
+pub use snapshot::AirspaceSnapshot;
 pub use task::{AirspaceStore, AirspaceViewer};
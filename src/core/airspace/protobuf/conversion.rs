@@ -0,0 +1,55 @@
+use crate::core::airspace::AirspaceSnapshot;
+use crate::core::airspace::protobuf::PbAirspaceSnapshot;
+use crate::core::parser::errors::AircraftConversionError;
+use crate::core::parser::protobuf::conversion::{datetime_from_timestamp, timestamp_from_datetime};
+
+impl TryFrom<PbAirspaceSnapshot> for AirspaceSnapshot {
+    type Error = AircraftConversionError;
+    fn try_from(snapshot: PbAirspaceSnapshot) -> Result<Self, Self::Error> {
+        Ok(Self {
+            datetime: datetime_from_timestamp(snapshot.datetime)?,
+            aircraft: snapshot
+                .aircraft
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+impl From<AirspaceSnapshot> for PbAirspaceSnapshot {
+    fn from(snapshot: AirspaceSnapshot) -> Self {
+        Self {
+            datetime: Some(timestamp_from_datetime(snapshot.datetime)),
+            aircraft: snapshot.aircraft.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ogn_aprs_parser::ICAOAddress;
+    use prost::Message;
+
+    use super::*;
+    use crate::test_utilities::create_dummy_aircraft_at_time;
+
+    #[test]
+    fn given_snapshot_when_converted_to_proto_and_back_then_it_is_unchanged() {
+        let datetime = chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let snapshot = AirspaceSnapshot {
+            datetime,
+            aircraft: vec![
+                create_dummy_aircraft_at_time(datetime, ICAOAddress::new(1).unwrap()),
+                create_dummy_aircraft_at_time(datetime, ICAOAddress::new(2).unwrap()),
+            ],
+        };
+
+        let encoded = PbAirspaceSnapshot::from(snapshot.clone()).encode_to_vec();
+        let decoded =
+            AirspaceSnapshot::try_from(PbAirspaceSnapshot::decode(encoded.as_slice()).unwrap())
+                .unwrap();
+
+        assert_eq!(decoded, snapshot);
+    }
+}
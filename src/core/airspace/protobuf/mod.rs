@@ -0,0 +1,3 @@
+include!(concat!(env!("OUT_DIR"), "/airspace.rs"));
+
+pub mod conversion;
@@ -0,0 +1,62 @@
+use crate::core::airspace::Airspace;
+use crate::core::parser::Aircraft;
+
+/// The latest known state of every aircraft in the airspace at `datetime`, ordered by
+/// ICAO address, after trackers without one in order of callsign.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct AirspaceSnapshot {
+    pub datetime: chrono::DateTime<chrono::Utc>,
+    pub aircraft: Vec<Aircraft>,
+}
+
+impl From<&Airspace> for AirspaceSnapshot {
+    fn from(airspace: &Airspace) -> Self {
+        let mut aircraft: Vec<Aircraft> = airspace
+            .track_to_aircraft_mapping()
+            .values()
+            .filter_map(|history| history.back().cloned())
+            .collect();
+        aircraft.sort_by(|first, second| {
+            let address =
+                |aircraft: &Aircraft| aircraft.icao_address.map(|address| address.value());
+            (address(first), &first.callsign).cmp(&(address(second), &second.callsign))
+        });
+        Self {
+            datetime: airspace.get_datetime(),
+            aircraft,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ogn_aprs_parser::ICAOAddress;
+
+    use super::*;
+    use crate::test_utilities::create_dummy_aircraft_at_time;
+
+    #[test]
+    fn given_airspace_with_histories_when_snapshot_taken_then_latest_aircraft_per_address_is_kept()
+    {
+        let now = chrono::Utc::now();
+        let first = ICAOAddress::new(1).unwrap();
+        let second = ICAOAddress::new(2).unwrap();
+        let mut airspace = Airspace::new(chrono::TimeDelta::seconds(60));
+        airspace.update(vec![
+            create_dummy_aircraft_at_time(now - chrono::TimeDelta::seconds(2), second),
+            create_dummy_aircraft_at_time(now - chrono::TimeDelta::seconds(1), first),
+            create_dummy_aircraft_at_time(now, first),
+        ]);
+
+        let snapshot = AirspaceSnapshot::from(&airspace);
+
+        assert_eq!(snapshot.datetime, now);
+        assert_eq!(
+            snapshot.aircraft,
+            [
+                create_dummy_aircraft_at_time(now, first),
+                create_dummy_aircraft_at_time(now - chrono::TimeDelta::seconds(2), second),
+            ]
+        );
+    }
+}
@@ -1,23 +1,77 @@
 use crate::core::airspace::detail::Airspace;
+use crate::core::airspace::{AirspaceSnapshot, PbAirspaceSnapshot};
+use crate::core::central_disk_logger::{AnyFormatLoggerHandle, LogSender};
+use crate::core::channel::ChannelReceiver;
 use crate::core::parser::Aircraft;
-use crate::core::thread_manager::{SteppableTask, TaskState};
+use crate::core::thread_manager::{SteppableTask, TaskState, Wakeup};
 
 pub struct AirspaceStore {
     inner: std::sync::Arc<std::sync::RwLock<Airspace>>,
-    aircraft_receiver: crossbeam_channel::Receiver<Aircraft>,
+    aircraft_receiver: ChannelReceiver<Aircraft>,
+    /// Most aircraft taken from the channel per step; everything pending when `None`.
+    batch_budget: Option<usize>,
+    snapshot_recorder: Option<SnapshotRecorder>,
+}
+
+/// Records an [`AirspaceSnapshot`] whenever the airspace time has advanced by `interval`.
+struct SnapshotRecorder {
+    logger: AnyFormatLoggerHandle<PbAirspaceSnapshot, AirspaceSnapshot>,
+    interval: chrono::TimeDelta,
+    last_snapshot: Option<chrono::DateTime<chrono::Utc>>,
+}
+impl SnapshotRecorder {
+    fn record_if_due(&mut self, airspace: &Airspace) {
+        let datetime = airspace.get_datetime();
+        if self
+            .last_snapshot
+            .is_some_and(|last_snapshot| datetime - last_snapshot < self.interval)
+        {
+            return;
+        }
+        self.last_snapshot = Some(datetime);
+        if let Err(err) = self.logger.send(AirspaceSnapshot::from(airspace)) {
+            log::warn!("Failed to record airspace snapshot: {err}");
+        }
+    }
 }
 impl AirspaceStore {
     #[must_use]
     pub fn new(
-        aircraft_receiver: crossbeam_channel::Receiver<Aircraft>,
+        aircraft_receiver: impl Into<ChannelReceiver<Aircraft>>,
         airspace_time_buffer: chrono::TimeDelta,
     ) -> Self {
         let empty_airspace = Airspace::new(airspace_time_buffer);
         AirspaceStore {
             inner: std::sync::Arc::new(std::sync::RwLock::new(empty_airspace)),
-            aircraft_receiver,
+            aircraft_receiver: aircraft_receiver.into(),
+            batch_budget: None,
+            snapshot_recorder: None,
         }
     }
+
+    /// Caps how many aircraft one step applies, bounding how long the write lock is held
+    /// when a burst arrives. Without a budget every pending aircraft is applied at once.
+    #[must_use]
+    pub fn with_batch_budget(mut self, batch_budget: usize) -> Self {
+        self.batch_budget = Some(batch_budget.max(1));
+        self
+    }
+
+    /// Records a snapshot of the airspace to `logger` every `interval` of airspace time,
+    /// which follows the aircraft timestamps rather than the wall clock.
+    #[must_use]
+    pub fn with_snapshot_logger(
+        mut self,
+        logger: AnyFormatLoggerHandle<PbAirspaceSnapshot, AirspaceSnapshot>,
+        interval: chrono::TimeDelta,
+    ) -> Self {
+        self.snapshot_recorder = Some(SnapshotRecorder {
+            logger,
+            interval,
+            last_snapshot: None,
+        });
+        self
+    }
     #[must_use]
     pub fn get_airspace_viewer(&self) -> AirspaceViewer {
         AirspaceViewer {
@@ -29,25 +83,41 @@ impl AirspaceStore {
 impl SteppableTask for AirspaceStore {
     fn step(&mut self) -> TaskState {
         let mut aircrafts = Vec::new();
-
-        match self.aircraft_receiver.try_recv() {
-            Ok(aircraft) => {
-                aircrafts.push(aircraft);
+        let mut disconnected = false;
+
+        while self
+            .batch_budget
+            .is_none_or(|batch_budget| aircrafts.len() < batch_budget)
+        {
+            match self.aircraft_receiver.try_recv() {
+                Ok(aircraft) => aircrafts.push(aircraft),
+                Err(crossbeam_channel::TryRecvError::Empty) => break,
+                Err(crossbeam_channel::TryRecvError::Disconnected) => {
+                    disconnected = true;
+                    break;
+                }
             }
-            Err(crossbeam_channel::TryRecvError::Empty) => {
-                return TaskState::Running;
-            }
-            Err(crossbeam_channel::TryRecvError::Disconnected) => {
+        }
+        if aircrafts.is_empty() {
+            if disconnected {
                 log::error!("AirspaceStore upstream disconnected");
                 return TaskState::Completed;
             }
+            return TaskState::Running;
         }
 
         if let Ok(mut airspace) = self.inner.write() {
             airspace.update(aircrafts);
+            if let Some(snapshot_recorder) = &mut self.snapshot_recorder {
+                snapshot_recorder.record_if_due(&airspace);
+            }
         }
         TaskState::Running
     }
+
+    fn wakeup(&self) -> Option<Wakeup<'_>> {
+        Some(Wakeup::on(&self.aircraft_receiver))
+    }
 }
 #[derive(Clone)]
 pub struct AirspaceViewer {
@@ -65,6 +135,7 @@ mod tests {
     use ogn_aprs_parser::ICAOAddress;
 
     use super::*;
+    use crate::core::airspace::TrackKey;
     use crate::test_utilities::create_dummy_aircraft_at_time;
 
     fn setup_store() -> (crossbeam_channel::Sender<Aircraft>, AirspaceStore) {
@@ -108,4 +179,72 @@ mod tests {
         // when queue is empty, and channel is disconnected, next step() should error
         assert!(matches!(store.step(), TaskState::Completed));
     }
+
+    #[rstest::rstest]
+    #[case::unbounded(None, &[5])]
+    #[case::budget_of_two(Some(2), &[2, 4, 5])]
+    fn given_pending_aircraft_when_stepping_then_batches_are_drained_within_budget(
+        #[case] batch_budget: Option<usize>,
+        #[case] expected_histories: &[usize],
+    ) {
+        let (sender, mut store) = setup_store();
+        if let Some(batch_budget) = batch_budget {
+            store = store.with_batch_budget(batch_budget);
+        }
+        let start = chrono::Utc::now();
+        let address = ICAOAddress::new(0).unwrap();
+        let key = TrackKey::from(&create_dummy_aircraft_at_time(start, address));
+        for seconds in 0..5 {
+            sender
+                .send(create_dummy_aircraft_at_time(
+                    start + chrono::TimeDelta::seconds(seconds),
+                    address,
+                ))
+                .unwrap();
+        }
+        let viewer = store.get_airspace_viewer();
+
+        let history_lengths: Vec<_> = expected_histories
+            .iter()
+            .map(|_| {
+                store.step();
+                viewer.read().get_history(&key).unwrap().len()
+            })
+            .collect();
+
+        assert_eq!(history_lengths, expected_histories);
+    }
+
+    #[test]
+    fn given_snapshot_logger_when_airspace_time_advances_by_interval_then_snapshots_are_recorded() {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let (log_sender, log_receiver) = crossbeam_channel::unbounded();
+        let mut store = AirspaceStore::new(receiver, chrono::TimeDelta::seconds(60))
+            .with_snapshot_logger(
+                AnyFormatLoggerHandle::Jsonl(
+                    crate::core::central_disk_logger::JsonlLoggerHandle::new(0, log_sender),
+                ),
+                chrono::TimeDelta::seconds(10),
+            );
+        let start = chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let address = ICAOAddress::new(0).unwrap();
+
+        for seconds in [0, 5, 10] {
+            sender
+                .send(create_dummy_aircraft_at_time(
+                    start + chrono::TimeDelta::seconds(seconds),
+                    address,
+                ))
+                .unwrap();
+            store.step();
+        }
+
+        let snapshots: Vec<AirspaceSnapshot> = log_receiver
+            .try_iter()
+            .map(|message| serde_json::from_slice(&message.payload).unwrap())
+            .collect();
+        let datetimes: Vec<_> = snapshots.iter().map(|snapshot| snapshot.datetime).collect();
+        assert_eq!(datetimes, [start, start + chrono::TimeDelta::seconds(10)]);
+        assert_eq!(snapshots[1].aircraft.len(), 1);
+    }
 }
@@ -0,0 +1,267 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+const GZIP_EXTENSION: &str = "gz";
+const ZSTD_EXTENSION: &str = "zst";
+
+/// Compression applied to a log file, identified by a trailing `.gz` or `.zst` extension.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Compression {
+    #[default]
+    None,
+    Gzip,
+    Zstd,
+}
+impl Compression {
+    #[must_use]
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some(GZIP_EXTENSION) => Compression::Gzip,
+            Some(ZSTD_EXTENSION) => Compression::Zstd,
+            _ => Compression::None,
+        }
+    }
+
+    #[must_use]
+    pub fn extension(self) -> Option<&'static str> {
+        match self {
+            Compression::None => None,
+            Compression::Gzip => Some(GZIP_EXTENSION),
+            Compression::Zstd => Some(ZSTD_EXTENSION),
+        }
+    }
+
+    /// Appends this compression's extension to `path` unless it already ends with it,
+    /// e.g. `ingestor.pb` becomes `ingestor.pb.zst`.
+    #[must_use]
+    pub fn apply_to(self, path: PathBuf) -> PathBuf {
+        match self.extension() {
+            Some(extension) if Compression::from_path(&path) != self => {
+                let mut path = path.into_os_string();
+                path.push(".");
+                path.push(extension);
+                PathBuf::from(path)
+            }
+            _ => path,
+        }
+    }
+
+    /// The path with this compression's extension removed, e.g. `ingestor.pb`.
+    #[must_use]
+    pub fn uncompressed_path(path: &Path) -> PathBuf {
+        match Compression::from_path(path) {
+            Compression::None => path.to_path_buf(),
+            _ => path.with_extension(""),
+        }
+    }
+}
+
+enum Encoder {
+    Plain(BufWriter<File>),
+    Gzip(flate2::write::GzEncoder<BufWriter<File>>),
+    Zstd(zstd::stream::write::Encoder<'static, BufWriter<File>>),
+}
+impl Encoder {
+    fn start(compression: Compression, writer: BufWriter<File>) -> Result<Self, std::io::Error> {
+        Ok(match compression {
+            Compression::None => Encoder::Plain(writer),
+            Compression::Gzip => Encoder::Gzip(flate2::write::GzEncoder::new(
+                writer,
+                flate2::Compression::default(),
+            )),
+            Compression::Zstd => Encoder::Zstd(zstd::stream::write::Encoder::new(writer, 0)?),
+        })
+    }
+
+    fn finish(self) -> Result<BufWriter<File>, std::io::Error> {
+        match self {
+            Encoder::Plain(writer) => Ok(writer),
+            Encoder::Gzip(encoder) => encoder.finish(),
+            Encoder::Zstd(encoder) => encoder.finish(),
+        }
+    }
+
+    fn file(&self) -> &File {
+        match self {
+            Encoder::Plain(writer) => writer.get_ref(),
+            Encoder::Gzip(encoder) => encoder.get_ref().get_ref(),
+            Encoder::Zstd(encoder) => encoder.get_ref().get_ref(),
+        }
+    }
+
+    fn writer(&mut self) -> &mut dyn Write {
+        match self {
+            Encoder::Plain(writer) => writer,
+            Encoder::Gzip(encoder) => encoder,
+            Encoder::Zstd(encoder) => encoder,
+        }
+    }
+}
+
+/// Writes a log file, optionally as a sequence of independently decodable gzip members
+/// or zstd frames. Readers handle concatenated frames, so closing a frame and starting
+/// a new one keeps everything written so far readable after a crash.
+pub struct LogWriter {
+    compression: Compression,
+    encoder: Option<Encoder>,
+    unfinished_frame_since: Option<std::time::Instant>,
+}
+impl LogWriter {
+    pub fn new(file: File, compression: Compression) -> Result<Self, std::io::Error> {
+        Ok(Self {
+            compression,
+            encoder: Some(Encoder::start(compression, BufWriter::new(file))?),
+            unfinished_frame_since: None,
+        })
+    }
+
+    #[must_use]
+    pub fn compression(&self) -> Compression {
+        self.compression
+    }
+
+    /// When data was first written to the current frame, if it holds any.
+    #[must_use]
+    pub fn unfinished_frame_since(&self) -> Option<std::time::Instant> {
+        self.unfinished_frame_since
+    }
+
+    /// Closes the current frame if it has held data for at least `interval`. Returns
+    /// whether it did.
+    pub fn finish_frame_if_due(
+        &mut self,
+        interval: std::time::Duration,
+    ) -> Result<bool, std::io::Error> {
+        let due = self
+            .unfinished_frame_since
+            .is_some_and(|since| since.elapsed() >= interval);
+        if due {
+            self.finish_frame()?;
+        }
+        Ok(due)
+    }
+
+    /// Closes the current frame and flushes it to the file. Uncompressed logs are just
+    /// flushed.
+    pub fn finish_frame(&mut self) -> Result<(), std::io::Error> {
+        self.unfinished_frame_since = None;
+        if let Some(Encoder::Plain(writer)) = &mut self.encoder {
+            return writer.flush();
+        }
+        let mut writer = self.encoder.take().ok_or_else(closed_error)?.finish()?;
+        writer.flush()?;
+        self.encoder = Some(Encoder::start(self.compression, writer)?);
+        Ok(())
+    }
+
+    /// Closes the current frame and waits until the file is durably on disk.
+    pub fn sync_all(&mut self) -> Result<(), std::io::Error> {
+        self.finish_frame()?;
+        self.encoder
+            .as_ref()
+            .ok_or_else(closed_error)?
+            .file()
+            .sync_all()
+    }
+}
+impl Write for LogWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self
+            .encoder
+            .as_mut()
+            .ok_or_else(closed_error)?
+            .writer()
+            .write(buf)?;
+        self.unfinished_frame_since
+            .get_or_insert_with(std::time::Instant::now);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.encoder
+            .as_mut()
+            .ok_or_else(closed_error)?
+            .writer()
+            .flush()
+    }
+}
+impl Drop for LogWriter {
+    fn drop(&mut self) {
+        if let Some(encoder) = self.encoder.take() {
+            let _ = encoder.finish().and_then(|mut writer| writer.flush());
+        }
+    }
+}
+impl std::fmt::Debug for LogWriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LogWriter")
+            .field("compression", &self.compression)
+            .field("unfinished_frame_since", &self.unfinished_frame_since)
+            .finish_non_exhaustive()
+    }
+}
+
+fn closed_error() -> std::io::Error {
+    std::io::Error::other("log writer was closed after a failed frame")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+    use crate::test_utilities::{TestPath, test_path};
+
+    fn decompress(path: &Path) -> Vec<u8> {
+        let file = File::open(path).unwrap();
+        let mut bytes = Vec::new();
+        match Compression::from_path(path) {
+            Compression::None => Box::new(file) as Box<dyn Read>,
+            Compression::Gzip => Box::new(flate2::read::MultiGzDecoder::new(file)),
+            Compression::Zstd => Box::new(zstd::stream::read::Decoder::new(file).unwrap()),
+        }
+        .read_to_end(&mut bytes)
+        .unwrap();
+        bytes
+    }
+
+    #[rstest::rstest]
+    #[case("log.pb", Compression::None)]
+    #[case("log.pb.gz", Compression::Gzip)]
+    #[case("log.pb.zst", Compression::Zstd)]
+    fn given_path_when_compression_detected_then_matches_extension(
+        #[case] path: &str,
+        #[case] expected: Compression,
+    ) {
+        assert_eq!(Compression::from_path(Path::new(path)), expected);
+        assert_eq!(
+            Compression::uncompressed_path(Path::new(path)),
+            Path::new("log.pb")
+        );
+        assert_eq!(expected.apply_to(PathBuf::from("log.pb")), Path::new(path));
+    }
+
+    #[rstest::rstest]
+    #[case("frames.pb.gz")]
+    #[case("frames.pb.zst")]
+    fn given_compressed_writer_when_frames_finished_between_writes_then_all_data_decodes(
+        test_path: TestPath,
+        #[case] file_name: &str,
+    ) {
+        let path = test_path.path.join(file_name);
+        let mut writer =
+            LogWriter::new(File::create(&path).unwrap(), Compression::from_path(&path)).unwrap();
+
+        writer.write_all(b"first frame ").unwrap();
+        writer.finish_frame().unwrap();
+        // A finished frame is readable before the writer is closed.
+        assert_eq!(decompress(&path), b"first frame ");
+
+        writer.write_all(b"second frame").unwrap();
+        drop(writer);
+
+        assert_eq!(decompress(&path), b"first frame second frame");
+    }
+}
@@ -0,0 +1,235 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Identifies a time index sidecar file and its format version.
+const TIME_INDEX_MAGIC: &[u8; 8] = b"FLTIDX01";
+const TIME_INDEX_ENTRY_LENGTH: usize = 16;
+
+pub const TIME_INDEX_EXTENSION: &str = "idx";
+pub const DEFAULT_TIME_INDEX_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Path of the time index sidecar for `recording`, e.g. `ingestor.pb.idx`.
+#[must_use]
+pub fn time_index_path(recording: &Path) -> PathBuf {
+    let mut path = recording.as_os_str().to_owned();
+    path.push(".");
+    path.push(TIME_INDEX_EXTENSION);
+    PathBuf::from(path)
+}
+
+/// Maps a time to the byte offset of a record in a recording. Every record stored before
+/// `offset` has a timestamp no later than `timestamp`, so replay can start reading at
+/// `offset` without missing any record from after `timestamp`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeIndexEntry {
+    pub timestamp: std::time::SystemTime,
+    pub offset: u64,
+}
+impl TimeIndexEntry {
+    fn to_bytes(self) -> [u8; TIME_INDEX_ENTRY_LENGTH] {
+        let since_epoch = self
+            .timestamp
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default();
+        // Rounded up, so that the stored time is never earlier than the records before `offset`.
+        let millis = since_epoch.as_millis()
+            + u128::from(!since_epoch.subsec_nanos().is_multiple_of(1_000_000));
+        let mut bytes = [0u8; TIME_INDEX_ENTRY_LENGTH];
+        bytes[..8].copy_from_slice(&u64::try_from(millis).unwrap_or(u64::MAX).to_le_bytes());
+        bytes[8..].copy_from_slice(&self.offset.to_le_bytes());
+        bytes
+    }
+
+    fn from_bytes(bytes: [u8; TIME_INDEX_ENTRY_LENGTH]) -> Self {
+        let (millis, offset) = bytes.split_at(8);
+        let millis = u64::from_le_bytes(millis.try_into().unwrap_or_default());
+        Self {
+            timestamp: std::time::UNIX_EPOCH + std::time::Duration::from_millis(millis),
+            offset: u64::from_le_bytes(offset.try_into().unwrap_or_default()),
+        }
+    }
+}
+
+/// Appends entries to a time index sidecar, at most one per `interval`.
+#[derive(Debug)]
+pub struct TimeIndexWriter {
+    writer: BufWriter<File>,
+    interval: std::time::Duration,
+    last_entry: Option<std::time::SystemTime>,
+}
+impl TimeIndexWriter {
+    /// Creates (or replaces) the index file at `path`.
+    pub fn create(path: &Path, interval: std::time::Duration) -> Result<Self, std::io::Error> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(TIME_INDEX_MAGIC)?;
+        Ok(Self {
+            writer,
+            interval,
+            last_entry: None,
+        })
+    }
+
+    /// Records `offset` for `timestamp` if `interval` has passed since the last entry.
+    /// Returns whether an entry was written.
+    pub fn record(
+        &mut self,
+        timestamp: std::time::SystemTime,
+        offset: u64,
+    ) -> Result<bool, std::io::Error> {
+        let due = self.last_entry.is_none_or(|last_entry| {
+            timestamp
+                .duration_since(last_entry)
+                .is_ok_and(|elapsed| elapsed >= self.interval)
+        });
+        if !due {
+            return Ok(false);
+        }
+        self.writer
+            .write_all(&TimeIndexEntry { timestamp, offset }.to_bytes())?;
+        self.last_entry = Some(timestamp);
+        Ok(true)
+    }
+
+    #[must_use]
+    pub fn interval(&self) -> std::time::Duration {
+        self.interval
+    }
+
+    pub fn flush(&mut self) -> Result<(), std::io::Error> {
+        self.writer.flush()
+    }
+
+    pub fn sync_all(&mut self) -> Result<(), std::io::Error> {
+        self.writer.flush()?;
+        self.writer.get_ref().sync_all()
+    }
+}
+
+/// A time index sidecar loaded into memory for O(log n) lookups.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TimeIndex {
+    entries: Vec<TimeIndexEntry>,
+}
+impl TimeIndex {
+    pub fn load(path: &Path) -> Result<Self, std::io::Error> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut magic = [0u8; TIME_INDEX_MAGIC.len()];
+        reader.read_exact(&mut magic)?;
+        if &magic != TIME_INDEX_MAGIC {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("{} is not a time index file", path.display()),
+            ));
+        }
+
+        let mut entries = Vec::new();
+        let mut bytes = [0u8; TIME_INDEX_ENTRY_LENGTH];
+        // A partially written trailing entry is ignored.
+        while reader.read_exact(&mut bytes).is_ok() {
+            entries.push(TimeIndexEntry::from_bytes(bytes));
+        }
+        Ok(Self { entries })
+    }
+
+    #[must_use]
+    pub fn entries(&self) -> &[TimeIndexEntry] {
+        &self.entries
+    }
+
+    /// Drops entries pointing past `length`, e.g. when the index was flushed to disk
+    /// before the recording it describes.
+    #[must_use]
+    pub fn within(mut self, length: u64) -> Self {
+        self.entries.retain(|entry| entry.offset <= length);
+        self
+    }
+
+    /// Byte offset to start reading from to find the first record at or after `target`.
+    #[must_use]
+    pub fn offset_before(&self, target: std::time::SystemTime) -> u64 {
+        let index = self
+            .entries
+            .partition_point(|entry| entry.timestamp < target);
+        index
+            .checked_sub(1)
+            .map_or(0, |index| self.entries[index].offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utilities::{TestPath, test_path};
+
+    fn at(seconds: u64) -> std::time::SystemTime {
+        std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000 + seconds)
+    }
+
+    #[rstest::rstest]
+    fn given_entries_recorded_faster_than_interval_when_loaded_then_only_one_per_interval_is_kept(
+        test_path: TestPath,
+    ) {
+        let path = time_index_path(&test_path.path.join("ingestor.pb"));
+        let mut writer =
+            TimeIndexWriter::create(&path, std::time::Duration::from_secs(10)).unwrap();
+        for (seconds, offset) in [(0, 0), (5, 100), (10, 200), (19, 300), (20, 400)] {
+            writer.record(at(seconds), offset).unwrap();
+        }
+        drop(writer);
+
+        let index = TimeIndex::load(&path).unwrap();
+
+        assert_eq!(
+            index.entries(),
+            [
+                TimeIndexEntry {
+                    timestamp: at(0),
+                    offset: 0
+                },
+                TimeIndexEntry {
+                    timestamp: at(10),
+                    offset: 200
+                },
+                TimeIndexEntry {
+                    timestamp: at(20),
+                    offset: 400
+                },
+            ]
+        );
+        assert_eq!(path.file_name().unwrap(), "ingestor.pb.idx");
+    }
+
+    #[rstest::rstest]
+    #[case(at(0), 0)]
+    #[case(at(10), 0)]
+    #[case(at(11), 200)]
+    #[case(at(25), 400)]
+    fn given_index_when_looking_up_target_then_returns_offset_of_last_entry_strictly_before_target(
+        test_path: TestPath,
+        #[case] target: std::time::SystemTime,
+        #[case] expected: u64,
+    ) {
+        let path = test_path.path.join("lookup.pb.idx");
+        let mut writer = TimeIndexWriter::create(&path, std::time::Duration::ZERO).unwrap();
+        for (seconds, offset) in [(0, 0), (10, 200), (20, 400)] {
+            writer.record(at(seconds), offset).unwrap();
+        }
+        drop(writer);
+
+        assert_eq!(
+            TimeIndex::load(&path).unwrap().offset_before(target),
+            expected
+        );
+    }
+
+    #[rstest::rstest]
+    fn given_file_without_magic_when_loaded_then_returns_invalid_data(test_path: TestPath) {
+        let path = test_path.path.join("bogus.pb.idx");
+        std::fs::write(&path, b"not an index").unwrap();
+
+        let error = TimeIndex::load(&path).unwrap_err();
+
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+}
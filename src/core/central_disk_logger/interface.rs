@@ -1,11 +1,18 @@
 use std::collections::HashMap;
 use std::fs::File;
-use std::io::BufWriter;
 use std::marker::PhantomData;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+use crate::core::central_disk_logger::compression::{Compression, LogWriter};
 use crate::core::central_disk_logger::errors;
-use crate::core::central_disk_logger::task::CentralDiskLogger;
+use crate::core::central_disk_logger::index::{
+    DEFAULT_TIME_INDEX_INTERVAL, TimeIndexWriter, time_index_path,
+};
+use crate::core::central_disk_logger::rotation::{
+    RotationPolicy, SegmentRotation, SegmentTemplate,
+};
+use crate::core::central_disk_logger::task::{CentralDiskLogger, LogFile};
+use crate::core::channel::{self, ChannelConfig, ChannelMonitor, ChannelReceiver, ChannelSender};
 use crate::ext::TryInsertExt;
 
 pub type LoggerTaskID = u8;
@@ -21,10 +28,21 @@ pub struct DiskLoggerMessage {
 #[derive(Debug)]
 pub struct LoggerHandle<F, M: ?Sized> {
     logger_id: LoggerTaskID,
-    sender: crossbeam_channel::Sender<DiskLoggerMessage>,
+    sender: ChannelSender<DiskLoggerMessage>,
     _marker: PhantomData<(F, M)>,
 }
 
+// Implemented by hand because deriving would require `F: Clone` and `M: Clone`.
+impl<F, M> Clone for LoggerHandle<F, M> {
+    fn clone(&self) -> Self {
+        Self {
+            logger_id: self.logger_id,
+            sender: self.sender.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
 pub trait LogSender<Input> {
     type Error;
     fn send(&self, message: Input) -> Result<(), Self::Error>;
@@ -33,11 +51,11 @@ pub trait LogSender<Input> {
 impl<F, M> LoggerHandle<F, M> {
     pub fn new(
         logger_id: LoggerTaskID,
-        sender: crossbeam_channel::Sender<DiskLoggerMessage>,
+        sender: impl Into<ChannelSender<DiskLoggerMessage>>,
     ) -> Self {
         Self {
             logger_id,
-            sender,
+            sender: sender.into(),
             _marker: PhantomData,
         }
     }
@@ -96,42 +114,155 @@ where
     }
 }
 
+/// A handle to a log whose format is picked from its path at runtime: protobuf messages
+/// `P` for `.pb` files, or JSON lines of `J` for `.jsonl` files.
+pub enum AnyFormatLoggerHandle<P, J> {
+    Proto(ProtoLoggerHandle<P>),
+    Jsonl(JsonlLoggerHandle<J>),
+}
+
+impl<P, J> Clone for AnyFormatLoggerHandle<P, J> {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Proto(handle) => Self::Proto(handle.clone()),
+            Self::Jsonl(handle) => Self::Jsonl(handle.clone()),
+        }
+    }
+}
+
+impl<T, P, J> LogSender<T> for AnyFormatLoggerHandle<P, J>
+where
+    T: Into<P> + Into<J>,
+    P: prost::Message,
+    J: serde::Serialize,
+{
+    type Error = errors::LoggingError<std::convert::Infallible>;
+
+    fn send(&self, message: T) -> Result<(), Self::Error> {
+        match self {
+            Self::Proto(handle) => handle.send(message),
+            Self::Jsonl(handle) => handle.send(message),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct DiskLoggerRegistry {
     current_logger_id: LoggerTaskID,
-    sender: crossbeam_channel::Sender<DiskLoggerMessage>,
-    receiver: crossbeam_channel::Receiver<DiskLoggerMessage>,
-    task_to_path_mapping: HashMap<LoggerTaskID, (PathBuf, BufWriter<File>)>,
+    sender: ChannelSender<DiskLoggerMessage>,
+    receiver: ChannelReceiver<DiskLoggerMessage>,
+    task_to_path_mapping: HashMap<LoggerTaskID, LogFile>,
+    time_index_interval: std::time::Duration,
 }
 impl DiskLoggerRegistry {
     pub fn new() -> Self {
-        let (sender, receiver) = crossbeam_channel::unbounded();
+        Self::with_channel(ChannelConfig::default())
+    }
+
+    /// A registry whose loggers share a channel with the given capacity and overflow policy.
+    #[must_use]
+    pub fn with_channel(config: ChannelConfig) -> Self {
+        let (sender, receiver) = channel::channel("disk_logger", config);
         Self {
             current_logger_id: 0,
             sender,
             receiver,
             task_to_path_mapping: HashMap::new(),
+            time_index_interval: DEFAULT_TIME_INDEX_INTERVAL,
         }
     }
 
+    /// Sets how often protobuf logs add an entry to their time index sidecar.
+    #[must_use]
+    pub fn with_time_index_interval(mut self, interval: std::time::Duration) -> Self {
+        self.time_index_interval = interval;
+        self
+    }
+
     pub fn register_proto<M>(
         &mut self,
         path: PathBuf,
     ) -> Result<LoggerHandle<ProtoFormat, M>, errors::DiskloggerRegistryError> {
-        if path.extension().is_none_or(|ext| ext != PROTO_FILE_FORMAT) {
+        self.register_proto_with_rotation(path, RotationPolicy::default())
+    }
+
+    /// Registers a protobuf log that rolls over to new files according to `rotation`.
+    /// Unless `rotation` is disabled, `path` is a [`SegmentTemplate`].
+    pub fn register_proto_with_rotation<M>(
+        &mut self,
+        path: PathBuf,
+        rotation: RotationPolicy,
+    ) -> Result<LoggerHandle<ProtoFormat, M>, errors::DiskloggerRegistryError> {
+        if !has_extension(&path, PROTO_FILE_FORMAT) {
             return Err(errors::DiskloggerRegistryError::InvalidPath(path));
         }
-        self.register::<ProtoFormat, M>(path)
+        let compression = Compression::from_path(&path);
+        let handle = self.register_with_rotation::<ProtoFormat, M>(path, rotation)?;
+        // Offsets into a compressed stream cannot be seeked to, so only plain logs are indexed.
+        if compression != Compression::None {
+            return Ok(handle);
+        }
+        let log_file = self
+            .task_to_path_mapping
+            .remove(&handle.logger_id)
+            .expect("log file was registered above");
+        let index_path = time_index_path(log_file.path());
+        let time_index =
+            TimeIndexWriter::create(&index_path, self.time_index_interval).map_err(|err| {
+                errors::DiskloggerRegistryError::LogFileCreationError {
+                    path: index_path,
+                    source: err,
+                }
+            })?;
+        self.task_to_path_mapping
+            .insert(handle.logger_id, log_file.with_time_index(time_index));
+        Ok(handle)
     }
 
     pub fn register_jsonl<M>(
         &mut self,
         path: PathBuf,
     ) -> Result<LoggerHandle<JsonlFormat, M>, errors::DiskloggerRegistryError> {
-        if path.extension().is_none_or(|ext| ext != JSONL_FILE_FORMAT) {
+        self.register_jsonl_with_rotation(path, RotationPolicy::default())
+    }
+
+    /// Registers a JSON lines log that rolls over to new files according to `rotation`.
+    /// Unless `rotation` is disabled, `path` is a [`SegmentTemplate`].
+    pub fn register_jsonl_with_rotation<M>(
+        &mut self,
+        path: PathBuf,
+        rotation: RotationPolicy,
+    ) -> Result<LoggerHandle<JsonlFormat, M>, errors::DiskloggerRegistryError> {
+        if !has_extension(&path, JSONL_FILE_FORMAT) {
             return Err(errors::DiskloggerRegistryError::InvalidPath(path));
         }
-        self.register::<JsonlFormat, M>(path)
+        self.register_with_rotation::<JsonlFormat, M>(path, rotation)
+    }
+
+    /// Registers a protobuf or JSON lines log, depending on the extension of `path`.
+    pub fn register_any_format<P, J>(
+        &mut self,
+        path: PathBuf,
+        rotation: RotationPolicy,
+    ) -> Result<AnyFormatLoggerHandle<P, J>, errors::DiskloggerRegistryError> {
+        if has_extension(&path, JSONL_FILE_FORMAT) {
+            self.register_jsonl_with_rotation(path, rotation)
+                .map(AnyFormatLoggerHandle::Jsonl)
+        } else {
+            self.register_proto_with_rotation(path, rotation)
+                .map(AnyFormatLoggerHandle::Proto)
+        }
+    }
+
+    #[must_use]
+    pub fn channel_monitor(&self) -> ChannelMonitor {
+        self.sender.monitor()
+    }
+
+    /// Whether no logger has been registered, in which case the built logger has nothing to do.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.task_to_path_mapping.is_empty()
     }
 
     pub fn build(self) -> CentralDiskLogger {
@@ -151,14 +282,62 @@ impl DiskLoggerRegistry {
                 });
             }
         };
-        let writer = BufWriter::new(file);
+        let log_file = Self::open_log_file(path, file)?;
+        self.insert(log_file)
+    }
 
+    fn register_with_rotation<F, M>(
+        &mut self,
+        path: PathBuf,
+        rotation: RotationPolicy,
+    ) -> Result<LoggerHandle<F, M>, errors::DiskloggerRegistryError> {
+        if !rotation.is_enabled() {
+            return self.register(path);
+        }
+        let Ok(template) = SegmentTemplate::new(&path) else {
+            return Err(errors::DiskloggerRegistryError::InvalidPath(path));
+        };
+        let mut rotation = SegmentRotation::new(rotation, template);
+        let (segment_path, file) = match rotation.start_segment(chrono::Utc::now()) {
+            Ok(segment) => segment,
+            Err(err) => {
+                return Err(errors::DiskloggerRegistryError::LogFileCreationError {
+                    path,
+                    source: err,
+                });
+            }
+        };
+        // Segments left behind by earlier runs count towards the retention.
+        if let Err(err) = rotation.prune(&segment_path) {
+            log::warn!(
+                "Unable to apply retention to log segments of {}: {err}",
+                path.display()
+            );
+        }
+        let log_file = Self::open_log_file(segment_path, file)?.with_rotation(rotation);
+        self.insert(log_file)
+    }
+
+    fn open_log_file(
+        path: PathBuf,
+        file: File,
+    ) -> Result<LogFile, errors::DiskloggerRegistryError> {
+        match LogWriter::new(file, Compression::from_path(&path)) {
+            Ok(writer) => Ok(LogFile::new(path, writer)),
+            Err(err) => {
+                Err(errors::DiskloggerRegistryError::LogFileCreationError { path, source: err })
+            }
+        }
+    }
+
+    fn insert<F, M>(
+        &mut self,
+        log_file: LogFile,
+    ) -> Result<LoggerHandle<F, M>, errors::DiskloggerRegistryError> {
         let logger_id = self.current_logger_id;
-        let _ = TryInsertExt::try_insert(&mut self.task_to_path_mapping, logger_id, (path, writer))
-            .map_err(|err| {
-                let (rejected_path, _rejected_writer) = err.value;
-                errors::DiskloggerRegistryError::PathAlreadyRegisteredError(rejected_path)
-            })?;
+        let path = log_file.path().to_path_buf();
+        let _ = TryInsertExt::try_insert(&mut self.task_to_path_mapping, logger_id, log_file)
+            .map_err(|_| errors::DiskloggerRegistryError::PathAlreadyRegisteredError(path))?;
 
         let handle = LoggerHandle {
             logger_id,
@@ -170,6 +349,13 @@ impl DiskLoggerRegistry {
         Ok(handle)
     }
 }
+/// Whether `path` has `extension`, optionally followed by a compression extension.
+fn has_extension(path: &Path, extension: &str) -> bool {
+    Compression::uncompressed_path(path)
+        .extension()
+        .is_some_and(|ext| ext == extension)
+}
+
 impl Default for DiskLoggerRegistry {
     fn default() -> Self {
         Self::new()
@@ -248,6 +434,30 @@ mod tests {
             assert!(file_path.exists());
         }
 
+        #[test]
+        fn given_rotation_when_registering_same_template_twice_then_each_gets_its_own_segment() {
+            let temp_dir = tempfile::tempdir().unwrap();
+            let template = temp_dir.path().join("restarted.pb");
+            let rotation = RotationPolicy {
+                retention: Some(5),
+                ..RotationPolicy::default()
+            };
+
+            let mut registry = DiskLoggerRegistry::new();
+            for _ in 0..2 {
+                registry
+                    .register_proto_with_rotation::<MockTaskProto>(template.clone(), rotation)
+                    .unwrap();
+            }
+
+            let segments = SegmentTemplate::new(&template)
+                .unwrap()
+                .existing_segments()
+                .unwrap();
+            assert_eq!(segments.len(), 2);
+            assert!(!template.exists());
+        }
+
         #[test]
         fn given_existing_file_when_creating_logger_then_returns_io_error() {
             let temp_dir = tempfile::tempdir().unwrap();
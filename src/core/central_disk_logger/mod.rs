@@ -1,12 +1,15 @@
+pub mod compression;
 pub mod errors;
+pub mod index;
 pub mod interface;
+pub mod rotation;
 pub mod task;
 
 #[cfg(test)]
 pub mod testing;
 
 pub use interface::{
-    DiskLoggerMessage, DiskLoggerRegistry, JsonlLoggerHandle, LogSender, LoggerTaskID,
-    ProtoLoggerHandle,
+    AnyFormatLoggerHandle, DiskLoggerMessage, DiskLoggerRegistry, JsonlLoggerHandle, LogSender,
+    LoggerTaskID, ProtoLoggerHandle,
 };
-pub use task::CentralDiskLogger;
+pub use task::{CentralDiskLogger, LogFile};
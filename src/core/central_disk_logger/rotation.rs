@@ -0,0 +1,416 @@
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+
+use crate::core::central_disk_logger::index::time_index_path;
+
+/// Inserted before the extensions of a rotated log path that has no timestamp of its own.
+const DEFAULT_TIMESTAMP_TEMPLATE: &str = "%Y%m%dT%H%M%SZ";
+
+/// Starts a new segment whenever the wall clock crosses an hour or UTC day boundary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RotationInterval {
+    Hourly,
+    Daily,
+}
+impl RotationInterval {
+    /// The first boundary strictly after `time`.
+    #[must_use]
+    pub fn next_boundary(self, time: DateTime<Utc>) -> DateTime<Utc> {
+        let period = match self {
+            RotationInterval::Hourly => 60 * 60,
+            RotationInterval::Daily => 24 * 60 * 60,
+        };
+        let seconds = (time.timestamp().div_euclid(period) + 1) * period;
+        DateTime::from_timestamp(seconds, 0).unwrap_or(DateTime::<Utc>::MAX_UTC)
+    }
+}
+
+/// When a registered log moves on to a new file, and how many of its files are kept.
+///
+/// With any field set, the registered path becomes a [`SegmentTemplate`], so that every
+/// segment (and every restart) gets a file of its own.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(default)]
+pub struct RotationPolicy {
+    pub interval: Option<RotationInterval>,
+    /// Rolls over before a write would take the segment past this many (uncompressed) bytes.
+    pub max_bytes: Option<u64>,
+    /// Deletes the oldest segments beyond this count. The current segment is always kept.
+    pub retention: Option<usize>,
+}
+impl RotationPolicy {
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        *self != Self::default()
+    }
+}
+
+/// Names the segments of a rotated log from a path whose file name holds chrono
+/// `strftime` specifiers, formatted in UTC, e.g. `ingestor-%Y%m%d-%H%M.pb`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SegmentTemplate {
+    directory: PathBuf,
+    file_name: String,
+}
+impl SegmentTemplate {
+    /// A file name without any specifier gets `-%Y%m%dT%H%M%SZ` inserted before its
+    /// extensions, e.g. `ingestor.pb` becomes `ingestor-20240101T120000Z.pb`.
+    pub fn new(path: &Path) -> Result<Self, std::io::Error> {
+        let file_name = path
+            .file_name()
+            .and_then(|file_name| file_name.to_str())
+            .ok_or_else(|| invalid_template(path))?;
+        let file_name = if file_name.contains('%') {
+            file_name.to_owned()
+        } else {
+            match file_name.split_once('.') {
+                Some((stem, extensions)) => {
+                    format!("{stem}-{DEFAULT_TIMESTAMP_TEMPLATE}.{extensions}")
+                }
+                None => format!("{file_name}-{DEFAULT_TIMESTAMP_TEMPLATE}"),
+            }
+        };
+        let template = Self {
+            directory: path.parent().map(Path::to_path_buf).unwrap_or_default(),
+            file_name,
+        };
+        template
+            .format(Utc::now())
+            .ok_or_else(|| invalid_template(path))?;
+        Ok(template)
+    }
+
+    fn format(&self, time: DateTime<Utc>) -> Option<String> {
+        use std::fmt::Write;
+        let mut file_name = String::new();
+        write!(file_name, "{}", time.format(&self.file_name)).ok()?;
+        Some(file_name)
+    }
+
+    /// The literal text before the first specifier and the extensions after the last one,
+    /// shared by every segment file name.
+    fn prefix_and_extensions(&self) -> (&str, &str) {
+        let first = self.file_name.find('%').unwrap_or(self.file_name.len());
+        let last = self
+            .file_name
+            .rfind('%')
+            .map_or(self.file_name.len(), |last| {
+                // Skips padding and width modifiers such as `%-d` or `%3f`.
+                self.file_name[last + 1..]
+                    .find(|c: char| c.is_ascii_alphabetic())
+                    .map_or(self.file_name.len(), |end| last + end + 2)
+            });
+        let extensions = self.file_name[last..]
+            .find('.')
+            .map_or(self.file_name.len(), |dot| last + dot);
+        (&self.file_name[..first], &self.file_name[extensions..])
+    }
+
+    /// Creates the segment file for `time`. A `-1`, `-2`, ... counter is added before the
+    /// extensions when the name is taken, e.g. after a restart or several size-based
+    /// rotations within the resolution of the template.
+    pub fn create_segment(&self, time: DateTime<Utc>) -> Result<(PathBuf, File), std::io::Error> {
+        let file_name = self
+            .format(time)
+            .ok_or_else(|| invalid_template(&self.directory.join(&self.file_name)))?;
+        let (_, extensions) = self.prefix_and_extensions();
+        let counter_at = file_name.len() - extensions.len();
+
+        for counter in 0u32.. {
+            let path = if counter == 0 {
+                self.directory.join(&file_name)
+            } else {
+                self.directory.join(format!(
+                    "{}-{counter}{}",
+                    &file_name[..counter_at],
+                    &file_name[counter_at..]
+                ))
+            };
+            match File::create_new(&path) {
+                Ok(file) => return Ok((path, file)),
+                Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => {}
+                Err(err) => return Err(err),
+            }
+        }
+        unreachable!("segment counter exhausted")
+    }
+
+    /// Whether `file_name` is one [`Self::create_segment`] could have made: the prefix, a
+    /// timestamp in the template's format, an optional counter and the extensions.
+    fn is_segment_name(&self, file_name: &str) -> bool {
+        let (prefix, extensions) = self.prefix_and_extensions();
+        let stamp_format = &self.file_name[prefix.len()..self.file_name.len() - extensions.len()];
+        let Some(stamp) = file_name
+            .strip_prefix(prefix)
+            .and_then(|rest| rest.strip_suffix(extensions))
+        else {
+            return false;
+        };
+        let is_stamp = |stamp: &str| {
+            chrono::format::parse(
+                &mut chrono::format::Parsed::new(),
+                stamp,
+                chrono::format::StrftimeItems::new(stamp_format),
+            )
+            .is_ok()
+        };
+        is_stamp(stamp)
+            || stamp.rsplit_once('-').is_some_and(|(stamp, counter)| {
+                !counter.is_empty()
+                    && counter.bytes().all(|b| b.is_ascii_digit())
+                    && is_stamp(stamp)
+            })
+    }
+
+    /// Segment files on disk, oldest first. Other files sharing the log's prefix and
+    /// extensions, such as `log-backup.pb` next to `log-20240301.pb`, are left out.
+    pub fn existing_segments(&self) -> Result<Vec<PathBuf>, std::io::Error> {
+        let directory = if self.directory.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            &self.directory
+        };
+
+        let mut segments = Vec::new();
+        for entry in std::fs::read_dir(directory)? {
+            let entry = entry?;
+            let Some(file_name) = entry.file_name().to_str().map(str::to_owned) else {
+                continue;
+            };
+            if self.is_segment_name(&file_name) && entry.file_type()?.is_file() {
+                let modified = entry.metadata()?.modified()?;
+                segments.push((modified, file_name.len(), self.directory.join(file_name)));
+            }
+        }
+        // Segments written within the timestamp resolution of the file system are ordered
+        // by name, where a counter makes a name longer than the one it follows.
+        segments.sort();
+        Ok(segments.into_iter().map(|(_, _, path)| path).collect())
+    }
+}
+
+fn invalid_template(path: &Path) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("{} is not a valid log file name template", path.display()),
+    )
+}
+
+/// Rolls a log over to new segments according to its [`RotationPolicy`].
+#[derive(Debug)]
+pub struct SegmentRotation {
+    policy: RotationPolicy,
+    template: SegmentTemplate,
+    next_rotation: Option<DateTime<Utc>>,
+}
+impl SegmentRotation {
+    #[must_use]
+    pub fn new(policy: RotationPolicy, template: SegmentTemplate) -> Self {
+        Self {
+            policy,
+            template,
+            next_rotation: None,
+        }
+    }
+
+    /// Whether writing `payload_length` more bytes at `now` should go to a new segment.
+    #[must_use]
+    pub fn is_due(&self, segment_length: u64, payload_length: usize, now: DateTime<Utc>) -> bool {
+        self.next_rotation
+            .is_some_and(|next_rotation| now >= next_rotation)
+            || self.policy.max_bytes.is_some_and(|max_bytes| {
+                segment_length > 0 && segment_length + payload_length as u64 > max_bytes
+            })
+    }
+
+    /// Creates the segment starting at `now` and schedules the next time-based rotation.
+    pub fn start_segment(&mut self, now: DateTime<Utc>) -> Result<(PathBuf, File), std::io::Error> {
+        let segment = self.template.create_segment(now)?;
+        self.next_rotation = self
+            .policy
+            .interval
+            .map(|interval| interval.next_boundary(now));
+        Ok(segment)
+    }
+
+    /// Deletes the oldest segments, and their time index sidecars, beyond the retention
+    /// count. Returns the deleted segments.
+    pub fn prune(&self, current: &Path) -> Result<Vec<PathBuf>, std::io::Error> {
+        let Some(retention) = self.policy.retention else {
+            return Ok(Vec::new());
+        };
+        let segments = self.template.existing_segments()?;
+        let excess = segments.len().saturating_sub(retention.max(1));
+
+        let mut deleted = Vec::new();
+        for segment in segments.into_iter().filter(|segment| segment != current) {
+            if deleted.len() == excess {
+                break;
+            }
+            std::fs::remove_file(&segment)?;
+            match std::fs::remove_file(time_index_path(&segment)) {
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => return Err(err),
+                _ => {}
+            }
+            deleted.push(segment);
+        }
+        Ok(deleted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utilities::{TestPath, test_path};
+
+    fn at(rfc3339: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(rfc3339).unwrap().into()
+    }
+
+    fn file_names(paths: &[PathBuf]) -> Vec<String> {
+        paths
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[rstest::rstest]
+    #[case(
+        RotationInterval::Hourly,
+        "2024-03-01T10:59:59Z",
+        "2024-03-01T11:00:00Z"
+    )]
+    #[case(
+        RotationInterval::Hourly,
+        "2024-03-01T11:00:00Z",
+        "2024-03-01T12:00:00Z"
+    )]
+    #[case(
+        RotationInterval::Daily,
+        "2024-03-01T23:30:00Z",
+        "2024-03-02T00:00:00Z"
+    )]
+    fn given_interval_when_next_boundary_computed_then_it_is_the_next_hour_or_day(
+        #[case] interval: RotationInterval,
+        #[case] time: &str,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(interval.next_boundary(at(time)), at(expected));
+    }
+
+    #[rstest::rstest]
+    #[case("ingestor.pb", "ingestor-20240301T103000Z.pb")]
+    #[case("ingestor.pb.zst", "ingestor-20240301T103000Z.pb.zst")]
+    #[case("ingestor-%Y-%m-%d.pb", "ingestor-2024-03-01.pb")]
+    fn given_template_when_segment_created_then_file_is_named_from_the_timestamp(
+        test_path: TestPath,
+        #[case] template: &str,
+        #[case] expected: &str,
+    ) {
+        let template = SegmentTemplate::new(&test_path.path.join(template)).unwrap();
+
+        let (path, _) = template.create_segment(at("2024-03-01T10:30:00Z")).unwrap();
+
+        assert_eq!(path, test_path.path.join(expected));
+    }
+
+    #[rstest::rstest]
+    fn given_segment_name_taken_when_segment_created_then_a_counter_is_added(test_path: TestPath) {
+        let template = SegmentTemplate::new(&test_path.path.join("log-%Y%m%d.pb.gz")).unwrap();
+
+        let created: Vec<PathBuf> = (0..3)
+            .map(|_| {
+                template
+                    .create_segment(at("2024-03-01T10:30:00Z"))
+                    .unwrap()
+                    .0
+            })
+            .collect();
+
+        assert_eq!(
+            file_names(&created),
+            [
+                "log-20240301.pb.gz",
+                "log-20240301-1.pb.gz",
+                "log-20240301-2.pb.gz"
+            ]
+        );
+    }
+
+    #[rstest::rstest]
+    fn given_retention_when_pruned_then_only_newest_segments_and_their_sidecars_remain(
+        test_path: TestPath,
+    ) {
+        let template = SegmentTemplate::new(&test_path.path.join("log.pb")).unwrap();
+        let mut rotation = SegmentRotation::new(
+            RotationPolicy {
+                retention: Some(2),
+                ..RotationPolicy::default()
+            },
+            template.clone(),
+        );
+        let unrelated = ["other.pb", "log-backup.pb", "log-20240301T090000Z-old.pb"]
+            .map(|file_name| test_path.path.join(file_name));
+        for path in &unrelated {
+            std::fs::write(path, b"").unwrap();
+        }
+        let mut segments = Vec::new();
+        for hour in 10..14 {
+            let (path, _) = rotation
+                .start_segment(at(&format!("2024-03-01T{hour}:00:00Z")))
+                .unwrap();
+            std::fs::write(time_index_path(&path), b"").unwrap();
+            segments.push(path);
+        }
+
+        let deleted = rotation.prune(&segments[3]).unwrap();
+
+        assert_eq!(deleted, segments[..2]);
+        assert_eq!(template.existing_segments().unwrap(), segments[2..]);
+        assert!(!time_index_path(&segments[0]).exists());
+        assert!(time_index_path(&segments[3]).exists());
+        assert!(unrelated.iter().all(|path| path.exists()));
+    }
+
+    #[rstest::rstest]
+    #[case("log-%Y%m%d.pb.gz", "log-20240301.pb.gz", true)]
+    #[case("log-%Y%m%d.pb.gz", "log-20240301-12.pb.gz", true)]
+    #[case("log-%Y%m%d-%H.pb", "log-20240301-10.pb", true)]
+    #[case("log-%Y%m%d-%H.pb", "log-20240301-10-2.pb", true)]
+    #[case("log.pb", "log-20240301T103000Z.pb", true)]
+    #[case("log.pb", "log-backup.pb", false)]
+    #[case("log-%Y%m%d.pb.gz", "log-20240301.pb", false)]
+    #[case("log-%Y%m%d.pb.gz", "log-20240301-.pb.gz", false)]
+    #[case("log-%Y%m%d.pb.gz", "log-2024030.pb.gz.bak", false)]
+    fn given_file_name_when_checked_against_template_then_only_generated_names_match(
+        #[case] template: &str,
+        #[case] file_name: &str,
+        #[case] expected: bool,
+    ) {
+        let template = SegmentTemplate::new(Path::new(template)).unwrap();
+
+        assert_eq!(template.is_segment_name(file_name), expected);
+    }
+
+    #[test]
+    fn given_policies_when_rotation_checked_then_due_on_boundary_or_size() {
+        let mut rotation = SegmentRotation::new(
+            RotationPolicy {
+                interval: Some(RotationInterval::Hourly),
+                max_bytes: Some(100),
+                retention: None,
+            },
+            SegmentTemplate::new(Path::new("unused.pb")).unwrap(),
+        );
+        rotation.next_rotation = Some(at("2024-03-01T11:00:00Z"));
+
+        assert!(!rotation.is_due(90, 10, at("2024-03-01T10:59:59Z")));
+        assert!(rotation.is_due(90, 11, at("2024-03-01T10:59:59Z")));
+        assert!(rotation.is_due(0, 0, at("2024-03-01T11:00:00Z")));
+        // A record larger than the limit still goes into an empty segment.
+        assert!(!rotation.is_due(0, 1000, at("2024-03-01T10:00:00Z")));
+    }
+}
@@ -1,63 +1,303 @@
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::{BufWriter, Write};
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
+use crate::core::central_disk_logger::compression::LogWriter;
 use crate::core::central_disk_logger::errors;
+use crate::core::central_disk_logger::index::{TimeIndexWriter, time_index_path};
 use crate::core::central_disk_logger::interface::{DiskLoggerMessage, LoggerTaskID};
-use crate::core::thread_manager::{SteppableTask, TaskState};
+use crate::core::central_disk_logger::rotation::SegmentRotation;
+use crate::core::channel::ChannelReceiver;
+use crate::core::thread_manager::{SteppableTask, TaskState, Wakeup};
 
+/// Buffered data, and compressed frames, are flushed at least this often, so that a crash
+/// loses at most the data written since.
+pub const DEFAULT_FLUSH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// A log file owned by the [`CentralDiskLogger`], with an optional time index sidecar.
+/// With a [`SegmentRotation`], `path` is the current segment and both the file and its
+/// sidecar are replaced whenever the rotation is due.
+#[derive(Debug)]
+pub struct LogFile {
+    path: PathBuf,
+    writer: LogWriter,
+    bytes_written: u64,
+    time_index: Option<TimeIndexWriter>,
+    rotation: Option<SegmentRotation>,
+}
+impl LogFile {
+    pub fn new(path: PathBuf, writer: LogWriter) -> Self {
+        Self {
+            path,
+            writer,
+            bytes_written: 0,
+            time_index: None,
+            rotation: None,
+        }
+    }
+
+    #[must_use]
+    pub fn with_time_index(mut self, time_index: TimeIndexWriter) -> Self {
+        self.time_index = Some(time_index);
+        self
+    }
+
+    /// `rotation` must have started the segment at `path`.
+    #[must_use]
+    pub fn with_rotation(mut self, rotation: SegmentRotation) -> Self {
+        self.rotation = Some(rotation);
+        self
+    }
+
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn write(&mut self, payload: Vec<u8>) -> Result<(), errors::CentralDiskLoggerError> {
+        self.write_at(payload, std::time::SystemTime::now())
+    }
+
+    fn write_at(
+        &mut self,
+        payload: Vec<u8>,
+        now: std::time::SystemTime,
+    ) -> Result<(), errors::CentralDiskLoggerError> {
+        if self
+            .rotation
+            .as_ref()
+            .is_some_and(|rotation| rotation.is_due(self.bytes_written, payload.len(), now.into()))
+            && let Err(err) = self.rotate(now)
+        {
+            log::warn!(
+                "Unable to rotate log file {}, writing on to it: {err}",
+                self.path.display()
+            );
+        }
+
+        // Records reach the logger after they were created, so everything before this
+        // offset is older than now, as the index requires.
+        if let Some(time_index) = &mut self.time_index
+            && let Err(err) = time_index.record(now, self.bytes_written)
+        {
+            log::warn!(
+                "Unable to write time index for log file {}: {err}",
+                self.path.display()
+            );
+        }
+
+        match self.writer.write_all(&payload) {
+            Ok(()) => {
+                self.bytes_written += payload.len() as u64;
+                Ok(())
+            }
+            Err(err) => Err(errors::CentralDiskLoggerError::WriteError {
+                path: self.path.clone(),
+                payload,
+                source: err,
+            }),
+        }
+    }
+
+    /// Flushes the data and time index once unflushed data is `interval` old.
+    fn flush_if_due(&mut self, interval: std::time::Duration) -> Result<(), std::io::Error> {
+        if self.writer.finish_frame_if_due(interval)?
+            && let Some(time_index) = &mut self.time_index
+        {
+            time_index.flush()?;
+        }
+        Ok(())
+    }
+
+    /// When [`Self::flush_if_due`] will next have something to flush.
+    fn flush_deadline(&self, interval: std::time::Duration) -> Option<std::time::Instant> {
+        self.writer
+            .unfinished_frame_since()
+            .map(|since| since + interval)
+    }
+
+    fn sync_all(&mut self) -> Result<(), std::io::Error> {
+        self.writer.sync_all()?;
+        if let Some(time_index) = &mut self.time_index {
+            time_index.sync_all()?;
+        }
+        Ok(())
+    }
+
+    /// Moves on to a new segment, finishing the current one, then applies retention.
+    fn rotate(&mut self, now: std::time::SystemTime) -> Result<(), std::io::Error> {
+        let Some(rotation) = &mut self.rotation else {
+            return Ok(());
+        };
+        let (path, file) = rotation.start_segment(now.into())?;
+        let writer = LogWriter::new(file, self.writer.compression())?;
+        let time_index = self
+            .time_index
+            .as_ref()
+            .map(|time_index| {
+                TimeIndexWriter::create(&time_index_path(&path), time_index.interval())
+            })
+            .transpose()?;
+
+        self.writer.finish_frame()?;
+        if let Some(time_index) = &mut self.time_index {
+            time_index.flush()?;
+        }
+        log::info!(
+            "Rotating log file {} to {}",
+            self.path.display(),
+            path.display()
+        );
+        self.path = path;
+        self.writer = writer;
+        self.time_index = time_index;
+        self.bytes_written = 0;
+
+        match rotation.prune(&self.path) {
+            Ok(deleted) => {
+                for segment in deleted {
+                    log::info!("Deleted log segment {} past retention", segment.display());
+                }
+            }
+            Err(err) => log::warn!(
+                "Unable to apply retention after rotating to {}: {err}",
+                self.path.display()
+            ),
+        }
+        Ok(())
+    }
+}
+
+/// Writes the messages of every registered logger to its log file. Queued messages are
+/// written and every file is synced to disk when the logger is dropped.
 #[derive(Debug)]
 pub struct CentralDiskLogger {
-    receiver: crossbeam_channel::Receiver<DiskLoggerMessage>,
-    id_to_path_writer_pair_mapping: HashMap<LoggerTaskID, (PathBuf, BufWriter<File>)>,
+    receiver: ChannelReceiver<DiskLoggerMessage>,
+    id_to_log_file_mapping: HashMap<LoggerTaskID, LogFile>,
+    flush_interval: std::time::Duration,
 }
 impl CentralDiskLogger {
     pub fn new(
-        receiver: crossbeam_channel::Receiver<DiskLoggerMessage>,
-        id_to_path_writer_pair_mapping: HashMap<LoggerTaskID, (PathBuf, BufWriter<File>)>,
+        receiver: impl Into<ChannelReceiver<DiskLoggerMessage>>,
+        id_to_log_file_mapping: HashMap<LoggerTaskID, LogFile>,
     ) -> Self {
         Self {
-            receiver,
-            id_to_path_writer_pair_mapping,
+            receiver: receiver.into(),
+            id_to_log_file_mapping,
+            flush_interval: DEFAULT_FLUSH_INTERVAL,
+        }
+    }
+
+    #[must_use]
+    pub fn with_flush_interval(mut self, flush_interval: std::time::Duration) -> Self {
+        self.flush_interval = flush_interval;
+        self
+    }
+
+    fn write(&mut self, message: DiskLoggerMessage) {
+        match self
+            .id_to_log_file_mapping
+            .get_mut(&message.logger_id)
+            .ok_or(errors::CentralDiskLoggerError::TaskNotRegistered(
+                message.logger_id,
+            )) {
+            Ok(log_file) => {
+                if let Err(write_error) = log_file.write(message.payload) {
+                    log::warn!("{write_error}");
+                }
+            }
+            Err(err) => log::warn!("{err}"),
         }
     }
+
+    fn flush_due(&mut self) {
+        for log_file in self.id_to_log_file_mapping.values_mut() {
+            if let Err(err) = log_file.flush_if_due(self.flush_interval) {
+                log::warn!(
+                    "Unable to flush log file {}: {err}",
+                    log_file.path.display()
+                );
+            }
+        }
+    }
+
+    /// Writes every message still queued, then flushes and syncs every log file to disk.
+    pub fn drain_and_sync(&mut self) {
+        let mut drained = 0usize;
+        while let Ok(message) = self.receiver.try_recv() {
+            self.write(message);
+            drained += 1;
+        }
+        for log_file in self.id_to_log_file_mapping.values_mut() {
+            if let Err(err) = log_file.sync_all() {
+                log::error!(
+                    "Unable to sync log file {} to disk: {err}",
+                    log_file.path.display()
+                );
+            }
+        }
+        log::info!(
+            "CentralDiskLogger: wrote {drained} queued messages and synced {} log files",
+            self.id_to_log_file_mapping.len()
+        );
+    }
 }
 
 impl SteppableTask for CentralDiskLogger {
     fn step(&mut self) -> TaskState {
         match self.receiver.try_recv() {
-            Ok(message) => {
-                match self
-                    .id_to_path_writer_pair_mapping
-                    .get_mut(&message.logger_id)
-                    .ok_or(errors::CentralDiskLoggerError::TaskNotRegistered(
-                        message.logger_id,
-                    )) {
-                    Ok((path, writer)) => {
-                        let _ = writer.write_all(&message.payload).map_err(|err| {
-                            let write_error = errors::CentralDiskLoggerError::WriteError {
-                                path: path.clone(),
-                                payload: message.payload,
-                                source: err,
-                            };
-                            log::warn!("{write_error}")
-                        });
-                    }
-                    Err(err) => log::warn!("{err}"),
-                };
-                TaskState::Running
-            }
-            Err(crossbeam_channel::TryRecvError::Empty) => TaskState::Running,
-            Err(crossbeam_channel::TryRecvError::Disconnected) => TaskState::Completed,
+            Ok(message) => self.write(message),
+            Err(crossbeam_channel::TryRecvError::Empty) => {}
+            Err(crossbeam_channel::TryRecvError::Disconnected) => return TaskState::Completed,
         }
+        self.flush_due();
+        TaskState::Running
+    }
+
+    fn wakeup(&self) -> Option<Wakeup<'_>> {
+        let next_flush = self
+            .id_to_log_file_mapping
+            .values()
+            .filter_map(|log_file| log_file.flush_deadline(self.flush_interval))
+            .min();
+        Some(Wakeup::on(&self.receiver).or_at(next_flush))
+    }
+}
+
+impl Drop for CentralDiskLogger {
+    fn drop(&mut self) {
+        self.drain_and_sync();
     }
 }
+
 #[cfg(test)]
 mod tests {
+    use std::fs::File;
+
     use super::*;
+    use crate::core::central_disk_logger::compression::Compression;
+    use crate::core::central_disk_logger::index::TimeIndex;
+    use crate::core::central_disk_logger::rotation::{
+        RotationInterval, RotationPolicy, SegmentTemplate,
+    };
     use std::fs;
 
+    fn rotating_log_file(
+        template: &Path,
+        policy: RotationPolicy,
+        now: std::time::SystemTime,
+    ) -> LogFile {
+        let mut rotation = SegmentRotation::new(policy, SegmentTemplate::new(template).unwrap());
+        let (path, file) = rotation.start_segment(now.into()).unwrap();
+        LogFile::new(path, LogWriter::new(file, Compression::None).unwrap()).with_rotation(rotation)
+    }
+
+    fn at(rfc3339: &str) -> std::time::SystemTime {
+        chrono::DateTime::parse_from_rfc3339(rfc3339)
+            .unwrap()
+            .into()
+    }
+
     #[test]
     fn given_valid_message_when_stepped_then_writes_payload_to_file() {
         let temp_dir = tempfile::tempdir().unwrap();
@@ -66,17 +306,14 @@ mod tests {
         let task_id = 42;
         mapping.insert(
             task_id,
-            (
+            LogFile::new(
                 file_path.clone(),
-                BufWriter::new(File::create_new(&file_path).unwrap()),
+                LogWriter::new(File::create_new(&file_path).unwrap(), Compression::None).unwrap(),
             ),
         );
 
         let (sender, receiver) = crossbeam_channel::unbounded();
-        let mut logger = CentralDiskLogger {
-            id_to_path_writer_pair_mapping: mapping,
-            receiver,
-        };
+        let mut logger = CentralDiskLogger::new(receiver, mapping);
 
         let expected_payload = b"test payload bytes".to_vec();
         sender
@@ -105,17 +342,14 @@ mod tests {
         let mut mapping = HashMap::new();
         mapping.insert(
             1,
-            (
+            LogFile::new(
                 file_path.clone(),
-                BufWriter::new(File::create_new(&file_path).unwrap()),
+                LogWriter::new(File::create_new(&file_path).unwrap(), Compression::None).unwrap(),
             ),
         );
 
         let (_sender, receiver) = crossbeam_channel::unbounded();
-        let mut logger = CentralDiskLogger {
-            id_to_path_writer_pair_mapping: mapping,
-            receiver,
-        };
+        let mut logger = CentralDiskLogger::new(receiver, mapping);
 
         let state = logger.step();
 
@@ -129,17 +363,14 @@ mod tests {
         let mut mapping = HashMap::new();
         mapping.insert(
             1,
-            (
+            LogFile::new(
                 file_path.clone(),
-                BufWriter::new(File::create_new(&file_path).unwrap()),
+                LogWriter::new(File::create_new(&file_path).unwrap(), Compression::None).unwrap(),
             ),
         );
 
         let (sender, receiver) = crossbeam_channel::unbounded();
-        let mut logger = CentralDiskLogger {
-            id_to_path_writer_pair_mapping: mapping,
-            receiver,
-        };
+        let mut logger = CentralDiskLogger::new(receiver, mapping);
 
         // Explicitly drop the sender to disconnect the channel
         drop(sender);
@@ -156,17 +387,14 @@ mod tests {
         let mut mapping = HashMap::new();
         mapping.insert(
             1,
-            (
+            LogFile::new(
                 file_path.clone(),
-                BufWriter::new(File::create_new(&file_path).unwrap()),
+                LogWriter::new(File::create_new(&file_path).unwrap(), Compression::None).unwrap(),
             ),
         );
 
         let (sender, receiver) = crossbeam_channel::unbounded();
-        let mut logger = CentralDiskLogger {
-            id_to_path_writer_pair_mapping: mapping,
-            receiver,
-        };
+        let mut logger = CentralDiskLogger::new(receiver, mapping);
 
         sender
             .send(DiskLoggerMessage {
@@ -179,4 +407,128 @@ mod tests {
 
         assert!(matches!(state, TaskState::Running));
     }
+
+    #[test]
+    fn given_max_bytes_when_payloads_exceed_it_then_log_rotates_and_each_segment_is_indexed() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let template = temp_dir.path().join("sized-%Y%m%d.pb");
+        let now = at("2024-03-01T10:00:00Z");
+        let policy = RotationPolicy {
+            max_bytes: Some(10),
+            ..RotationPolicy::default()
+        };
+        let mut log_file = rotating_log_file(&template, policy, now).with_time_index(
+            TimeIndexWriter::create(
+                &time_index_path(&temp_dir.path().join("sized-20240301.pb")),
+                std::time::Duration::ZERO,
+            )
+            .unwrap(),
+        );
+
+        for payload in [b"123456", b"789012", b"345678"] {
+            log_file.write_at(payload.to_vec(), now).unwrap();
+        }
+        drop(log_file);
+
+        let segments = SegmentTemplate::new(&template)
+            .unwrap()
+            .existing_segments()
+            .unwrap();
+        let contents: Vec<Vec<u8>> = segments
+            .iter()
+            .map(|path| fs::read(path).unwrap())
+            .collect();
+        assert_eq!(contents, [b"123456", b"789012", b"345678"]);
+        for segment in &segments {
+            let index = TimeIndex::load(&time_index_path(segment)).unwrap();
+            assert_eq!(index.entries()[0].offset, 0);
+        }
+    }
+
+    #[test]
+    fn given_hourly_rotation_when_hour_boundary_passes_then_next_write_goes_to_new_segment() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let policy = RotationPolicy {
+            interval: Some(RotationInterval::Hourly),
+            ..RotationPolicy::default()
+        };
+        let mut log_file = rotating_log_file(
+            &temp_dir.path().join("hourly-%H%M.jsonl"),
+            policy,
+            at("2024-03-01T10:15:00Z"),
+        );
+
+        log_file
+            .write_at(b"a\n".to_vec(), at("2024-03-01T10:59:59Z"))
+            .unwrap();
+        log_file
+            .write_at(b"b\n".to_vec(), at("2024-03-01T11:00:00Z"))
+            .unwrap();
+        drop(log_file);
+
+        assert_eq!(
+            fs::read(temp_dir.path().join("hourly-1015.jsonl")).unwrap(),
+            b"a\n"
+        );
+        assert_eq!(
+            fs::read(temp_dir.path().join("hourly-1100.jsonl")).unwrap(),
+            b"b\n"
+        );
+    }
+
+    #[test]
+    fn given_queued_messages_when_logger_dropped_then_all_are_written_to_disk() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("drained.bin");
+        let mut mapping = HashMap::new();
+        mapping.insert(
+            1,
+            LogFile::new(
+                file_path.clone(),
+                LogWriter::new(File::create_new(&file_path).unwrap(), Compression::Zstd).unwrap(),
+            ),
+        );
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let logger = CentralDiskLogger::new(receiver, mapping);
+
+        for payload in [b"first ".to_vec(), b"second".to_vec()] {
+            sender
+                .send(DiskLoggerMessage {
+                    logger_id: 1,
+                    payload,
+                })
+                .unwrap();
+        }
+        drop(logger);
+
+        let written = zstd::decode_all(File::open(&file_path).unwrap()).unwrap();
+        assert_eq!(written, b"first second");
+    }
+
+    #[test]
+    fn given_flush_interval_elapsed_when_stepped_then_payload_is_on_disk_while_logger_runs() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("flushed.bin");
+        let mut mapping = HashMap::new();
+        mapping.insert(
+            1,
+            LogFile::new(
+                file_path.clone(),
+                LogWriter::new(File::create_new(&file_path).unwrap(), Compression::None).unwrap(),
+            ),
+        );
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let mut logger = CentralDiskLogger::new(receiver, mapping)
+            .with_flush_interval(std::time::Duration::ZERO);
+
+        sender
+            .send(DiskLoggerMessage {
+                logger_id: 1,
+                payload: b"flushed".to_vec(),
+            })
+            .unwrap();
+        logger.step();
+
+        assert_eq!(fs::read(&file_path).unwrap(), b"flushed");
+    }
 }
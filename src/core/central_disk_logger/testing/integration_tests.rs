@@ -92,6 +92,35 @@ fn given_multiple_handles_when_messages_sent_concurrently_then_system_routes_cor
     assert_eq!(fs::read(&file_path_2).unwrap(), expected_2);
 }
 
+#[test]
+fn given_proto_logger_when_messages_sent_then_time_index_sidecar_points_at_first_record() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let file_path = temp_dir.path().join("indexed.pb");
+
+    let mut registry =
+        DiskLoggerRegistry::new().with_time_index_interval(std::time::Duration::from_secs(3600));
+    let handle = registry
+        .register_proto::<MockTaskProto>(file_path.clone())
+        .unwrap();
+    let mut central_logger = registry.build();
+
+    let before = std::time::SystemTime::now();
+    for value in [1, 2] {
+        handle
+            .send(MockTaskStruct {
+                larger_than_zero: value,
+            })
+            .unwrap();
+        central_logger.step();
+    }
+    drop(central_logger);
+
+    let index = index::TimeIndex::load(&index::time_index_path(&file_path)).unwrap();
+    assert_eq!(index.entries().len(), 1);
+    assert_eq!(index.entries()[0].offset, 0);
+    assert!(index.entries()[0].timestamp >= before);
+}
+
 #[test]
 fn given_jsonl_logger_when_message_sent_and_stepped_then_correct_json_lines_on_disk() {
     let temp_dir = tempfile::tempdir().unwrap();
@@ -0,0 +1,343 @@
+use std::borrow::Cow;
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+
+use crate::core::thread_manager::WakeSource;
+
+/// What a sender does when its channel is at capacity.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    /// Waits for the consumer to make room.
+    #[default]
+    Block,
+    /// Evicts the oldest queued message to make room for the new one.
+    DropOldest,
+    /// Discards the new message.
+    DropNewest,
+}
+
+/// Capacity and overflow policy of one pipeline edge. Unbounded unless a capacity is set.
+///
+/// A capacity of zero is rejected: it would make a rendezvous channel, on which
+/// [`OverflowPolicy::DropOldest`] never finds anything to evict.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChannelConfig {
+    pub capacity: Option<NonZeroUsize>,
+    pub policy: OverflowPolicy,
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    name: Cow<'static, str>,
+    capacity: Option<usize>,
+    policy: OverflowPolicy,
+    sent: AtomicU64,
+    dropped: AtomicU64,
+    queued: AtomicUsize,
+    receiver_dropped: AtomicBool,
+}
+impl Shared {
+    fn dequeued(&self) {
+        let _ = self
+            .queued
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |queued| {
+                queued.checked_sub(1)
+            });
+    }
+}
+
+/// Creates a pipeline channel named `name` for its [`ChannelStats`].
+#[must_use]
+pub fn channel<T>(
+    name: impl Into<Cow<'static, str>>,
+    config: ChannelConfig,
+) -> (ChannelSender<T>, ChannelReceiver<T>) {
+    let (sender, receiver) = match config.capacity {
+        Some(capacity) => crossbeam_channel::bounded(capacity.get()),
+        None => crossbeam_channel::unbounded(),
+    };
+    let shared = Arc::new(Shared {
+        name: name.into(),
+        capacity: config.capacity.map(NonZeroUsize::get),
+        policy: config.policy,
+        ..Shared::default()
+    });
+    let evict_from = (config.policy == OverflowPolicy::DropOldest).then(|| receiver.clone());
+    (
+        ChannelSender {
+            sender,
+            evict_from,
+            shared: shared.clone(),
+        },
+        ChannelReceiver { receiver, shared },
+    )
+}
+
+/// Sending half of a pipeline channel, applying the channel's [`OverflowPolicy`].
+#[derive(Debug)]
+pub struct ChannelSender<T> {
+    sender: crossbeam_channel::Sender<T>,
+    /// Lets drop-oldest senders evict queued messages.
+    evict_from: Option<crossbeam_channel::Receiver<T>>,
+    shared: Arc<Shared>,
+}
+
+// Implemented by hand because deriving would require `T: Clone`.
+impl<T> Clone for ChannelSender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+            evict_from: self.evict_from.clone(),
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> ChannelSender<T> {
+    /// Sends `message`, or drops a message as the overflow policy dictates when the
+    /// channel is full. Only fails once the receiver is gone.
+    pub fn send(&self, message: T) -> Result<(), crossbeam_channel::SendError<T>> {
+        if self.shared.receiver_dropped.load(Ordering::Relaxed) {
+            return Err(crossbeam_channel::SendError(message));
+        }
+        // Counted before sending, so that the receiver never sees a message it cannot uncount.
+        self.shared.queued.fetch_add(1, Ordering::Relaxed);
+        let result = match self.shared.policy {
+            OverflowPolicy::Block => self.sender.send(message),
+            OverflowPolicy::DropNewest => match self.sender.try_send(message) {
+                Err(crossbeam_channel::TrySendError::Full(_)) => {
+                    self.shared.dequeued();
+                    self.shared.dropped.fetch_add(1, Ordering::Relaxed);
+                    return Ok(());
+                }
+                Err(crossbeam_channel::TrySendError::Disconnected(message)) => {
+                    Err(crossbeam_channel::SendError(message))
+                }
+                Ok(()) => Ok(()),
+            },
+            OverflowPolicy::DropOldest => self.send_evicting_oldest(message),
+        };
+        match result {
+            Ok(()) => {
+                self.shared.sent.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(_) => self.shared.dequeued(),
+        }
+        result
+    }
+
+    fn send_evicting_oldest(&self, mut message: T) -> Result<(), crossbeam_channel::SendError<T>> {
+        loop {
+            match self.sender.try_send(message) {
+                Ok(()) => return Ok(()),
+                Err(crossbeam_channel::TrySendError::Disconnected(message)) => {
+                    return Err(crossbeam_channel::SendError(message));
+                }
+                Err(crossbeam_channel::TrySendError::Full(rejected)) => {
+                    message = rejected;
+                    if let Some(evict_from) = &self.evict_from
+                        && evict_from.try_recv().is_ok()
+                    {
+                        self.shared.dequeued();
+                        self.shared.dropped.fetch_add(1, Ordering::Relaxed);
+                    }
+                }
+            }
+        }
+    }
+
+    #[must_use]
+    pub fn monitor(&self) -> ChannelMonitor {
+        ChannelMonitor {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+/// Wraps a plain crossbeam sender as an unbounded, blocking channel with its own counters.
+impl<T> From<crossbeam_channel::Sender<T>> for ChannelSender<T> {
+    fn from(sender: crossbeam_channel::Sender<T>) -> Self {
+        Self {
+            sender,
+            evict_from: None,
+            shared: Arc::default(),
+        }
+    }
+}
+
+/// Receiving half of a pipeline channel.
+#[derive(Debug)]
+pub struct ChannelReceiver<T> {
+    receiver: crossbeam_channel::Receiver<T>,
+    shared: Arc<Shared>,
+}
+
+impl<T> ChannelReceiver<T> {
+    pub fn recv(&self) -> Result<T, crossbeam_channel::RecvError> {
+        let message = self.receiver.recv()?;
+        self.shared.dequeued();
+        Ok(message)
+    }
+
+    pub fn try_recv(&self) -> Result<T, crossbeam_channel::TryRecvError> {
+        let message = self.receiver.try_recv()?;
+        self.shared.dequeued();
+        Ok(message)
+    }
+
+    pub fn recv_timeout(
+        &self,
+        timeout: std::time::Duration,
+    ) -> Result<T, crossbeam_channel::RecvTimeoutError> {
+        let message = self.receiver.recv_timeout(timeout)?;
+        self.shared.dequeued();
+        Ok(message)
+    }
+
+    #[must_use]
+    pub fn monitor(&self) -> ChannelMonitor {
+        ChannelMonitor {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Drop for ChannelReceiver<T> {
+    fn drop(&mut self) {
+        // Drop-oldest senders hold a receiver themselves, so they would not see the
+        // channel disconnect otherwise.
+        self.shared.receiver_dropped.store(true, Ordering::Relaxed);
+    }
+}
+
+impl<T> WakeSource for ChannelReceiver<T> {
+    fn register<'a>(&'a self, select: &mut crossbeam_channel::Select<'a>) {
+        select.recv(&self.receiver);
+    }
+}
+
+/// Wraps a plain crossbeam receiver, with its own counters.
+impl<T> From<crossbeam_channel::Receiver<T>> for ChannelReceiver<T> {
+    fn from(receiver: crossbeam_channel::Receiver<T>) -> Self {
+        Self {
+            receiver,
+            shared: Arc::default(),
+        }
+    }
+}
+
+/// Reads the counters of a channel without keeping either end of it alive.
+#[derive(Debug, Clone)]
+pub struct ChannelMonitor {
+    shared: Arc<Shared>,
+}
+impl ChannelMonitor {
+    #[must_use]
+    pub fn stats(&self) -> ChannelStats {
+        ChannelStats {
+            name: self.shared.name.clone(),
+            capacity: self.shared.capacity,
+            policy: self.shared.policy,
+            queued: self.shared.queued.load(Ordering::Relaxed),
+            sent: self.shared.sent.load(Ordering::Relaxed),
+            dropped: self.shared.dropped.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelStats {
+    pub name: Cow<'static, str>,
+    pub capacity: Option<usize>,
+    pub policy: OverflowPolicy,
+    /// Messages waiting for the receiver.
+    pub queued: usize,
+    /// Messages accepted into the channel, including any evicted later.
+    pub sent: u64,
+    /// Messages discarded by the overflow policy.
+    pub dropped: u64,
+}
+impl std::fmt::Display for ChannelStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {} queued", self.name, self.queued)?;
+        if let Some(capacity) = self.capacity {
+            write!(f, " of {capacity} ({:?})", self.policy)?;
+        }
+        write!(f, ", {} sent, {} dropped", self.sent, self.dropped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bounded(policy: OverflowPolicy) -> (ChannelSender<u32>, ChannelReceiver<u32>) {
+        channel(
+            "test",
+            ChannelConfig {
+                capacity: NonZeroUsize::new(2),
+                policy,
+            },
+        )
+    }
+
+    #[test]
+    fn given_drop_oldest_when_channel_full_then_oldest_messages_are_evicted() {
+        let (sender, receiver) = bounded(OverflowPolicy::DropOldest);
+
+        for message in 1..=4 {
+            sender.send(message).unwrap();
+        }
+
+        assert_eq!(receiver.try_recv(), Ok(3));
+        assert_eq!(receiver.try_recv(), Ok(4));
+        let stats = sender.monitor().stats();
+        assert_eq!((stats.sent, stats.dropped, stats.queued), (4, 2, 0));
+    }
+
+    #[test]
+    fn given_drop_newest_when_channel_full_then_new_messages_are_discarded() {
+        let (sender, receiver) = bounded(OverflowPolicy::DropNewest);
+
+        for message in 1..=4 {
+            sender.send(message).unwrap();
+        }
+
+        let stats = receiver.monitor().stats();
+        assert_eq!((stats.sent, stats.dropped, stats.queued), (2, 2, 2));
+        assert_eq!(receiver.try_recv(), Ok(1));
+        assert_eq!(receiver.try_recv(), Ok(2));
+        assert_eq!(receiver.monitor().stats().queued, 0);
+    }
+
+    #[test]
+    fn given_block_when_channel_full_then_sender_waits_for_receiver() {
+        let (sender, receiver) = bounded(OverflowPolicy::Block);
+        sender.send(1).unwrap();
+        sender.send(2).unwrap();
+
+        let blocked_sender = sender.clone();
+        let handle = std::thread::spawn(move || blocked_sender.send(3));
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        assert!(!handle.is_finished());
+
+        assert_eq!(receiver.recv(), Ok(1));
+        handle.join().unwrap().unwrap();
+        let stats = sender.monitor().stats();
+        assert_eq!((stats.sent, stats.dropped, stats.queued), (3, 0, 2));
+    }
+
+    #[rstest::rstest]
+    #[case(OverflowPolicy::Block)]
+    #[case(OverflowPolicy::DropOldest)]
+    #[case(OverflowPolicy::DropNewest)]
+    fn given_receiver_dropped_when_sending_then_send_fails(#[case] policy: OverflowPolicy) {
+        let (sender, receiver) = bounded(policy);
+        drop(receiver);
+
+        assert_eq!(sender.send(1), Err(crossbeam_channel::SendError(1)));
+    }
+}
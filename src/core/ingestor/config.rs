@@ -1,7 +1,597 @@
-#[derive(serde::Deserialize)]
+use crate::core::ingestor::errors::{FilterError, ReplayError};
+use crate::core::ingestor::session::LoginCredentials;
+use crate::core::thread_manager::Supervisor;
+
+#[derive(Clone, serde::Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct GliderNetConfig {
+    /// Tag attached to packets from this source; defaults to `host:port`.
+    pub name: Option<String>,
     pub host: String,
     pub port: u16,
-    pub filter: String,
+    pub filter: ServerFilter,
+    /// Callsign to log in with; anonymous receive-only login (`N0CALL`) when unset.
+    pub callsign: Option<String>,
+    /// Overrides the passcode that is otherwise computed from `callsign`.
+    pub passcode: Option<i32>,
+    #[serde(default)]
+    pub reconnect: ReconnectConfig,
+    #[serde(default)]
+    pub session: SessionConfig,
+    /// Restarts the source with a fresh connection once it exhausts `reconnect.max_attempts`,
+    /// fails the server's login check or panics. The pipeline shuts down when the source is
+    /// not restarted, so `escalate` is always on for live sources.
+    #[serde(default)]
+    pub supervision: Supervisor,
+}
+impl GliderNetConfig {
+    #[must_use]
+    pub fn credentials(&self) -> LoginCredentials {
+        let mut credentials = self
+            .callsign
+            .as_deref()
+            .map_or_else(LoginCredentials::default, LoginCredentials::for_callsign);
+        if let Some(passcode) = self.passcode {
+            credentials.passcode = passcode;
+        }
+        credentials
+    }
+}
+
+/// Controls how a dropped APRS-IS connection is re-established.
+///
+/// Delays grow exponentially from `initial_backoff_millis` up to `max_backoff_seconds`.
+/// `max_attempts` is the number of consecutive failed attempts tolerated before the
+/// source gives up; leaving it unset retries forever.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct ReconnectConfig {
+    pub initial_backoff_millis: u64,
+    pub max_backoff_seconds: u64,
+    pub max_attempts: Option<u32>,
+}
+impl Default for ReconnectConfig {
+    fn default() -> Self {
+        Self {
+            initial_backoff_millis: 500,
+            max_backoff_seconds: 60,
+            max_attempts: None,
+        }
+    }
+}
+
+/// Liveness settings for an APRS-IS session.
+///
+/// A `#keepalive` comment is sent to the server every `keepalive_interval_seconds`, and
+/// the connection is treated as stalled when nothing has been received from the server
+/// for `stall_timeout_seconds`.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct SessionConfig {
+    pub keepalive_interval_seconds: u64,
+    pub stall_timeout_seconds: u64,
+}
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            keepalive_interval_seconds: 240,
+            stall_timeout_seconds: 60,
+        }
+    }
+}
+
+/// Playback rate of a recording, given in config as a multiplier (`speed = 2.0`) or as
+/// `speed = "unthrottled"` to replay as fast as packets can be read.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize)]
+#[serde(try_from = "ReplaySpeedSetting")]
+pub enum ReplaySpeed {
+    /// Plays back this many times faster than recorded; `1.0` is real time.
+    Multiplier(f64),
+    Unthrottled,
+}
+impl ReplaySpeed {
+    pub fn validate(self) -> Result<Self, ReplayError> {
+        match self {
+            ReplaySpeed::Multiplier(multiplier)
+                if !(multiplier.is_finite() && multiplier > 0.0) =>
+            {
+                Err(ReplayError::InvalidSpeed(multiplier))
+            }
+            _ => Ok(self),
+        }
+    }
+}
+impl Default for ReplaySpeed {
+    fn default() -> Self {
+        ReplaySpeed::Multiplier(1.0)
+    }
+}
+
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum ReplaySpeedSetting {
+    Multiplier(f64),
+    Named(String),
+}
+impl TryFrom<ReplaySpeedSetting> for ReplaySpeed {
+    type Error = ReplayError;
+
+    fn try_from(setting: ReplaySpeedSetting) -> Result<Self, Self::Error> {
+        match setting {
+            ReplaySpeedSetting::Multiplier(multiplier) => {
+                ReplaySpeed::Multiplier(multiplier).validate()
+            }
+            ReplaySpeedSetting::Named(name) if name == "unthrottled" => {
+                Ok(ReplaySpeed::Unthrottled)
+            }
+            ReplaySpeedSetting::Named(name) => Err(ReplayError::UnknownSpeed(name)),
+        }
+    }
+}
+
+/// APRS-IS server-side filter, either as the raw filter string sent to the server or
+/// as a list of typed filters, e.g.
+///
+/// ```toml
+/// [[ingestor.source.filter]]
+/// type = "range"
+/// latitude = 51.5
+/// longitude = -0.1
+/// distance_km = 200
+///
+/// [[ingestor.source.filter]]
+/// type = "prefix"
+/// prefixes = ["ICA"]
+/// exclude = true
+/// ```
+///
+/// Both forms are checked by [`ServerFilter::filters`] before anything is sent.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+#[serde(untagged)]
+pub enum ServerFilter {
+    Raw(String),
+    Filters(Vec<AprsFilter>),
+}
+impl ServerFilter {
+    /// Parses (for the raw form) and validates every filter.
+    pub fn filters(&self) -> Result<Vec<AprsFilter>, FilterError> {
+        let filters = match self {
+            ServerFilter::Raw(raw) => raw
+                .split_whitespace()
+                .map(str::parse)
+                .collect::<Result<Vec<AprsFilter>, _>>()?,
+            ServerFilter::Filters(filters) => filters.clone(),
+        };
+        for filter in &filters {
+            filter.validate()?;
+        }
+        Ok(filters)
+    }
+
+    /// Renders the validated filters in APRS-IS syntax, space separated.
+    pub fn render(&self) -> Result<String, FilterError> {
+        Ok(self
+            .filters()?
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(" "))
+    }
+}
+
+/// A single APRS-IS filter; with `exclude` set it is rendered with a leading `-`,
+/// which removes matching packets from what the other filters let through.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+pub struct AprsFilter {
+    #[serde(flatten)]
+    pub kind: FilterKind,
+    #[serde(default)]
+    pub exclude: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum FilterKind {
+    /// `r/lat/lon/dist`: packets within `distance_km` of a point.
+    Range {
+        latitude: f64,
+        longitude: f64,
+        distance_km: f64,
+    },
+    /// `a/latN/lonW/latS/lonE`: packets inside a bounding box.
+    Area {
+        north: f64,
+        west: f64,
+        south: f64,
+        east: f64,
+    },
+    /// `p/aa/bb`: packets whose source callsign starts with one of `prefixes`.
+    Prefix { prefixes: Vec<String> },
+    /// `b/call1/call2`: packets from the listed callsigns (`*` wildcards allowed).
+    Budlist { callsigns: Vec<String> },
+    /// `t/poimqstunw`: packets of the listed types.
+    Type { types: Vec<PacketType> },
+    /// `f/call/dist`: packets within `distance_km` of another station's last position.
+    FriendRange { callsign: String, distance_km: f64 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PacketType {
+    Position,
+    Object,
+    Item,
+    Message,
+    Query,
+    Status,
+    Telemetry,
+    UserDefined,
+    Nws,
+    Weather,
+}
+impl PacketType {
+    const ALL: [PacketType; 10] = [
+        PacketType::Position,
+        PacketType::Object,
+        PacketType::Item,
+        PacketType::Message,
+        PacketType::Query,
+        PacketType::Status,
+        PacketType::Telemetry,
+        PacketType::UserDefined,
+        PacketType::Nws,
+        PacketType::Weather,
+    ];
+
+    #[must_use]
+    pub fn code(self) -> char {
+        match self {
+            PacketType::Position => 'p',
+            PacketType::Object => 'o',
+            PacketType::Item => 'i',
+            PacketType::Message => 'm',
+            PacketType::Query => 'q',
+            PacketType::Status => 's',
+            PacketType::Telemetry => 't',
+            PacketType::UserDefined => 'u',
+            PacketType::Nws => 'n',
+            PacketType::Weather => 'w',
+        }
+    }
+
+    pub fn from_code(code: char) -> Result<Self, FilterError> {
+        Self::ALL
+            .into_iter()
+            .find(|packet_type| packet_type.code() == code)
+            .ok_or(FilterError::UnknownPacketType(code))
+    }
+}
+
+impl AprsFilter {
+    pub fn validate(&self) -> Result<(), FilterError> {
+        match &self.kind {
+            FilterKind::Range {
+                latitude,
+                longitude,
+                distance_km,
+            } => {
+                validate_latitude(*latitude)?;
+                validate_longitude(*longitude)?;
+                validate_distance(*distance_km)
+            }
+            FilterKind::Area {
+                north,
+                west,
+                south,
+                east,
+            } => {
+                validate_latitude(*north)?;
+                validate_latitude(*south)?;
+                validate_longitude(*west)?;
+                validate_longitude(*east)?;
+                if north < south {
+                    return Err(FilterError::InvertedArea {
+                        north: *north,
+                        south: *south,
+                    });
+                }
+                Ok(())
+            }
+            FilterKind::Prefix { prefixes } => validate_entries("prefix", prefixes),
+            FilterKind::Budlist { callsigns } => validate_entries("budlist", callsigns),
+            FilterKind::Type { types } => {
+                if types.is_empty() {
+                    return Err(FilterError::Empty("type"));
+                }
+                Ok(())
+            }
+            FilterKind::FriendRange {
+                callsign,
+                distance_km,
+            } => {
+                validate_entries("friend_range", std::slice::from_ref(callsign))?;
+                validate_distance(*distance_km)
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for AprsFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.exclude {
+            write!(f, "-")?;
+        }
+        match &self.kind {
+            FilterKind::Range {
+                latitude,
+                longitude,
+                distance_km,
+            } => write!(f, "r/{latitude}/{longitude}/{distance_km}"),
+            FilterKind::Area {
+                north,
+                west,
+                south,
+                east,
+            } => write!(f, "a/{north}/{west}/{south}/{east}"),
+            FilterKind::Prefix { prefixes } => write!(f, "p/{}", prefixes.join("/")),
+            FilterKind::Budlist { callsigns } => write!(f, "b/{}", callsigns.join("/")),
+            FilterKind::Type { types } => {
+                let codes: String = types.iter().map(|packet_type| packet_type.code()).collect();
+                write!(f, "t/{codes}")
+            }
+            FilterKind::FriendRange {
+                callsign,
+                distance_km,
+            } => write!(f, "f/{callsign}/{distance_km}"),
+        }
+    }
+}
+
+impl std::str::FromStr for AprsFilter {
+    type Err = FilterError;
+
+    /// Parses a single filter in APRS-IS syntax. Values are not range checked here;
+    /// see [`AprsFilter::validate`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (exclude, body) = match s.strip_prefix('-') {
+            Some(body) => (true, body),
+            None => (false, s),
+        };
+        let malformed = || FilterError::Malformed(s.to_string());
+        let number = |value: &str| value.parse::<f64>().map_err(|_| malformed());
+
+        let mut parts = body.split('/');
+        let filter_type = parts.next().ok_or_else(malformed)?;
+        let arguments: Vec<&str> = parts.collect();
+
+        let kind = match (filter_type, arguments.as_slice()) {
+            ("r", [latitude, longitude, distance_km]) => FilterKind::Range {
+                latitude: number(latitude)?,
+                longitude: number(longitude)?,
+                distance_km: number(distance_km)?,
+            },
+            ("a", [north, west, south, east]) => FilterKind::Area {
+                north: number(north)?,
+                west: number(west)?,
+                south: number(south)?,
+                east: number(east)?,
+            },
+            ("p", prefixes) => FilterKind::Prefix {
+                prefixes: prefixes.iter().map(ToString::to_string).collect(),
+            },
+            ("b", callsigns) => FilterKind::Budlist {
+                callsigns: callsigns.iter().map(ToString::to_string).collect(),
+            },
+            ("t", [codes]) => FilterKind::Type {
+                types: codes
+                    .chars()
+                    .map(PacketType::from_code)
+                    .collect::<Result<_, _>>()?,
+            },
+            ("f", [callsign, distance_km]) => FilterKind::FriendRange {
+                callsign: (*callsign).to_string(),
+                distance_km: number(distance_km)?,
+            },
+            ("r" | "a" | "t" | "f", _) => return Err(malformed()),
+            (other, _) => return Err(FilterError::UnknownFilter(other.to_string())),
+        };
+        Ok(Self { kind, exclude })
+    }
+}
+
+fn validate_latitude(latitude: f64) -> Result<(), FilterError> {
+    if (-90.0..=90.0).contains(&latitude) {
+        Ok(())
+    } else {
+        Err(FilterError::LatitudeOutOfRange(latitude))
+    }
+}
+
+fn validate_longitude(longitude: f64) -> Result<(), FilterError> {
+    if (-180.0..=180.0).contains(&longitude) {
+        Ok(())
+    } else {
+        Err(FilterError::LongitudeOutOfRange(longitude))
+    }
+}
+
+/// Half the Earth's equatorial circumference: a range this large already covers the globe.
+pub const MAX_FILTER_DISTANCE_KM: f64 = 20_037.5;
+
+fn validate_distance(distance_km: f64) -> Result<(), FilterError> {
+    if distance_km > 0.0 && distance_km <= MAX_FILTER_DISTANCE_KM {
+        Ok(())
+    } else {
+        Err(FilterError::InvalidDistance(distance_km))
+    }
+}
+
+fn validate_entries(filter_name: &'static str, entries: &[String]) -> Result<(), FilterError> {
+    if entries.is_empty() {
+        return Err(FilterError::Empty(filter_name));
+    }
+    match entries
+        .iter()
+        .find(|entry| entry.is_empty() || entry.contains(['/', ' ']))
+    {
+        Some(entry) => Err(FilterError::InvalidEntry(entry.clone())),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rstest::rstest]
+    #[case("", "N0CALL", -1)]
+    #[case("callsign = \"ogn123\"", "OGN123", 16551)]
+    #[case("callsign = \"OGN123\"\npasscode = 42", "OGN123", 42)]
+    fn given_login_fields_when_config_parsed_then_credentials_are_derived(
+        #[case] login_fields: &str,
+        #[case] expected_callsign: &str,
+        #[case] expected_passcode: i32,
+    ) {
+        let toml = format!(
+            "host = \"aprs.glidernet.org\"\nport = 14580\nfilter = \"r/0/0/100\"\n{login_fields}"
+        );
+        let config: GliderNetConfig = toml::from_str(&toml).unwrap();
+
+        assert_eq!(
+            config.credentials(),
+            LoginCredentials {
+                callsign: expected_callsign.to_string(),
+                passcode: expected_passcode,
+            }
+        );
+    }
+
+    mod server_filter {
+        use super::*;
+
+        fn parse_toml(toml: &str) -> ServerFilter {
+            #[derive(serde::Deserialize)]
+            struct Wrapper {
+                filter: ServerFilter,
+            }
+            toml::from_str::<Wrapper>(toml).unwrap().filter
+        }
+
+        #[test]
+        fn given_structured_filters_when_rendered_then_produces_aprs_is_syntax() {
+            let filter = parse_toml(
+                r#"
+                [[filter]]
+                type = "range"
+                latitude = 51.5
+                longitude = -0.25
+                distance_km = 200
+
+                [[filter]]
+                type = "area"
+                north = 60
+                west = -10
+                south = 45.5
+                east = 20
+
+                [[filter]]
+                type = "prefix"
+                prefixes = ["FLR", "OGN"]
+
+                [[filter]]
+                type = "budlist"
+                callsigns = ["ICA4B*"]
+
+                [[filter]]
+                type = "type"
+                types = ["position", "weather"]
+
+                [[filter]]
+                type = "friend_range"
+                callsign = "LFNW"
+                distance_km = 50
+
+                [[filter]]
+                type = "prefix"
+                prefixes = ["ICA"]
+                exclude = true
+                "#,
+            );
+
+            assert_eq!(
+                filter.render().unwrap(),
+                "r/51.5/-0.25/200 a/60/-10/45.5/20 p/FLR/OGN b/ICA4B* t/pw f/LFNW/50 -p/ICA"
+            );
+        }
+
+        #[test]
+        fn given_raw_filter_string_when_rendered_then_round_trips() {
+            let raw = "r/-33.5/151.25/100 -b/FLR123456 t/poimqstunw";
+            let filter = ServerFilter::Raw(raw.to_string());
+
+            assert_eq!(filter.render().unwrap(), raw);
+        }
+
+        #[rstest::rstest]
+        #[case("r/91/0/100", FilterError::LatitudeOutOfRange(91.0))]
+        #[case("r/0/-180.5/100", FilterError::LongitudeOutOfRange(-180.5))]
+        #[case("r/0/0/0", FilterError::InvalidDistance(0.0))]
+        #[case("r/0/0/250000000000", FilterError::InvalidDistance(250_000_000_000.0))]
+        #[case("f/LFNW/20040", FilterError::InvalidDistance(20_040.0))]
+        #[case("a/10/0/20/5", FilterError::InvertedArea { north: 10.0, south: 20.0 })]
+        #[case("p", FilterError::Empty("prefix"))]
+        #[case("t/px", FilterError::UnknownPacketType('x'))]
+        #[case("x/1/2", FilterError::UnknownFilter("x".into()))]
+        #[case("r/0/0", FilterError::Malformed("r/0/0".into()))]
+        #[case("r/north/0/10", FilterError::Malformed("r/north/0/10".into()))]
+        fn given_invalid_raw_filter_when_validated_then_returns_error(
+            #[case] raw: &str,
+            #[case] expected: FilterError,
+        ) {
+            let filter = ServerFilter::Raw(raw.to_string());
+
+            assert_eq!(filter.filters().unwrap_err(), expected);
+        }
+
+        #[test]
+        fn given_structured_filter_with_out_of_range_coordinates_when_validated_then_returns_error()
+        {
+            let filter = parse_toml(
+                r#"
+                [[filter]]
+                type = "range"
+                latitude = 0
+                longitude = 200
+                distance_km = 10
+                "#,
+            );
+
+            assert_eq!(
+                filter.filters().unwrap_err(),
+                FilterError::LongitudeOutOfRange(200.0)
+            );
+        }
+
+        #[test]
+        fn given_structured_filter_with_misspelled_field_when_parsed_then_fails() {
+            #[derive(Debug, serde::Deserialize)]
+            #[allow(dead_code)]
+            struct Wrapper {
+                filter: ServerFilter,
+            }
+
+            let result = toml::from_str::<Wrapper>(
+                r#"
+                [[filter]]
+                type = "range"
+                latitude = 0
+                longitude = 0
+                distance_km = 10
+                exlcude = true
+                "#,
+            );
+
+            assert!(result.is_err());
+        }
+    }
 }
@@ -0,0 +1,174 @@
+use std::hash::{Hash, Hasher};
+
+use crate::core::channel::{ChannelReceiver, ChannelSender};
+use crate::core::ingestor::task::AprsPacket;
+use crate::core::thread_manager::{SteppableTask, TaskState, Wakeup};
+
+/// Drops packets already seen within `window`, so that sources with overlapping
+/// filters do not feed the same beacon downstream twice.
+///
+/// Two packets are duplicates when they share the sender callsign and the payload after
+/// the first `:`. The digipeater path is ignored because different APRS-IS servers and
+/// receivers rewrite it for the same beacon.
+pub struct PacketDeduplicator {
+    receiver: ChannelReceiver<AprsPacket>,
+    sender: ChannelSender<AprsPacket>,
+    window: std::time::Duration,
+    seen: std::collections::HashSet<u64>,
+    expiry_queue: std::collections::VecDeque<(std::time::SystemTime, u64)>,
+    duplicates_dropped: u64,
+}
+impl PacketDeduplicator {
+    #[must_use]
+    pub fn new(
+        receiver: impl Into<ChannelReceiver<AprsPacket>>,
+        sender: impl Into<ChannelSender<AprsPacket>>,
+        window: std::time::Duration,
+    ) -> Self {
+        Self {
+            receiver: receiver.into(),
+            sender: sender.into(),
+            window,
+            seen: std::collections::HashSet::new(),
+            expiry_queue: std::collections::VecDeque::new(),
+            duplicates_dropped: 0,
+        }
+    }
+
+    #[must_use]
+    pub fn duplicates_dropped(&self) -> u64 {
+        self.duplicates_dropped
+    }
+
+    /// Returns `true` if `packet` has not been seen within the window, and records it.
+    pub fn admit(&mut self, packet: &AprsPacket) -> bool {
+        self.expire(packet.timestamp);
+
+        let key = dedup_key(&packet.message);
+        if !self.seen.insert(key) {
+            self.duplicates_dropped += 1;
+            return false;
+        }
+        self.expiry_queue.push_back((packet.timestamp, key));
+        true
+    }
+
+    fn expire(&mut self, now: std::time::SystemTime) {
+        while let Some(&(seen_at, key)) = self.expiry_queue.front() {
+            if now.duration_since(seen_at).unwrap_or_default() < self.window {
+                break;
+            }
+            self.expiry_queue.pop_front();
+            self.seen.remove(&key);
+        }
+    }
+}
+
+impl SteppableTask for PacketDeduplicator {
+    fn step(&mut self) -> TaskState {
+        let packet = match self.receiver.try_recv() {
+            Ok(packet) => packet,
+            Err(crossbeam_channel::TryRecvError::Empty) => return TaskState::Running,
+            Err(crossbeam_channel::TryRecvError::Disconnected) => {
+                log::info!(
+                    "PacketDeduplicator upstream disconnected after dropping {} duplicates. Task complete",
+                    self.duplicates_dropped
+                );
+                return TaskState::Completed;
+            }
+        };
+
+        if self.admit(&packet)
+            && let Err(err) = self.sender.send(packet)
+        {
+            log::error!("PacketDeduplicator: Failed to send to channel: {err}");
+        }
+        TaskState::Running
+    }
+
+    fn wakeup(&self) -> Option<Wakeup<'_>> {
+        Some(Wakeup::on(&self.receiver))
+    }
+}
+
+fn dedup_key(message: &[u8]) -> u64 {
+    let callsign = message.split(|&b| b == b'>').next().unwrap_or_default();
+    let payload = message
+        .iter()
+        .position(|&b| b == b':')
+        .map_or(message, |index| &message[index..]);
+
+    let mut hasher = std::hash::DefaultHasher::new();
+    callsign.hash(&mut hasher);
+    payload.trim_ascii_end().hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BEACON_VIA_RECEIVER_A: &str =
+        "FLRDDA5BA>OGFLR,qAS,LFNW:/163148h4559.85N/00348.19E'/342/A=001345 !W06! id0ADDA5BA\n";
+    const BEACON_VIA_RECEIVER_B: &str =
+        "FLRDDA5BA>OGFLR,qAS,LFNX:/163148h4559.85N/00348.19E'/342/A=001345 !W06! id0ADDA5BA\n";
+
+    fn packet_at(message: &'static str, seconds: u64) -> AprsPacket {
+        AprsPacket {
+            timestamp: std::time::UNIX_EPOCH + std::time::Duration::from_secs(seconds),
+            message: message.into(),
+            source: "test".into(),
+        }
+    }
+
+    fn deduplicator(window_seconds: u64) -> PacketDeduplicator {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        PacketDeduplicator::new(
+            receiver,
+            sender,
+            std::time::Duration::from_secs(window_seconds),
+        )
+    }
+
+    #[test]
+    fn given_same_beacon_via_different_paths_within_window_then_only_first_is_admitted() {
+        let mut deduplicator = deduplicator(5);
+
+        assert!(deduplicator.admit(&packet_at(BEACON_VIA_RECEIVER_A, 0)));
+        assert!(!deduplicator.admit(&packet_at(BEACON_VIA_RECEIVER_B, 2)));
+        assert_eq!(deduplicator.duplicates_dropped(), 1);
+    }
+
+    #[test]
+    fn given_same_beacon_after_window_then_it_is_admitted_again() {
+        let mut deduplicator = deduplicator(5);
+
+        assert!(deduplicator.admit(&packet_at(BEACON_VIA_RECEIVER_A, 0)));
+        assert!(deduplicator.admit(&packet_at(BEACON_VIA_RECEIVER_A, 5)));
+        assert_eq!(deduplicator.duplicates_dropped(), 0);
+    }
+
+    #[test]
+    fn given_packets_from_merged_sources_when_stepped_then_duplicates_are_not_forwarded() {
+        let (upstream_sender, upstream_receiver) = crossbeam_channel::unbounded();
+        let (downstream_sender, downstream_receiver) = crossbeam_channel::unbounded();
+        let mut deduplicator = PacketDeduplicator::new(
+            upstream_receiver,
+            downstream_sender,
+            std::time::Duration::from_secs(5),
+        );
+
+        upstream_sender
+            .send(packet_at(BEACON_VIA_RECEIVER_A, 0))
+            .unwrap();
+        upstream_sender
+            .send(packet_at(BEACON_VIA_RECEIVER_B, 1))
+            .unwrap();
+        drop(upstream_sender);
+
+        while matches!(deduplicator.step(), TaskState::Running) {}
+
+        let forwarded: Vec<AprsPacket> = downstream_receiver.try_iter().collect();
+        assert_eq!(forwarded, vec![packet_at(BEACON_VIA_RECEIVER_A, 0)]);
+    }
+}
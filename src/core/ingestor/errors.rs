@@ -30,4 +30,54 @@ pub enum PacketError {
     IoError(#[from] std::io::Error),
     #[error("Source disconnected or reached end of file")]
     Disconnected,
+    #[error("Source lost its connection and is waiting to reconnect")]
+    Reconnecting,
+    #[error("Gave up on source after {0} failed reconnect attempts")]
+    RetryBudgetExhausted(u32),
+    #[error("No data available from source yet")]
+    Idle,
+    #[error("Server sent nothing for {0:?}, treating connection as stalled")]
+    Stalled(std::time::Duration),
+    #[error("APRS-IS session error: {0}")]
+    Session(#[from] SessionError),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SessionError {
+    #[error("Server logged in callsign {actual}, expected {expected}")]
+    CallsignMismatch { expected: String, actual: String },
+    #[error("Malformed logresp line: {0}")]
+    MalformedLoginResponse(String),
+}
+
+#[derive(Debug, PartialEq, thiserror::Error)]
+pub enum FilterError {
+    #[error("Latitude {0} is outside of [-90, 90]")]
+    LatitudeOutOfRange(f64),
+    #[error("Longitude {0} is outside of [-180, 180]")]
+    LongitudeOutOfRange(f64),
+    #[error("Distance {0} km must be positive and at most 20037.5 km")]
+    InvalidDistance(f64),
+    #[error("Area north edge {north} lies south of its south edge {south}")]
+    InvertedArea { north: f64, south: f64 },
+    #[error("'{0}' filter needs at least one entry")]
+    Empty(&'static str),
+    #[error("Invalid filter entry: '{0}'")]
+    InvalidEntry(String),
+    #[error("Unknown packet type: '{0}'")]
+    UnknownPacketType(char),
+    #[error("Unknown filter type: '{0}'")]
+    UnknownFilter(String),
+    #[error("Malformed filter: '{0}'")]
+    Malformed(String),
+}
+
+#[derive(Debug, PartialEq, thiserror::Error)]
+pub enum ReplayError {
+    #[error("Replay speed multiplier {0} must be positive and finite")]
+    InvalidSpeed(f64),
+    #[error("Unknown replay speed: '{0}'")]
+    UnknownSpeed(String),
+    #[error("Replay source has been dropped")]
+    SourceClosed,
 }
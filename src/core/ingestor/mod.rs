@@ -1,7 +1,15 @@
 pub mod config;
+pub mod dedup;
 pub mod errors;
 pub mod protobuf;
+pub mod reconnect;
+pub mod recording;
+pub mod replay;
+pub mod session;
 pub mod task;
 
+pub use dedup::PacketDeduplicator;
 pub use protobuf::PbAprsPacket;
-pub use task::{APRSDataSource, AprsPacket, Ingestor};
+pub use reconnect::ReconnectMonitor;
+pub use replay::{ReplayHandle, ReplaySource, SeekTarget};
+pub use task::{APRSDataSource, AprsPacket, Ingestor, SourceName};
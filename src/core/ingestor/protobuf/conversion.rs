@@ -1,6 +1,6 @@
 use crate::core::ingestor::errors::PacketConversionError;
 use crate::core::ingestor::protobuf::PbAprsPacket;
-use crate::core::ingestor::task::AprsPacket;
+use crate::core::ingestor::task::{AprsPacket, SourceName};
 
 impl TryFrom<PbAprsPacket> for AprsPacket {
     type Error = PacketConversionError;
@@ -13,6 +13,7 @@ impl TryFrom<PbAprsPacket> for AprsPacket {
         Ok(Self {
             timestamp,
             message: packet.message,
+            source: SourceName::default(),
         })
     }
 }
@@ -0,0 +1,466 @@
+use std::net::ToSocketAddrs;
+
+use crate::core::ingestor::config::{GliderNetConfig, ReconnectConfig, SessionConfig};
+use crate::core::ingestor::errors;
+use crate::core::ingestor::errors::FilterError;
+use crate::core::ingestor::session::{AprsIsSession, LoginCredentials, SessionEvent};
+use crate::core::ingestor::task::{
+    APRSDataSource, AprsPacket, INGESTOR_CONNECTION_TIMEOUT, INGESTOR_READ_TIMEOUT, LiveSource,
+    SourceName, authentication_handshake,
+};
+
+/// Longest time a single `create_aprs_packet` call blocks while waiting for the next
+/// reconnect attempt, so that the owning task still observes stop requests promptly.
+const RECONNECT_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// Exponential backoff with "equal jitter": each delay is drawn uniformly from
+/// `[d / 2, d]`, where `d` doubles on every attempt until it reaches `max`.
+#[derive(Debug, Clone)]
+pub struct ExponentialBackoff {
+    initial: std::time::Duration,
+    max: std::time::Duration,
+    attempt: u32,
+}
+impl ExponentialBackoff {
+    #[must_use]
+    pub fn new(initial: std::time::Duration, max: std::time::Duration) -> Self {
+        Self {
+            initial,
+            max: max.max(initial),
+            attempt: 0,
+        }
+    }
+
+    /// Number of delays handed out since the last `reset`.
+    #[must_use]
+    pub fn attempt(&self) -> u32 {
+        self.attempt
+    }
+
+    pub fn reset(&mut self) {
+        self.attempt = 0;
+    }
+
+    pub fn next_delay(&mut self) -> std::time::Duration {
+        let ceiling = self
+            .initial
+            .checked_mul(2u32.saturating_pow(self.attempt))
+            .map_or(self.max, |delay| delay.min(self.max));
+        self.attempt = self.attempt.saturating_add(1);
+
+        let half = ceiling / 2;
+        half + half.mul_f64(fastrand::f64())
+    }
+}
+
+/// Opens a fresh, authenticated stream to an APRS data provider.
+pub trait Connect: Send {
+    type Stream: std::io::Read + std::io::Write + Send;
+    fn connect(&mut self) -> std::io::Result<Self::Stream>;
+
+    /// Credentials sent in the login line, used to verify the server's `logresp`.
+    fn credentials(&self) -> LoginCredentials {
+        LoginCredentials::default()
+    }
+}
+
+impl<F, S> Connect for F
+where
+    F: FnMut() -> std::io::Result<S> + Send,
+    S: std::io::Read + std::io::Write + Send,
+{
+    type Stream = S;
+    fn connect(&mut self) -> std::io::Result<S> {
+        self()
+    }
+}
+
+/// Connects to an APRS-IS server, re-resolving the host on every call so that
+/// round-robin DNS entries can route around a server that went away.
+pub struct GliderNetConnector {
+    host: String,
+    port: u16,
+    filter: String,
+    credentials: LoginCredentials,
+}
+impl GliderNetConnector {
+    pub fn new(config: &GliderNetConfig) -> Result<Self, FilterError> {
+        Ok(Self {
+            host: config.host.clone(),
+            port: config.port,
+            filter: config.filter.render()?,
+            credentials: config.credentials(),
+        })
+    }
+}
+impl Connect for GliderNetConnector {
+    type Stream = std::net::TcpStream;
+
+    fn connect(&mut self) -> std::io::Result<Self::Stream> {
+        let address_str = format!("{}:{}", self.host, self.port);
+
+        let mut resolved_address = address_str.to_socket_addrs()?;
+
+        let address = resolved_address.next().ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::NotFound, "Could not resolve host")
+        })?;
+
+        let mut stream =
+            std::net::TcpStream::connect_timeout(&address, INGESTOR_CONNECTION_TIMEOUT)?;
+
+        let _ = stream.set_read_timeout(Some(INGESTOR_READ_TIMEOUT));
+
+        authentication_handshake(&mut stream, &self.credentials, &self.filter)?;
+
+        Ok(stream)
+    }
+
+    fn credentials(&self) -> LoginCredentials {
+        self.credentials.clone()
+    }
+}
+
+/// Reads how often a live source reconnected from other threads. Clones share the count,
+/// so it carries on across the instances a supervisor builds for the source.
+#[derive(Debug, Clone)]
+pub struct ReconnectMonitor {
+    source: SourceName,
+    reconnects: std::sync::Arc<std::sync::atomic::AtomicU64>,
+}
+impl ReconnectMonitor {
+    #[must_use]
+    pub fn new(source: &str) -> Self {
+        Self {
+            source: source.into(),
+            reconnects: std::sync::Arc::default(),
+        }
+    }
+
+    /// Name of the source whose reconnects are counted.
+    #[must_use]
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Number of successful reconnects so far.
+    #[must_use]
+    pub fn reconnects(&self) -> u64 {
+        self.reconnects.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    fn record_reconnect(&self) -> u64 {
+        self.reconnects
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+            + 1
+    }
+}
+impl std::fmt::Display for ReconnectMonitor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Source {}: {} reconnects",
+            self.source,
+            self.reconnects()
+        )
+    }
+}
+
+/// A live source that transparently reconnects when the underlying stream drops.
+///
+/// While a reconnect is pending, `create_aprs_packet` returns
+/// [`errors::PacketError::Reconnecting`]. Once `max_attempts` consecutive attempts have
+/// failed it returns [`errors::PacketError::RetryBudgetExhausted`], which errors the
+/// ingestor task.
+pub struct ReconnectingSource<C: Connect> {
+    connector: C,
+    live: Option<LiveSource<C::Stream>>,
+    session_config: SessionConfig,
+    session_events: Option<crossbeam_channel::Sender<SessionEvent>>,
+    backoff: ExponentialBackoff,
+    max_attempts: Option<u32>,
+    next_attempt_at: std::time::Instant,
+    monitor: ReconnectMonitor,
+}
+impl<C: Connect> ReconnectingSource<C> {
+    /// Wraps an already established stream; `connector` is only used after it drops.
+    pub fn new(
+        connector: C,
+        stream: C::Stream,
+        config: &ReconnectConfig,
+        session_config: SessionConfig,
+    ) -> Self {
+        let session = AprsIsSession::new(&connector.credentials(), &session_config);
+        Self {
+            live: Some(LiveSource::with_session(stream, session)),
+            connector,
+            session_config,
+            session_events: None,
+            backoff: ExponentialBackoff::new(
+                std::time::Duration::from_millis(config.initial_backoff_millis),
+                std::time::Duration::from_secs(config.max_backoff_seconds),
+            ),
+            max_attempts: config.max_attempts,
+            next_attempt_at: std::time::Instant::now(),
+            monitor: ReconnectMonitor::new(""),
+        }
+    }
+
+    /// Forwards server comments from every connection made by this source to `sender`.
+    #[must_use]
+    pub fn with_session_events(mut self, sender: crossbeam_channel::Sender<SessionEvent>) -> Self {
+        self.live = self
+            .live
+            .map(|live| live.with_session_events(sender.clone()));
+        self.session_events = Some(sender);
+        self
+    }
+
+    /// Counts the reconnects of this source in `monitor`.
+    #[must_use]
+    pub fn with_monitor(mut self, monitor: ReconnectMonitor) -> Self {
+        self.monitor = monitor;
+        self
+    }
+
+    fn schedule_reconnect(&mut self) -> errors::PacketError {
+        if let Some(max_attempts) = self.max_attempts
+            && self.backoff.attempt() >= max_attempts
+        {
+            return errors::PacketError::RetryBudgetExhausted(self.backoff.attempt());
+        }
+        let delay = self.backoff.next_delay();
+        log::info!(
+            "Reconnect attempt {} scheduled in {delay:?}",
+            self.backoff.attempt()
+        );
+        self.next_attempt_at = std::time::Instant::now() + delay;
+        errors::PacketError::Reconnecting
+    }
+
+    fn try_reconnect(&mut self) -> Result<(), errors::PacketError> {
+        let now = std::time::Instant::now();
+        if now < self.next_attempt_at {
+            std::thread::sleep((self.next_attempt_at - now).min(RECONNECT_POLL_INTERVAL));
+            return Err(errors::PacketError::Reconnecting);
+        }
+
+        match self.connector.connect() {
+            Ok(stream) => {
+                let session =
+                    AprsIsSession::new(&self.connector.credentials(), &self.session_config);
+                let mut live = LiveSource::with_session(stream, session);
+                if let Some(sender) = &self.session_events {
+                    live = live.with_session_events(sender.clone());
+                }
+                self.live = Some(live);
+                let reconnects = self.monitor.record_reconnect();
+                self.backoff.reset();
+                log::warn!("Reconnected to APRS-IS (reconnect #{reconnects})");
+                Ok(())
+            }
+            Err(err) => {
+                log::warn!("Reconnect attempt {} failed: {err}", self.backoff.attempt());
+                Err(self.schedule_reconnect())
+            }
+        }
+    }
+}
+
+impl<C: Connect> APRSDataSource for ReconnectingSource<C> {
+    fn create_aprs_packet(&mut self) -> Result<AprsPacket, errors::PacketError> {
+        if self.live.is_none() {
+            self.try_reconnect()?;
+        }
+        let Some(live) = self.live.as_mut() else {
+            return Err(errors::PacketError::Reconnecting);
+        };
+
+        match live.create_aprs_packet() {
+            Err(errors::PacketError::Disconnected) => {
+                self.live = None;
+                Err(self.schedule_reconnect())
+            }
+            Err(errors::PacketError::IoError(err)) if is_connection_lost(&err) => {
+                log::warn!("APRS-IS connection lost: {err}");
+                self.live = None;
+                Err(self.schedule_reconnect())
+            }
+            Err(stalled @ errors::PacketError::Stalled(_)) => {
+                log::warn!("{stalled}");
+                self.live = None;
+                Err(self.schedule_reconnect())
+            }
+            other => other,
+        }
+    }
+}
+
+fn is_connection_lost(error: &std::io::Error) -> bool {
+    matches!(
+        error.kind(),
+        std::io::ErrorKind::ConnectionReset
+            | std::io::ErrorKind::ConnectionAborted
+            | std::io::ErrorKind::BrokenPipe
+            | std::io::ErrorKind::UnexpectedEof
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fast_config(max_attempts: Option<u32>) -> ReconnectConfig {
+        ReconnectConfig {
+            initial_backoff_millis: 1,
+            max_backoff_seconds: 0,
+            max_attempts,
+        }
+    }
+
+    fn stream(data: &str) -> std::io::Cursor<Vec<u8>> {
+        std::io::Cursor::new(data.as_bytes().to_vec())
+    }
+
+    fn next_packet<C: Connect>(
+        source: &mut ReconnectingSource<C>,
+    ) -> Result<AprsPacket, errors::PacketError> {
+        loop {
+            match source.create_aprs_packet() {
+                Err(errors::PacketError::Reconnecting) => {}
+                other => return other,
+            }
+        }
+    }
+
+    #[test]
+    fn given_backoff_when_delays_requested_then_delays_grow_within_jitter_bounds_and_cap() {
+        let initial = std::time::Duration::from_millis(100);
+        let max = std::time::Duration::from_millis(350);
+        let mut backoff = ExponentialBackoff::new(initial, max);
+
+        for ceiling_millis in [100, 200, 350, 350] {
+            let ceiling = std::time::Duration::from_millis(ceiling_millis);
+            let delay = backoff.next_delay();
+            assert!(delay >= ceiling / 2 && delay <= ceiling, "{delay:?}");
+        }
+        assert_eq!(backoff.attempt(), 4);
+
+        backoff.reset();
+        assert!(backoff.next_delay() <= initial);
+    }
+
+    #[test]
+    fn given_stream_drops_when_reconnect_succeeds_then_packets_resume_and_reconnect_is_counted_in_monitor()
+     {
+        let mut streams = std::collections::VecDeque::from([stream("PACKET_2\n")]);
+        let connector = move || {
+            streams
+                .pop_front()
+                .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::ConnectionRefused))
+        };
+        let monitor = ReconnectMonitor::new("test");
+        let mut source = ReconnectingSource::new(
+            connector,
+            stream("PACKET_1\n"),
+            &fast_config(None),
+            SessionConfig::default(),
+        )
+        .with_monitor(monitor.clone());
+
+        assert_eq!(next_packet(&mut source).unwrap().message, "PACKET_1\n");
+        assert_eq!(next_packet(&mut source).unwrap().message, "PACKET_2\n");
+        assert_eq!(monitor.reconnects(), 1);
+    }
+
+    #[test]
+    fn given_failing_reconnects_when_budget_exhausted_then_source_reports_exhaustion() {
+        let attempts = std::sync::Arc::new(std::sync::atomic::AtomicU32::new(0));
+        let counter = attempts.clone();
+        let connector = move || -> std::io::Result<std::io::Cursor<Vec<u8>>> {
+            counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Err(std::io::Error::from(std::io::ErrorKind::ConnectionRefused))
+        };
+        let monitor = ReconnectMonitor::new("test");
+        let mut source = ReconnectingSource::new(
+            connector,
+            stream(""),
+            &fast_config(Some(3)),
+            SessionConfig::default(),
+        )
+        .with_monitor(monitor.clone());
+
+        assert!(matches!(
+            next_packet(&mut source),
+            Err(errors::PacketError::RetryBudgetExhausted(3))
+        ));
+        assert_eq!(attempts.load(std::sync::atomic::Ordering::SeqCst), 3);
+        assert_eq!(monitor.reconnects(), 0);
+    }
+
+    #[test]
+    fn given_failed_attempts_when_reconnect_succeeds_then_retry_budget_is_reset() {
+        let mut results = std::collections::VecDeque::from([
+            Err(std::io::Error::from(std::io::ErrorKind::ConnectionRefused)),
+            Ok(stream("")),
+            Err(std::io::Error::from(std::io::ErrorKind::ConnectionRefused)),
+            Ok(stream("PACKET_1\n")),
+        ]);
+        let connector = move || {
+            results
+                .pop_front()
+                .unwrap_or_else(|| Err(std::io::Error::from(std::io::ErrorKind::NotFound)))
+        };
+        let monitor = ReconnectMonitor::new("test");
+        let mut source = ReconnectingSource::new(
+            connector,
+            stream(""),
+            &fast_config(Some(2)),
+            SessionConfig::default(),
+        )
+        .with_monitor(monitor.clone());
+
+        assert_eq!(next_packet(&mut source).unwrap().message, "PACKET_1\n");
+        assert_eq!(monitor.reconnects(), 2);
+    }
+
+    #[test]
+    fn given_stalled_server_when_stall_timeout_exceeded_then_source_reconnects() {
+        struct SilentStream;
+        impl std::io::Read for SilentStream {
+            fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::from(std::io::ErrorKind::TimedOut))
+            }
+        }
+        impl std::io::Write for SilentStream {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                Ok(buf.len())
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let session_config = SessionConfig {
+            stall_timeout_seconds: 0,
+            ..SessionConfig::default()
+        };
+        let monitor = ReconnectMonitor::new("test");
+        let mut source = ReconnectingSource::new(
+            || Ok(SilentStream),
+            SilentStream,
+            &fast_config(Some(1)),
+            session_config,
+        )
+        .with_monitor(monitor.clone());
+        std::thread::sleep(std::time::Duration::from_millis(2));
+
+        assert!(matches!(
+            source.create_aprs_packet(),
+            Err(errors::PacketError::Reconnecting)
+        ));
+        while monitor.reconnects() == 0 {
+            let _ = source.create_aprs_packet();
+        }
+        assert_eq!(monitor.reconnects(), 1);
+    }
+}
@@ -0,0 +1,476 @@
+use std::io::{Read, Seek};
+
+use prost::Message;
+
+use crate::core::central_disk_logger::compression::Compression;
+use crate::core::central_disk_logger::index::{TimeIndexWriter, time_index_path};
+use crate::core::ingestor::protobuf::PbAprsPacket;
+
+/// Upper bound on the encoded size of a single record. APRS packets are a few hundred
+/// bytes at most, so anything larger is treated as a corrupted length prefix.
+pub const MAX_RECORD_LENGTH: usize = 64 * 1024;
+
+/// Longest encoding of a `u64` varint.
+const MAX_VARINT_LENGTH: usize = 10;
+
+/// Bytes requested from the underlying reader at a time.
+const READ_CHUNK_LENGTH: usize = 64 * 1024;
+
+/// Streams length-delimited [`PbAprsPacket`]s from a recording without loading it into
+/// memory.
+///
+/// A record that fails to decode, or runs past the end of the file, is skipped by
+/// resynchronising one byte at a time until a valid record is found. Resynchronising
+/// works on buffered data, so the underlying reader only needs to support `Seek` for
+/// [`RecordReader::seek_to`].
+pub struct RecordReader<R> {
+    reader: R,
+    buffer: Vec<u8>,
+    /// Start of the unread data in `buffer`, which sits at `offset` in the stream.
+    start: usize,
+    offset: u64,
+    end_of_input: bool,
+    skipped_bytes: u64,
+}
+impl RecordReader<Recording> {
+    pub fn open(path: &std::path::Path) -> Result<Self, std::io::Error> {
+        Ok(Self::new(Recording::open(path)?))
+    }
+}
+impl<R: Read> RecordReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            reader: inner,
+            buffer: Vec::new(),
+            start: 0,
+            offset: 0,
+            end_of_input: false,
+            skipped_bytes: 0,
+        }
+    }
+
+    /// Byte offset of the next record to be read.
+    #[must_use]
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Total bytes skipped while resynchronising past corrupted records.
+    #[must_use]
+    pub fn skipped_bytes(&self) -> u64 {
+        self.skipped_bytes
+    }
+
+    /// Returns the next valid record together with its byte offset, or `None` at the end
+    /// of the recording.
+    pub fn next_record(&mut self) -> Result<Option<(u64, PbAprsPacket)>, std::io::Error> {
+        let mut corrupted_from = None;
+        loop {
+            let record_offset = self.offset;
+            match self.read_record()? {
+                Some(RecordRead::Valid(packet)) => {
+                    if let Some(start) = corrupted_from {
+                        log::warn!(
+                            "Skipped {} corrupted bytes at byte {start} of recording",
+                            record_offset - start
+                        );
+                    }
+                    return Ok(Some((record_offset, packet)));
+                }
+                Some(RecordRead::Corrupted) => {
+                    corrupted_from.get_or_insert(record_offset);
+                    self.skipped_bytes += 1;
+                    self.consume(1);
+                }
+                None => {
+                    if let Some(start) = corrupted_from {
+                        log::warn!(
+                            "Recording ends with {} unreadable bytes from byte {start}",
+                            self.offset - start
+                        );
+                    }
+                    return Ok(None);
+                }
+            }
+        }
+    }
+
+    /// Decodes one record at the current offset, returning `None` at the end of the file.
+    fn read_record(&mut self) -> Result<Option<RecordRead>, std::io::Error> {
+        self.fill(MAX_VARINT_LENGTH)?;
+        let available = &self.buffer[self.start..];
+        if available.is_empty() {
+            return Ok(None);
+        }
+
+        let Some((length, prefix_length)) = decode_length_prefix(available) else {
+            return Ok(Some(RecordRead::Corrupted));
+        };
+        let Some(record_length) = usize::try_from(length)
+            .ok()
+            .filter(|&length| length <= MAX_RECORD_LENGTH)
+            .map(|length| prefix_length + length)
+        else {
+            return Ok(Some(RecordRead::Corrupted));
+        };
+
+        if !self.fill(record_length)? {
+            return Ok(Some(RecordRead::Corrupted));
+        }
+        let record = &self.buffer[self.start + prefix_length..self.start + record_length];
+        match PbAprsPacket::decode(record) {
+            Ok(packet) if has_valid_timestamp(&packet) => {
+                self.consume(record_length);
+                Ok(Some(RecordRead::Valid(packet)))
+            }
+            _ => Ok(Some(RecordRead::Corrupted)),
+        }
+    }
+
+    /// Reads until at least `length` unread bytes are buffered, returning `false` if the
+    /// input ends first.
+    fn fill(&mut self, length: usize) -> Result<bool, std::io::Error> {
+        while self.buffer.len() - self.start < length && !self.end_of_input {
+            if self.start > 0 {
+                self.buffer.drain(..self.start);
+                self.start = 0;
+            }
+            let filled = self.buffer.len();
+            self.buffer.resize(filled + READ_CHUNK_LENGTH, 0);
+            let read = loop {
+                match self.reader.read(&mut self.buffer[filled..]) {
+                    Err(error) if error.kind() == std::io::ErrorKind::Interrupted => {}
+                    result => break result,
+                }
+            };
+            match read {
+                Ok(read) => {
+                    self.buffer.truncate(filled + read);
+                    self.end_of_input = read == 0;
+                }
+                Err(error) => {
+                    self.buffer.truncate(filled);
+                    return Err(error);
+                }
+            }
+        }
+        Ok(self.buffer.len() - self.start >= length)
+    }
+
+    fn consume(&mut self, length: usize) {
+        self.start += length;
+        self.offset += length as u64;
+    }
+}
+impl<R: Read + Seek> RecordReader<R> {
+    pub fn seek_to(&mut self, offset: u64) -> Result<(), std::io::Error> {
+        self.reader.seek(std::io::SeekFrom::Start(offset))?;
+        self.buffer.clear();
+        self.start = 0;
+        self.offset = offset;
+        self.end_of_input = false;
+        Ok(())
+    }
+}
+
+/// Decodes a varint length prefix, returning it with its encoded size, or `None` when it
+/// is over-long or cut off by the end of the input.
+fn decode_length_prefix(bytes: &[u8]) -> Option<(u64, usize)> {
+    let mut length = 0u64;
+    for (index, byte) in bytes.iter().take(MAX_VARINT_LENGTH).enumerate() {
+        length |= u64::from(byte & 0x7f) << (7 * index);
+        if byte & 0x80 == 0 {
+            return Some((length, index + 1));
+        }
+    }
+    None
+}
+
+/// A recording file, transparently decompressed when its extension is `.gz` or `.zst`.
+///
+/// Compressed recordings can only be seeked back to their start, which reopens them.
+pub struct Recording {
+    path: std::path::PathBuf,
+    source: RecordingSource,
+}
+enum RecordingSource {
+    Plain(std::fs::File),
+    Compressed(Box<dyn Read + Send>),
+}
+impl Recording {
+    pub fn open(path: &std::path::Path) -> Result<Self, std::io::Error> {
+        let file = std::fs::File::open(path)?;
+        let source = match Compression::from_path(path) {
+            Compression::None => RecordingSource::Plain(file),
+            Compression::Gzip => RecordingSource::Compressed(Box::new(TruncationTolerant::new(
+                flate2::read::MultiGzDecoder::new(file),
+            ))),
+            Compression::Zstd => RecordingSource::Compressed(Box::new(TruncationTolerant::new(
+                zstd::stream::read::Decoder::new(file)?,
+            ))),
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    #[must_use]
+    pub fn is_compressed(&self) -> bool {
+        matches!(self.source, RecordingSource::Compressed(_))
+    }
+}
+impl Read for Recording {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match &mut self.source {
+            RecordingSource::Plain(file) => file.read(buf),
+            RecordingSource::Compressed(decoder) => decoder.read(buf),
+        }
+    }
+}
+impl Seek for Recording {
+    fn seek(&mut self, position: std::io::SeekFrom) -> std::io::Result<u64> {
+        match (&mut self.source, position) {
+            (RecordingSource::Plain(file), _) => file.seek(position),
+            (RecordingSource::Compressed(_), std::io::SeekFrom::Start(0)) => {
+                *self = Recording::open(&self.path)?;
+                Ok(0)
+            }
+            (RecordingSource::Compressed(_), _) => Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                format!(
+                    "{} is compressed and can only be rewound",
+                    self.path.display()
+                ),
+            )),
+        }
+    }
+}
+
+/// Ends a decompressed stream at the first decoding error instead of failing, so that
+/// the frame left unfinished by a crash does not hide the frames before it.
+struct TruncationTolerant<R> {
+    decoder: R,
+    ended: bool,
+}
+impl<R> TruncationTolerant<R> {
+    fn new(decoder: R) -> Self {
+        Self {
+            decoder,
+            ended: false,
+        }
+    }
+}
+impl<R: Read> Read for TruncationTolerant<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.ended {
+            return Ok(0);
+        }
+        match self.decoder.read(buf) {
+            Err(error) if error.kind() != std::io::ErrorKind::Interrupted => {
+                log::warn!("Compressed recording ends with an unreadable frame: {error}");
+                self.ended = true;
+                Ok(0)
+            }
+            result => result,
+        }
+    }
+}
+
+/// Rewrites the time index sidecar of `recording` from the records it contains, e.g. for
+/// files recorded before indexes were written. Returns the number of entries written.
+pub fn rebuild_time_index(
+    recording: &std::path::Path,
+    interval: std::time::Duration,
+) -> Result<usize, std::io::Error> {
+    let mut records = RecordReader::open(recording)?;
+    if records.reader.is_compressed() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "time indexes can only be built for uncompressed recordings",
+        ));
+    }
+    let mut time_index = TimeIndexWriter::create(&time_index_path(recording), interval)?;
+
+    let mut entries = 0;
+    let mut latest: Option<std::time::SystemTime> = None;
+    while let Some((offset, packet)) = records.next_record()? {
+        let Some(timestamp) = packet
+            .timestamp
+            .and_then(|timestamp| std::time::SystemTime::try_from(timestamp).ok())
+        else {
+            continue;
+        };
+        // Only a record newer than everything before it can start an entry, because
+        // entries promise that no earlier record is newer than the entry.
+        if latest.is_none_or(|latest| timestamp > latest) {
+            if time_index.record(timestamp, offset)? {
+                entries += 1;
+            }
+            latest = Some(timestamp);
+        }
+    }
+    time_index.flush()?;
+
+    if records.skipped_bytes() > 0 {
+        log::warn!(
+            "Skipped {} corrupted bytes while indexing {}",
+            records.skipped_bytes(),
+            recording.display()
+        );
+    }
+    Ok(entries)
+}
+
+enum RecordRead {
+    Valid(PbAprsPacket),
+    Corrupted,
+}
+
+/// Every record written by the ingestor carries a timestamp, so its absence is a strong
+/// sign that bytes from the middle of a record were decoded by mistake.
+fn has_valid_timestamp(packet: &PbAprsPacket) -> bool {
+    packet
+        .timestamp
+        .is_some_and(|timestamp| std::time::SystemTime::try_from(timestamp).is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+    use crate::core::central_disk_logger::compression::LogWriter;
+    use crate::core::central_disk_logger::index::TimeIndex;
+    use crate::test_utilities::{TestPath, test_path};
+
+    fn encode(messages: &[&str]) -> Vec<u8> {
+        let base_time = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+        let mut bytes = Vec::new();
+        for (index, message) in messages.iter().enumerate() {
+            PbAprsPacket {
+                timestamp: Some(prost_types::Timestamp::from(
+                    base_time + std::time::Duration::from_secs(index as u64),
+                )),
+                message: message.to_string().into(),
+            }
+            .encode_length_delimited(&mut bytes)
+            .unwrap();
+        }
+        bytes
+    }
+
+    fn read_all(bytes: Vec<u8>) -> (Vec<String>, u64) {
+        let mut reader = RecordReader::new(std::io::Cursor::new(bytes));
+        let mut messages = Vec::new();
+        while let Some((_, packet)) = reader.next_record().unwrap() {
+            messages.push(String::from_utf8(packet.message.to_vec()).unwrap());
+        }
+        (messages, reader.skipped_bytes())
+    }
+
+    #[test]
+    fn given_well_formed_recording_when_read_then_records_and_offsets_are_returned_in_order() {
+        let bytes = encode(&["packet 1\n", "packet 2\n"]);
+        let first_record_length = encode(&["packet 1\n"]).len() as u64;
+        let mut reader = RecordReader::new(std::io::Cursor::new(bytes));
+
+        let (first_offset, _) = reader.next_record().unwrap().unwrap();
+        let (second_offset, second) = reader.next_record().unwrap().unwrap();
+
+        assert_eq!(first_offset, 0);
+        assert_eq!(second_offset, first_record_length);
+        assert_eq!(second.message, "packet 2\n");
+        assert!(reader.next_record().unwrap().is_none());
+    }
+
+    #[test]
+    fn given_truncated_trailing_record_when_read_then_complete_records_are_returned_and_reading_ends()
+     {
+        let mut bytes = encode(&["packet 1\n", "packet 2\n"]);
+        bytes.truncate(bytes.len() - 4);
+
+        let (messages, _) = read_all(bytes);
+
+        assert_eq!(messages, ["packet 1\n"]);
+    }
+
+    #[test]
+    fn given_garbage_between_records_when_read_then_reader_resynchronises_on_next_record() {
+        let mut bytes = encode(&["packet 1\n"]);
+        bytes.extend_from_slice(&[0xff, 0xff, 0x03, 0x12, 0x00, 0x7f]);
+        bytes.extend(encode(&["packet 2\n"]));
+
+        let (messages, skipped_bytes) = read_all(bytes);
+
+        assert_eq!(messages, ["packet 1\n", "packet 2\n"]);
+        assert_eq!(skipped_bytes, 6);
+    }
+
+    #[rstest::rstest]
+    fn given_recording_without_index_when_rebuilt_then_entries_point_at_records_one_interval_apart(
+        test_path: TestPath,
+    ) {
+        let recording = test_path.path.join("old.pb");
+        let bytes = encode(&["packet 1\n", "packet 2\n", "packet 3\n", "packet 4\n"]);
+        let record_length = bytes.len() as u64 / 4;
+        std::fs::write(&recording, bytes).unwrap();
+
+        let entries = rebuild_time_index(&recording, std::time::Duration::from_secs(2)).unwrap();
+
+        let offsets: Vec<u64> = TimeIndex::load(&time_index_path(&recording))
+            .unwrap()
+            .entries()
+            .iter()
+            .map(|entry| entry.offset)
+            .collect();
+        assert_eq!(entries, 2);
+        assert_eq!(offsets, [0, 2 * record_length]);
+    }
+
+    #[rstest::rstest]
+    #[case("compressed.pb.gz")]
+    #[case("compressed.pb.zst")]
+    fn given_compressed_recording_with_unfinished_last_frame_when_read_then_finished_frames_are_returned(
+        test_path: TestPath,
+        #[case] file_name: &str,
+    ) {
+        let path = test_path.path.join(file_name);
+        let file = std::fs::File::create(&path).unwrap();
+        let mut writer = LogWriter::new(file, Compression::from_path(&path)).unwrap();
+        writer.write_all(&encode(&["packet 1\n"])).unwrap();
+        writer.finish_frame().unwrap();
+        let first_frame_length = std::fs::metadata(&path).unwrap().len();
+        writer.write_all(&encode(&["packet 2\n"])).unwrap();
+        drop(writer);
+        // Simulate a crash half way through writing the second frame.
+        let second_frame_length = std::fs::metadata(&path).unwrap().len() - first_frame_length;
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_len(first_frame_length + second_frame_length / 2)
+            .unwrap();
+
+        let mut records = RecordReader::open(&path).unwrap();
+        let (_, first) = records.next_record().unwrap().unwrap();
+        records.seek_to(0).unwrap();
+        let (_, rewound) = records.next_record().unwrap().unwrap();
+
+        assert_eq!(first.message, "packet 1\n");
+        assert_eq!(rewound, first);
+        assert!(records.next_record().unwrap().is_none());
+        assert!(rebuild_time_index(&path, std::time::Duration::from_secs(1)).is_err());
+    }
+
+    #[test]
+    fn given_oversized_length_prefix_when_read_then_record_is_skipped() {
+        let mut bytes = vec![0x80, 0x80, 0x80, 0x01];
+        bytes.extend(encode(&["packet 1\n"]));
+
+        let (messages, skipped_bytes) = read_all(bytes);
+
+        assert_eq!(messages, ["packet 1\n"]);
+        assert_eq!(skipped_bytes, 4);
+    }
+}
@@ -0,0 +1,599 @@
+use crate::core::central_disk_logger::index::{TimeIndex, time_index_path};
+use crate::core::ingestor::config::ReplaySpeed;
+use crate::core::ingestor::errors;
+use crate::core::ingestor::recording::{RecordReader, Recording};
+use crate::core::ingestor::task::{APRSDataSource, AprsPacket};
+
+/// Longest a paused or throttled replay blocks before handing control back to the
+/// task loop, so that stop signals and replay commands stay responsive.
+const REPLAY_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// Point in a recording to continue the replay from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeekTarget {
+    /// First packet recorded at or after this time.
+    Timestamp(std::time::SystemTime),
+    /// Offset from the first packet in the recording.
+    Offset(std::time::Duration),
+}
+
+#[derive(Debug)]
+enum ReplayCommand {
+    SetSpeed(ReplaySpeed),
+    Pause,
+    Resume,
+    Seek(SeekTarget),
+}
+
+/// Controls a running [`ReplaySource`] from another thread.
+#[derive(Debug, Clone)]
+pub struct ReplayHandle {
+    sender: crossbeam_channel::Sender<ReplayCommand>,
+}
+impl ReplayHandle {
+    pub fn set_speed(&self, speed: ReplaySpeed) -> Result<(), errors::ReplayError> {
+        self.send(ReplayCommand::SetSpeed(speed.validate()?))
+    }
+
+    pub fn pause(&self) -> Result<(), errors::ReplayError> {
+        self.send(ReplayCommand::Pause)
+    }
+
+    pub fn resume(&self) -> Result<(), errors::ReplayError> {
+        self.send(ReplayCommand::Resume)
+    }
+
+    pub fn seek(&self, target: SeekTarget) -> Result<(), errors::ReplayError> {
+        self.send(ReplayCommand::Seek(target))
+    }
+
+    fn send(&self, command: ReplayCommand) -> Result<(), errors::ReplayError> {
+        self.sender
+            .send(command)
+            .map_err(|_| errors::ReplayError::SourceClosed)
+    }
+}
+
+/// Maps recording time onto wall-clock time: `recording_time` was due at `started_at`.
+#[derive(Debug, Clone, Copy)]
+struct ReplayClock {
+    started_at: std::time::Instant,
+    recording_time: std::time::SystemTime,
+}
+impl ReplayClock {
+    fn start(recording_time: std::time::SystemTime) -> Self {
+        Self {
+            started_at: std::time::Instant::now(),
+            recording_time,
+        }
+    }
+
+    fn recording_now(&self, multiplier: f64) -> std::time::SystemTime {
+        self.recording_time + self.started_at.elapsed().mul_f64(multiplier)
+    }
+
+    /// Wall-clock time left until a packet recorded at `timestamp` is due.
+    fn time_until(
+        &self,
+        timestamp: std::time::SystemTime,
+        multiplier: f64,
+    ) -> Option<std::time::Duration> {
+        // Packets recorded before the anchor (out of order) are played immediately.
+        let offset = timestamp.duration_since(self.recording_time).ok()?;
+        offset
+            .div_f64(multiplier)
+            .checked_sub(self.started_at.elapsed())
+            .filter(|remaining| !remaining.is_zero())
+    }
+}
+
+/// Plays back a recording made by the disk logger, pacing packets by their recorded
+/// timestamps at the configured [`ReplaySpeed`]. The file is streamed, so memory use
+/// does not grow with the size of the recording.
+pub struct ReplaySource {
+    records: RecordReader<Recording>,
+    time_index: Option<TimeIndex>,
+    command_sender: crossbeam_channel::Sender<ReplayCommand>,
+    commands: crossbeam_channel::Receiver<ReplayCommand>,
+    speed: ReplaySpeed,
+    paused: bool,
+    clock: Option<ReplayClock>,
+    recording_start: Option<std::time::SystemTime>,
+    last_read_timestamp: Option<std::time::SystemTime>,
+    seek_target: Option<SeekTarget>,
+    pending: Option<(Option<std::time::SystemTime>, AprsPacket)>,
+}
+impl ReplaySource {
+    /// Opens `input_path`, using its time index sidecar for seeking when there is one.
+    pub fn new(input_path: &std::path::Path) -> Result<Self, std::io::Error> {
+        let records = RecordReader::open(input_path)?;
+        let recording_length = std::fs::metadata(input_path)?.len();
+        let time_index = match TimeIndex::load(&time_index_path(input_path)) {
+            Ok(time_index) => Some(time_index.within(recording_length)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                log::info!(
+                    "No time index for {}, seeking will scan the recording",
+                    input_path.display()
+                );
+                None
+            }
+            Err(err) => {
+                log::warn!("Ignoring time index for {}: {err}", input_path.display());
+                None
+            }
+        };
+        let (command_sender, commands) = crossbeam_channel::unbounded();
+        Ok(Self {
+            records,
+            time_index,
+            command_sender,
+            commands,
+            speed: ReplaySpeed::default(),
+            paused: false,
+            clock: None,
+            recording_start: None,
+            last_read_timestamp: None,
+            seek_target: None,
+            pending: None,
+        })
+    }
+
+    #[must_use]
+    pub fn with_speed(mut self, speed: ReplaySpeed) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Starts the replay at `target` instead of the beginning of the recording.
+    pub fn starting_at(mut self, target: SeekTarget) -> Result<Self, std::io::Error> {
+        self.seek(target)?;
+        Ok(self)
+    }
+
+    #[must_use]
+    pub fn handle(&self) -> ReplayHandle {
+        ReplayHandle {
+            sender: self.command_sender.clone(),
+        }
+    }
+
+    fn apply_command(&mut self, command: ReplayCommand) -> Result<(), std::io::Error> {
+        match command {
+            ReplayCommand::SetSpeed(speed) => {
+                self.rebase_clock();
+                if speed == ReplaySpeed::Unthrottled {
+                    self.clock = None;
+                }
+                self.speed = speed;
+            }
+            ReplayCommand::Pause => {
+                self.rebase_clock();
+                self.paused = true;
+            }
+            ReplayCommand::Resume => {
+                if let Some(clock) = &mut self.clock {
+                    clock.started_at = std::time::Instant::now();
+                }
+                self.paused = false;
+            }
+            ReplayCommand::Seek(target) => self.seek(target)?,
+        }
+        Ok(())
+    }
+
+    /// Re-anchors the clock at the current replay position, so that a change of speed
+    /// or a pause only affects packets from now on.
+    fn rebase_clock(&mut self) {
+        if self.paused {
+            return;
+        }
+        if let (Some(clock), ReplaySpeed::Multiplier(multiplier)) = (&mut self.clock, self.speed) {
+            *clock = ReplayClock {
+                started_at: std::time::Instant::now(),
+                recording_time: clock.recording_now(multiplier),
+            };
+        }
+    }
+
+    fn seek(&mut self, target: SeekTarget) -> Result<(), std::io::Error> {
+        self.clock = None;
+        if self.recording_start.is_none() {
+            self.recording_start = self.read_recording_start()?;
+        }
+        let resolved = self.resolve(target);
+        let behind_cursor = match (resolved, self.last_read_timestamp) {
+            (Some(target_time), Some(last_read)) => target_time < last_read,
+            (None, _) => true,
+            (Some(_), None) => false,
+        };
+        let indexed_offset = resolved
+            .zip(self.time_index.as_ref())
+            .map(|(target_time, time_index)| time_index.offset_before(target_time));
+
+        let jump_to = match indexed_offset {
+            Some(offset) if behind_cursor || offset > self.records.offset() => Some(offset),
+            None if behind_cursor => Some(0),
+            _ => None,
+        };
+        if let Some(offset) = jump_to {
+            self.records.seek_to(offset)?;
+            self.last_read_timestamp = None;
+            self.pending = None;
+        } else if let Some((Some(pending_timestamp), _)) = &self.pending
+            && resolved.is_some_and(|target_time| *pending_timestamp < target_time)
+        {
+            self.pending = None;
+        }
+        self.seek_target = Some(target);
+        Ok(())
+    }
+
+    /// Reads the timestamp of the first record without moving the replay position.
+    fn read_recording_start(&mut self) -> Result<Option<std::time::SystemTime>, std::io::Error> {
+        let resume_at = self.records.offset();
+        self.records.seek_to(0)?;
+        let first_record = self.records.next_record()?;
+        self.records.seek_to(resume_at)?;
+        Ok(first_record
+            .and_then(|(_, packet)| packet.timestamp)
+            .and_then(|timestamp| std::time::SystemTime::try_from(timestamp).ok()))
+    }
+
+    fn resolve(&self, target: SeekTarget) -> Option<std::time::SystemTime> {
+        match target {
+            SeekTarget::Timestamp(timestamp) => Some(timestamp),
+            SeekTarget::Offset(offset) => self.recording_start.map(|start| start + offset),
+        }
+    }
+
+    fn next_record(
+        &mut self,
+    ) -> Result<(Option<std::time::SystemTime>, AprsPacket), errors::PacketError> {
+        loop {
+            let Some((_, pb_aprs_packet)) = self.records.next_record()? else {
+                return Err(errors::PacketError::Disconnected);
+            };
+
+            let timestamp = pb_aprs_packet
+                .timestamp
+                .and_then(|timestamp| std::time::SystemTime::try_from(timestamp).ok());
+            if timestamp.is_some() {
+                self.recording_start = self.recording_start.or(timestamp);
+                self.last_read_timestamp = timestamp;
+            }
+
+            if let Some(target) = self.seek_target {
+                let before_target = timestamp
+                    .zip(self.resolve(target))
+                    .is_some_and(|(timestamp, target_time)| timestamp < target_time);
+                if before_target {
+                    continue;
+                }
+                self.seek_target = None;
+            }
+
+            let packet = pb_aprs_packet
+                .try_into()
+                .map_err(errors::PacketError::Conversion)?;
+            return Ok((timestamp, packet));
+        }
+    }
+
+    fn time_until_due(&mut self, timestamp: std::time::SystemTime) -> Option<std::time::Duration> {
+        let ReplaySpeed::Multiplier(multiplier) = self.speed else {
+            return None;
+        };
+        match &self.clock {
+            Some(clock) => clock.time_until(timestamp, multiplier),
+            None => {
+                self.clock = Some(ReplayClock::start(timestamp));
+                None
+            }
+        }
+    }
+}
+impl APRSDataSource for ReplaySource {
+    fn create_aprs_packet(&mut self) -> Result<AprsPacket, errors::PacketError> {
+        loop {
+            while let Ok(command) = self.commands.try_recv() {
+                self.apply_command(command)?;
+            }
+
+            if self.paused {
+                match self.commands.recv_timeout(REPLAY_POLL_INTERVAL) {
+                    Ok(command) => self.apply_command(command)?,
+                    Err(_) => return Err(errors::PacketError::Idle),
+                }
+                continue;
+            }
+
+            let (timestamp, packet) = match self.pending.take() {
+                Some(pending) => pending,
+                None => self.next_record()?,
+            };
+
+            let Some(wait) = timestamp.and_then(|timestamp| self.time_until_due(timestamp)) else {
+                return Ok(packet);
+            };
+
+            self.pending = Some((timestamp, packet));
+            match self.commands.recv_timeout(wait.min(REPLAY_POLL_INTERVAL)) {
+                Ok(command) => self.apply_command(command)?,
+                Err(_) if wait > REPLAY_POLL_INTERVAL => return Err(errors::PacketError::Idle),
+                Err(_) => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+    use crate::core::ingestor::recording::rebuild_time_index;
+    use crate::core::ingestor::{Ingestor, PbAprsPacket};
+    use crate::core::thread_manager::{SteppableTask, TaskState};
+    use crate::test_utilities::{TestPath, test_path, write_pb_message_to_disk};
+
+    fn create_writer(
+        output_path: &std::path::Path,
+    ) -> Result<std::io::BufWriter<std::fs::File>, std::io::Error> {
+        std::fs::File::create_new(output_path).map(std::io::BufWriter::new)
+    }
+
+    /// Writes one packet per offset, labelled `packet <index>`, and returns the time of the first.
+    fn write_recording(path: &std::path::Path, offsets_millis: &[u64]) -> std::time::SystemTime {
+        let base_time = std::time::SystemTime::now();
+        let mut writer = create_writer(path).expect("Failed to create writer");
+        for (index, offset) in offsets_millis.iter().enumerate() {
+            let packet = PbAprsPacket {
+                timestamp: Some(prost_types::Timestamp::from(
+                    base_time + std::time::Duration::from_millis(*offset),
+                )),
+                message: format!("packet {}\n", index + 1).into(),
+            };
+            write_pb_message_to_disk(&mut writer, &packet).unwrap();
+        }
+        writer.flush().unwrap();
+        base_time
+    }
+
+    /// Steps the source until it yields a packet, skipping `Idle` results.
+    fn next_packet(source: &mut ReplaySource) -> Result<AprsPacket, errors::PacketError> {
+        loop {
+            match source.create_aprs_packet() {
+                Err(errors::PacketError::Idle) => {}
+                result => return result,
+            }
+        }
+    }
+
+    #[rstest::rstest]
+    #[test_log::test]
+    fn when_ingestor_reads_from_log_file_then_sender_receives_expected_aprs_packet(
+        test_path: TestPath,
+    ) {
+        let log_path = &test_path.path.join("test_ingestor_log.pb");
+
+        let now = std::time::SystemTime::now();
+        let timestamp = prost_types::Timestamp::from(now);
+        let expected_aprs_packet = PbAprsPacket {
+            timestamp: Some(timestamp),
+            message: "aprs\n".into(),
+        };
+
+        {
+            // explicitly flush writer and drop within closure
+            let mut writer = create_writer(log_path).expect("Failed to create writer");
+            let _ = write_pb_message_to_disk(&mut writer, &expected_aprs_packet);
+            writer.flush().unwrap();
+        }
+
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let source = ReplaySource::new(log_path).expect("Failed to create data source");
+
+        let mut ingestor = Ingestor::new(source, sender, None);
+        let mut cont = true;
+        while cont {
+            cont = matches!(ingestor.step(), TaskState::Running);
+        }
+
+        // drop ingestor to flush writer
+        drop(ingestor);
+
+        let vec: Vec<AprsPacket> = receiver.iter().collect();
+        assert!(vec.len() == 1);
+        assert_eq!(
+            *vec.first().unwrap(),
+            expected_aprs_packet.try_into().unwrap()
+        );
+    }
+
+    #[rstest::rstest]
+    fn when_reading_from_replay_source_then_delays_are_applied_correctly(test_path: TestPath) {
+        let log_path = test_path.path.join("test_replay_delay.pb");
+        write_recording(&log_path, &[0, 50, 100]);
+
+        let mut source = ReplaySource::new(&log_path).expect("Failed to open replay source");
+
+        let start = std::time::Instant::now();
+
+        let p1 = source.create_aprs_packet().unwrap();
+        assert_eq!(p1.message, "packet 1\n");
+        let elapsed_p1 = start.elapsed().as_millis();
+        assert!(elapsed_p1.abs_diff(0) <= 5);
+
+        let p2 = source.create_aprs_packet().unwrap();
+        assert_eq!(p2.message, "packet 2\n");
+        let elapsed_p2 = start.elapsed().as_millis();
+        assert!(elapsed_p2 >= 50);
+        assert!(elapsed_p2.abs_diff(50) <= 5);
+
+        let p3 = source.create_aprs_packet().unwrap();
+        assert_eq!(p3.message, "packet 3\n");
+        let elapsed_p3 = start.elapsed().as_millis();
+        assert!(elapsed_p3 >= 100);
+        assert!(elapsed_p3.abs_diff(100) <= 5);
+
+        let p4 = source.create_aprs_packet().is_err();
+        assert!(p4);
+    }
+
+    #[rstest::rstest]
+    fn given_recording_with_corrupted_and_truncated_records_when_replayed_then_valid_packets_are_emitted_and_replay_ends(
+        test_path: TestPath,
+    ) {
+        let log_path = test_path.path.join("corrupted.pb");
+        write_recording(&log_path, &[0, 1, 2]);
+        let mut bytes = std::fs::read(&log_path).unwrap();
+        let record_length = bytes.len() / 3;
+        bytes[record_length] = 0xff;
+        bytes.truncate(bytes.len() - 3);
+        std::fs::write(&log_path, bytes).unwrap();
+
+        let mut source = ReplaySource::new(&log_path)
+            .unwrap()
+            .with_speed(ReplaySpeed::Unthrottled);
+
+        assert_eq!(source.create_aprs_packet().unwrap().message, "packet 1\n");
+        assert!(matches!(
+            source.create_aprs_packet(),
+            Err(errors::PacketError::Disconnected)
+        ));
+    }
+
+    #[rstest::rstest]
+    fn given_double_speed_when_replaying_then_delays_are_halved(test_path: TestPath) {
+        let log_path = test_path.path.join("double_speed.pb");
+        write_recording(&log_path, &[0, 100]);
+        let mut source = ReplaySource::new(&log_path)
+            .unwrap()
+            .with_speed(ReplaySpeed::Multiplier(2.0));
+
+        let start = std::time::Instant::now();
+        next_packet(&mut source).unwrap();
+        next_packet(&mut source).unwrap();
+
+        let elapsed = start.elapsed().as_millis();
+        assert!(elapsed >= 50);
+        assert!(elapsed.abs_diff(50) <= 5);
+    }
+
+    #[rstest::rstest]
+    fn given_unthrottled_speed_when_replaying_then_packets_are_not_delayed(test_path: TestPath) {
+        let log_path = test_path.path.join("unthrottled.pb");
+        write_recording(&log_path, &[0, 60_000, 120_000]);
+        let mut source = ReplaySource::new(&log_path)
+            .unwrap()
+            .with_speed(ReplaySpeed::Unthrottled);
+
+        let start = std::time::Instant::now();
+        for _ in 0..3 {
+            source.create_aprs_packet().unwrap();
+        }
+
+        assert!(start.elapsed() < std::time::Duration::from_millis(50));
+        assert!(matches!(
+            source.create_aprs_packet(),
+            Err(errors::PacketError::Disconnected)
+        ));
+    }
+
+    #[rstest::rstest]
+    fn given_paused_replay_when_stepped_then_source_is_idle_until_resumed(test_path: TestPath) {
+        let log_path = test_path.path.join("paused.pb");
+        write_recording(&log_path, &[0, 10]);
+        let mut source = ReplaySource::new(&log_path).unwrap();
+        let handle = source.handle();
+
+        handle.pause().unwrap();
+        assert!(matches!(
+            source.create_aprs_packet(),
+            Err(errors::PacketError::Idle)
+        ));
+
+        handle.resume().unwrap();
+        assert_eq!(next_packet(&mut source).unwrap().message, "packet 1\n");
+    }
+
+    #[rstest::rstest]
+    #[case(SeekTarget::Offset(std::time::Duration::from_secs(60)))]
+    #[case(SeekTarget::Offset(std::time::Duration::from_secs(59)))]
+    fn given_seek_when_replaying_then_playback_continues_from_first_packet_at_or_after_target(
+        test_path: TestPath,
+        #[case] target: SeekTarget,
+    ) {
+        let log_path = test_path.path.join("seek.pb");
+        write_recording(&log_path, &[0, 30_000, 60_000, 90_000]);
+        let mut source = ReplaySource::new(&log_path)
+            .unwrap()
+            .with_speed(ReplaySpeed::Unthrottled);
+        let handle = source.handle();
+
+        assert_eq!(source.create_aprs_packet().unwrap().message, "packet 1\n");
+
+        handle.seek(target).unwrap();
+        assert_eq!(source.create_aprs_packet().unwrap().message, "packet 3\n");
+    }
+
+    #[rstest::rstest]
+    fn given_seek_to_earlier_timestamp_when_replaying_then_playback_rewinds(test_path: TestPath) {
+        let log_path = test_path.path.join("rewind.pb");
+        let base_time = write_recording(&log_path, &[0, 30_000, 60_000]);
+        let mut source = ReplaySource::new(&log_path)
+            .unwrap()
+            .with_speed(ReplaySpeed::Unthrottled);
+        let handle = source.handle();
+
+        for _ in 0..3 {
+            source.create_aprs_packet().unwrap();
+        }
+        handle
+            .seek(SeekTarget::Timestamp(
+                base_time + std::time::Duration::from_secs(30),
+            ))
+            .unwrap();
+
+        assert_eq!(source.create_aprs_packet().unwrap().message, "packet 2\n");
+    }
+
+    #[rstest::rstest]
+    fn given_indexed_recording_when_started_at_timestamp_then_first_packet_is_first_at_or_after_it(
+        test_path: TestPath,
+    ) {
+        let log_path = test_path.path.join("indexed.pb");
+        let base_time = write_recording(&log_path, &[0, 10_000, 20_000, 30_000, 40_000]);
+        rebuild_time_index(&log_path, std::time::Duration::from_secs(10)).unwrap();
+
+        let mut source = ReplaySource::new(&log_path)
+            .unwrap()
+            .with_speed(ReplaySpeed::Unthrottled)
+            .starting_at(SeekTarget::Timestamp(
+                base_time + std::time::Duration::from_secs(25),
+            ))
+            .unwrap();
+
+        assert!(source.time_index.is_some());
+        assert_eq!(source.create_aprs_packet().unwrap().message, "packet 4\n");
+    }
+
+    #[rstest::rstest]
+    fn given_dropped_source_when_handle_used_then_returns_source_closed(test_path: TestPath) {
+        let log_path = test_path.path.join("closed.pb");
+        write_recording(&log_path, &[0]);
+        let source = ReplaySource::new(&log_path).unwrap();
+        let handle = source.handle();
+
+        assert!(matches!(
+            handle.set_speed(ReplaySpeed::Multiplier(0.0)),
+            Err(errors::ReplayError::InvalidSpeed(_))
+        ));
+
+        drop(source);
+        assert!(matches!(
+            handle.pause(),
+            Err(errors::ReplayError::SourceClosed)
+        ));
+    }
+}
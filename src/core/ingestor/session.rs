@@ -0,0 +1,323 @@
+use crate::core::ingestor::config::SessionConfig;
+use crate::core::ingestor::errors::SessionError;
+
+/// Comment line sent by the client to keep the APRS-IS connection alive.
+pub const CLIENT_KEEPALIVE: &[u8] = b"#keepalive\r\n";
+
+/// Software name and version reported to the server in the login line.
+pub const CLIENT_NAME: &str = "AirspaceRadar";
+pub const CLIENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Callsign and passcode used for anonymous, receive-only logins.
+pub const RECEIVE_ONLY_CALLSIGN: &str = "N0CALL";
+pub const RECEIVE_ONLY_PASSCODE: i32 = -1;
+
+/// Computes the APRS-IS passcode for `callsign`. The SSID (anything after `-`) is
+/// ignored and the callsign is hashed case-insensitively.
+#[must_use]
+pub fn aprs_is_passcode(callsign: &str) -> i32 {
+    let base_callsign = callsign.split('-').next().unwrap_or_default();
+
+    let mut hash: u16 = 0x73e2;
+    for pair in base_callsign.to_ascii_uppercase().as_bytes().chunks(2) {
+        hash ^= u16::from(pair[0]) << 8;
+        if let Some(&low) = pair.get(1) {
+            hash ^= u16::from(low);
+        }
+    }
+    i32::from(hash & 0x7fff)
+}
+
+/// Identity presented to the APRS-IS server when logging in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoginCredentials {
+    pub callsign: String,
+    pub passcode: i32,
+}
+impl LoginCredentials {
+    /// Credentials for `callsign` with its passcode computed by [`aprs_is_passcode`].
+    #[must_use]
+    pub fn for_callsign(callsign: &str) -> Self {
+        Self {
+            callsign: callsign.to_ascii_uppercase(),
+            passcode: aprs_is_passcode(callsign),
+        }
+    }
+
+    /// Whether the server is expected to answer with `verified` in its `logresp`.
+    #[must_use]
+    pub fn expects_verification(&self) -> bool {
+        self.passcode != RECEIVE_ONLY_PASSCODE
+    }
+
+    /// Builds the login line, e.g.
+    /// `user N0CALL pass -1 vers AirspaceRadar 0.1.0 filter r/0/0/100\r\n`.
+    #[must_use]
+    pub fn login_line(&self, filter: &str) -> String {
+        format!(
+            "user {} pass {} vers {CLIENT_NAME} {CLIENT_VERSION} filter {filter}\r\n",
+            self.callsign, self.passcode
+        )
+    }
+}
+impl Default for LoginCredentials {
+    fn default() -> Self {
+        Self {
+            callsign: RECEIVE_ONLY_CALLSIGN.to_string(),
+            passcode: RECEIVE_ONLY_PASSCODE,
+        }
+    }
+}
+
+/// The server's reply to our `user ... pass ...` login line, e.g.
+/// `# logresp N0CALL unverified, server GLIDERN2`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoginResponse {
+    pub callsign: String,
+    pub verified: bool,
+    pub server: Option<String>,
+}
+
+/// Server-side comment lines (prefixed with `#`), typed by their role in the session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionEvent {
+    /// Software banner sent by the server before the login response.
+    ServerBanner(String),
+    LoginResponse(LoginResponse),
+    /// Periodic comment the server emits after login to show it is still alive.
+    ServerKeepalive(String),
+}
+
+/// Tracks the state of a single APRS-IS connection: login verification, when our
+/// last keepalive went out and when the server was last heard from.
+#[derive(Debug)]
+pub struct AprsIsSession {
+    credentials: LoginCredentials,
+    keepalive_interval: std::time::Duration,
+    stall_timeout: std::time::Duration,
+    login_response: Option<LoginResponse>,
+    last_server_activity: std::time::Instant,
+    last_keepalive_sent: std::time::Instant,
+}
+impl AprsIsSession {
+    #[must_use]
+    pub fn new(credentials: &LoginCredentials, config: &SessionConfig) -> Self {
+        let now = std::time::Instant::now();
+        Self {
+            credentials: credentials.clone(),
+            keepalive_interval: std::time::Duration::from_secs(config.keepalive_interval_seconds),
+            stall_timeout: std::time::Duration::from_secs(config.stall_timeout_seconds),
+            login_response: None,
+            last_server_activity: now,
+            last_keepalive_sent: now,
+        }
+    }
+
+    #[must_use]
+    pub fn login_response(&self) -> Option<&LoginResponse> {
+        self.login_response.as_ref()
+    }
+
+    pub fn record_server_activity(&mut self) {
+        self.last_server_activity = std::time::Instant::now();
+    }
+
+    /// Returns how long the server has been silent, if that exceeds the stall timeout.
+    #[must_use]
+    pub fn stalled_for(&self) -> Option<std::time::Duration> {
+        let silence = self.last_server_activity.elapsed();
+        (silence > self.stall_timeout).then_some(silence)
+    }
+
+    #[must_use]
+    pub fn keepalive_due(&self) -> bool {
+        self.last_keepalive_sent.elapsed() >= self.keepalive_interval
+    }
+
+    pub fn record_keepalive_sent(&mut self) {
+        self.last_keepalive_sent = std::time::Instant::now();
+    }
+
+    /// Classifies a server comment (with the leading `#` already stripped).
+    pub fn handle_comment(&mut self, comment: &str) -> Result<SessionEvent, SessionError> {
+        let comment = comment.trim();
+        if let Some(response) = comment.strip_prefix("logresp") {
+            let login_response = parse_login_response(response)?;
+            if !login_response
+                .callsign
+                .eq_ignore_ascii_case(&self.credentials.callsign)
+            {
+                return Err(SessionError::CallsignMismatch {
+                    expected: self.credentials.callsign.clone(),
+                    actual: login_response.callsign,
+                });
+            }
+            if self.credentials.expects_verification() && !login_response.verified {
+                log::warn!(
+                    "APRS-IS login for {} was not verified, check the configured passcode",
+                    login_response.callsign
+                );
+            }
+            self.login_response = Some(login_response.clone());
+            return Ok(SessionEvent::LoginResponse(login_response));
+        }
+
+        if self.login_response.is_some() {
+            Ok(SessionEvent::ServerKeepalive(comment.to_string()))
+        } else {
+            Ok(SessionEvent::ServerBanner(comment.to_string()))
+        }
+    }
+}
+
+fn parse_login_response(response: &str) -> Result<LoginResponse, SessionError> {
+    let malformed = || SessionError::MalformedLoginResponse(response.trim().to_string());
+
+    let mut tokens = response.split_whitespace();
+    let callsign = tokens.next().ok_or_else(malformed)?.to_string();
+    let verified = match tokens.next().map(|status| status.trim_end_matches(',')) {
+        Some("verified") => true,
+        Some("unverified") => false,
+        _ => return Err(malformed()),
+    };
+    let server = match (tokens.next(), tokens.next()) {
+        (Some("server"), Some(name)) => Some(name.to_string()),
+        _ => None,
+    };
+
+    Ok(LoginResponse {
+        callsign,
+        verified,
+        server,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(config: &SessionConfig) -> AprsIsSession {
+        AprsIsSession::new(&LoginCredentials::default(), config)
+    }
+
+    #[rstest::rstest]
+    #[case("N0CALL", 13023)]
+    #[case("n0call", 13023)]
+    #[case("N0CALL-10", 13023)]
+    #[case("W1AW", 25988)]
+    #[case("OGN123", 16551)]
+    fn given_callsign_when_passcode_computed_then_matches_aprs_is_hash(
+        #[case] callsign: &str,
+        #[case] expected: i32,
+    ) {
+        assert_eq!(aprs_is_passcode(callsign), expected);
+    }
+
+    #[test]
+    fn given_default_credentials_when_login_line_built_then_logs_in_receive_only() {
+        let credentials = LoginCredentials::default();
+
+        assert_eq!(
+            credentials.login_line("r/0/0/100"),
+            format!("user N0CALL pass -1 vers AirspaceRadar {CLIENT_VERSION} filter r/0/0/100\r\n")
+        );
+        assert!(!credentials.expects_verification());
+    }
+
+    #[test]
+    fn given_callsign_credentials_when_login_line_built_then_includes_callsign_passcode_and_crate_version()
+     {
+        let credentials = LoginCredentials::for_callsign("w1aw-5");
+
+        assert_eq!(
+            credentials.login_line("p/W1"),
+            format!(
+                "user W1AW-5 pass 25988 vers AirspaceRadar {} filter p/W1\r\n",
+                env!("CARGO_PKG_VERSION")
+            )
+        );
+        assert!(credentials.expects_verification());
+    }
+
+    #[rstest::rstest]
+    #[case(
+        " logresp N0CALL unverified, server GLIDERN2",
+        LoginResponse { callsign: "N0CALL".into(), verified: false, server: Some("GLIDERN2".into()) }
+    )]
+    #[case(
+        "logresp n0call verified",
+        LoginResponse { callsign: "n0call".into(), verified: true, server: None }
+    )]
+    fn given_logresp_comment_when_handled_then_login_response_is_parsed(
+        #[case] comment: &str,
+        #[case] expected: LoginResponse,
+    ) {
+        let mut session = session(&SessionConfig::default());
+
+        let event = session.handle_comment(comment).unwrap();
+
+        assert_eq!(event, SessionEvent::LoginResponse(expected.clone()));
+        assert_eq!(session.login_response(), Some(&expected));
+    }
+
+    #[test]
+    fn given_logresp_for_other_callsign_when_handled_then_returns_mismatch_error() {
+        let mut session = session(&SessionConfig::default());
+
+        let result = session.handle_comment("logresp OTHER verified, server GLIDERN1");
+
+        assert!(matches!(
+            result,
+            Err(SessionError::CallsignMismatch { actual, .. }) if actual == "OTHER"
+        ));
+        assert!(session.login_response().is_none());
+    }
+
+    #[test]
+    fn given_logresp_without_status_when_handled_then_returns_malformed_error() {
+        let mut session = session(&SessionConfig::default());
+
+        assert!(matches!(
+            session.handle_comment("logresp N0CALL"),
+            Err(SessionError::MalformedLoginResponse(_))
+        ));
+    }
+
+    #[test]
+    fn given_server_comments_when_handled_then_comments_before_login_are_banners_and_after_are_keepalives()
+     {
+        let mut session = session(&SessionConfig::default());
+
+        let banner = session.handle_comment(" aprsc 2.1.14-g5e130d9").unwrap();
+        session
+            .handle_comment(" logresp N0CALL unverified, server GLIDERN2")
+            .unwrap();
+        let keepalive = session
+            .handle_comment(" aprsc 2.1.14-g5e130d9 22 Mar 2026 10:00:00 GMT GLIDERN2")
+            .unwrap();
+
+        assert_eq!(
+            banner,
+            SessionEvent::ServerBanner("aprsc 2.1.14-g5e130d9".into())
+        );
+        assert!(matches!(keepalive, SessionEvent::ServerKeepalive(_)));
+    }
+
+    #[test]
+    fn given_zero_timeouts_when_time_passes_then_stall_is_detected_and_keepalive_is_due() {
+        let config = SessionConfig {
+            keepalive_interval_seconds: 0,
+            stall_timeout_seconds: 0,
+        };
+        let session = session(&config);
+        std::thread::sleep(std::time::Duration::from_millis(2));
+
+        assert!(session.keepalive_due());
+        assert!(session.stalled_for().is_some());
+
+        let default_session =
+            AprsIsSession::new(&LoginCredentials::default(), &SessionConfig::default());
+        assert!(!default_session.keepalive_due());
+        assert!(default_session.stalled_for().is_none());
+    }
+}
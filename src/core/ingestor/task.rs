@@ -1,69 +1,84 @@
-use std::net::ToSocketAddrs;
-
-use prost::Message;
-
 use crate::core::central_disk_logger::{LogSender, ProtoLoggerHandle};
-use crate::core::ingestor::config::GliderNetConfig;
+use crate::core::channel::ChannelSender;
+use crate::core::ingestor::config::{GliderNetConfig, ReplaySpeed, SessionConfig};
 use crate::core::ingestor::errors;
 use crate::core::ingestor::protobuf::PbAprsPacket;
+use crate::core::ingestor::reconnect::{
+    Connect, GliderNetConnector, ReconnectMonitor, ReconnectingSource,
+};
+use crate::core::ingestor::replay::{ReplayHandle, ReplaySource};
+use crate::core::ingestor::session::{
+    AprsIsSession, CLIENT_KEEPALIVE, LoginCredentials, SessionEvent,
+};
 use crate::core::thread_manager::{SteppableTask, TaskState};
 
 pub const INGESTOR_CONNECTION_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+/// Socket read timeout; each expiry is an opportunity to send keepalives and check for stalls.
+pub const INGESTOR_READ_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
 
 pub struct Ingestor {
+    name: SourceName,
     source: Box<dyn APRSDataSource>,
-    sender: crossbeam_channel::Sender<AprsPacket>,
+    sender: ChannelSender<AprsPacket>,
     logger: Option<ProtoLoggerHandle<PbAprsPacket>>,
 }
 impl Ingestor {
     pub fn new<C: APRSDataSource + 'static>(
         source: C,
-        sender: crossbeam_channel::Sender<AprsPacket>,
+        sender: impl Into<ChannelSender<AprsPacket>>,
         logger: Option<ProtoLoggerHandle<PbAprsPacket>>,
     ) -> Self {
         Self {
+            name: SourceName::default(),
             source: Box::new(source),
-            sender,
+            sender: sender.into(),
             logger,
         }
     }
 
+    /// Tags every packet produced by this ingestor with `name`.
+    #[must_use]
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Replays a recording made with the disk logger, returning a handle that controls
+    /// the playback speed and position.
     pub fn read_data_from_file(
         read_path: &std::path::Path,
-        sender: crossbeam_channel::Sender<AprsPacket>,
+        speed: ReplaySpeed,
+        sender: impl Into<ChannelSender<AprsPacket>>,
         logger: Option<ProtoLoggerHandle<PbAprsPacket>>,
-    ) -> Result<Self, std::io::Error> {
+    ) -> Result<(Self, ReplayHandle), std::io::Error> {
         log::info!(
             "Reading APRS data from file: {}",
             read_path.to_string_lossy()
         );
-        let source = ReplaySource::new(read_path)?;
-        Ok(Self::new(source, sender, logger))
+        let source = ReplaySource::new(read_path)?.with_speed(speed);
+        let handle = source.handle();
+        Ok((Self::new(source, sender, logger), handle))
     }
 
+    /// Connects to an APRS-IS server, reconnecting as by `config.reconnect`, counting
+    /// reconnects in `reconnect_monitor` and forwarding server comments to `session_events`.
     pub fn connect_glidernet(
         config: &GliderNetConfig,
-        sender: crossbeam_channel::Sender<AprsPacket>,
+        sender: impl Into<ChannelSender<AprsPacket>>,
         logger: Option<ProtoLoggerHandle<PbAprsPacket>>,
+        reconnect_monitor: ReconnectMonitor,
+        session_events: crossbeam_channel::Sender<SessionEvent>,
     ) -> Result<Self, std::io::Error> {
         log::info!("Connecting to TCP stream.");
 
-        let address_str = format!("{}:{}", config.host, config.port);
-
-        let mut resolved_address = address_str.to_socket_addrs()?;
-
-        let address = resolved_address.next().ok_or_else(|| {
-            std::io::Error::new(std::io::ErrorKind::NotFound, "Could not resolve host")
-        })?;
-
-        let mut stream =
-            std::net::TcpStream::connect_timeout(&address, INGESTOR_CONNECTION_TIMEOUT)?;
-
-        let _ = stream.set_read_timeout(Some(std::time::Duration::from_secs(5)));
-
-        authentication_handshake(&mut stream, &config.filter)?;
+        let mut connector = GliderNetConnector::new(config)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+        let stream = connector.connect()?;
 
-        let source = LiveSource::new(stream);
+        let source =
+            ReconnectingSource::new(connector, stream, &config.reconnect, config.session.clone())
+                .with_monitor(reconnect_monitor)
+                .with_session_events(session_events);
 
         Ok(Self::new(source, sender, logger))
     }
@@ -72,7 +87,8 @@ impl Ingestor {
 impl SteppableTask for Ingestor {
     fn step(&mut self) -> TaskState {
         match self.source.create_aprs_packet() {
-            Ok(aprs_packet) => {
+            Ok(mut aprs_packet) => {
+                aprs_packet.source = self.name.clone();
                 if let Some(logger) = &self.logger {
                     let _ = logger.send(aprs_packet.clone());
                 }
@@ -88,126 +104,146 @@ impl SteppableTask for Ingestor {
                 log::error!("Stream disconnected");
                 TaskState::Completed
             }
+            Err(errors::PacketError::Reconnecting | errors::PacketError::Idle) => {
+                TaskState::Running
+            }
+            // A login check that failed would fail again on reconnect, so the connection is
+            // given up like an exhausted retry budget.
+            Err(
+                err @ (errors::PacketError::RetryBudgetExhausted(_)
+                | errors::PacketError::Session(_)),
+            ) => TaskState::Errored(Box::new(err)),
             Err(err) => {
                 log::error!("{err}");
                 TaskState::Running
             }
         }
     }
+
+    fn name(&self) -> String {
+        if self.name.is_empty() {
+            "Ingestor".to_string()
+        } else {
+            format!("Ingestor({})", self.name)
+        }
+    }
 }
+/// Name of the ingest source a packet was received from.
+pub type SourceName = std::sync::Arc<str>;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct AprsPacket {
     pub timestamp: std::time::SystemTime,
     pub message: bytes::Bytes,
+    pub source: SourceName,
 }
 
 pub trait APRSDataSource: Send {
     fn create_aprs_packet(&mut self) -> Result<AprsPacket, errors::PacketError>;
 }
 
-struct LiveSource<R: std::io::Read> {
-    pub reader: std::io::BufReader<R>,
+pub struct LiveSource<S: std::io::Read + std::io::Write> {
+    pub reader: std::io::BufReader<S>,
+    line_buffer: Vec<u8>,
+    session: AprsIsSession,
+    session_events: Option<crossbeam_channel::Sender<SessionEvent>>,
 }
-impl<R: std::io::Read> LiveSource<R> {
-    pub fn new(tcp_stream: R) -> Self {
+impl<S: std::io::Read + std::io::Write> LiveSource<S> {
+    pub fn new(tcp_stream: S) -> Self {
+        Self::with_session(
+            tcp_stream,
+            AprsIsSession::new(&LoginCredentials::default(), &SessionConfig::default()),
+        )
+    }
+
+    pub fn with_session(tcp_stream: S, session: AprsIsSession) -> Self {
         Self {
             reader: std::io::BufReader::new(tcp_stream),
+            line_buffer: Vec::new(),
+            session,
+            session_events: None,
         }
     }
-}
-impl<R: std::io::Read + Send> APRSDataSource for LiveSource<R> {
-    fn create_aprs_packet(&mut self) -> Result<AprsPacket, errors::PacketError> {
-        let mut buffer = Vec::new();
-        match std::io::BufRead::read_until(&mut self.reader, b'\n', &mut buffer) {
-            Ok(bytes_read) => {
-                if bytes_read == 0 {
-                    log::info!("End of TCP stream");
-                    return Err(errors::PacketError::Disconnected);
-                }
 
-                let timestamp = std::time::SystemTime::now();
+    /// Forwards every server comment, as a typed [`SessionEvent`], to `sender`.
+    #[must_use]
+    pub fn with_session_events(mut self, sender: crossbeam_channel::Sender<SessionEvent>) -> Self {
+        self.session_events = Some(sender);
+        self
+    }
 
-                let packet = AprsPacket {
-                    timestamp,
-                    message: buffer.into(),
-                };
-                Ok(packet)
-            }
-            Err(error) => {
-                let packet_error = errors::PacketError::IoError(error);
-                log::error!("{packet_error}");
-                Err(packet_error)
-            }
+    fn send_keepalive_if_due(&mut self) -> Result<(), errors::PacketError> {
+        if self.session.keepalive_due() {
+            let stream = self.reader.get_mut();
+            stream.write_all(CLIENT_KEEPALIVE)?;
+            stream.flush()?;
+            self.session.record_keepalive_sent();
         }
+        Ok(())
     }
-}
 
-struct ReplaySource {
-    cursor: std::io::Cursor<Vec<u8>>,
-    first_packet_timestamp: Option<std::time::SystemTime>,
-    first_replay_time: Option<std::time::Instant>,
-}
-impl ReplaySource {
-    pub fn new(input_path: &std::path::Path) -> Result<Self, std::io::Error> {
-        let bytes = std::fs::read(input_path)?;
-        let cursor = std::io::Cursor::new(bytes);
-        Ok(Self {
-            cursor,
-            first_packet_timestamp: None,
-            first_replay_time: None,
-        })
+    fn handle_server_comment(&mut self, line: &[u8]) -> Result<(), errors::PacketError> {
+        let comment = String::from_utf8_lossy(&line[1..]);
+        let event = self.session.handle_comment(&comment)?;
+        if let Some(sender) = &self.session_events {
+            let _ = sender.send(event);
+        }
+        Ok(())
     }
 }
-impl APRSDataSource for ReplaySource {
+impl<S: std::io::Read + std::io::Write + Send> APRSDataSource for LiveSource<S> {
     fn create_aprs_packet(&mut self) -> Result<AprsPacket, errors::PacketError> {
-        let position = usize::try_from(self.cursor.position())
-            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+        loop {
+            self.send_keepalive_if_due()?;
 
-        if position >= self.cursor.get_ref().len() {
-            return Err(errors::PacketError::Disconnected);
-        }
+            // A read timeout can interrupt a line half way, so partial data is kept in
+            // `line_buffer` until the rest of the line arrives.
+            match std::io::BufRead::read_until(&mut self.reader, b'\n', &mut self.line_buffer) {
+                Ok(0) => {
+                    log::info!("End of TCP stream");
+                    return Err(errors::PacketError::Disconnected);
+                }
+                Ok(_) => {
+                    self.session.record_server_activity();
+                    let line = std::mem::take(&mut self.line_buffer);
+                    if line.first() == Some(&b'#') {
+                        self.handle_server_comment(&line)?;
+                        continue;
+                    }
 
-        match PbAprsPacket::decode_length_delimited(&mut self.cursor) {
-            Ok(pb_aprs_packet) => {
-                if let Some(packet_timestamp) = pb_aprs_packet.timestamp
-                    && let Ok(packet_system_time) =
-                        std::time::SystemTime::try_from(packet_timestamp)
+                    return Ok(AprsPacket {
+                        timestamp: std::time::SystemTime::now(),
+                        message: line.into(),
+                        source: SourceName::default(),
+                    });
+                }
+                Err(error)
+                    if matches!(
+                        error.kind(),
+                        std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                    ) =>
                 {
-                    // set first_replay_time and first_packet_timestamp with the first packet
-                    let start_time = *self
-                        .first_replay_time
-                        .get_or_insert_with(std::time::Instant::now);
-                    let first_time = *self
-                        .first_packet_timestamp
-                        .get_or_insert(packet_system_time);
-
-                    // Calculate the duration from the first packet to current packet
-                    if let Ok(target_offset) = packet_system_time.duration_since(first_time) {
-                        let elapsed = start_time.elapsed();
-
-                        if target_offset > elapsed {
-                            std::thread::sleep(target_offset.checked_sub(elapsed).unwrap());
-                        }
-                    }
+                    return match self.session.stalled_for() {
+                        Some(silence) => Err(errors::PacketError::Stalled(silence)),
+                        None => Err(errors::PacketError::Idle),
+                    };
+                }
+                Err(error) => {
+                    let packet_error = errors::PacketError::IoError(error);
+                    log::error!("{packet_error}");
+                    return Err(packet_error);
                 }
-                pb_aprs_packet
-                    .try_into()
-                    .map_err(errors::PacketError::Conversion)
-            }
-            Err(error) => {
-                let decode_error = errors::PacketError::DecodeReadError(error);
-                log::error!("{decode_error}");
-                Err(decode_error)
             }
         }
     }
 }
 
-fn authentication_handshake<W: std::io::Write>(
+pub(crate) fn authentication_handshake<W: std::io::Write>(
     writer: &mut W,
+    credentials: &LoginCredentials,
     filter: &str,
 ) -> std::io::Result<()> {
-    let login = format!("user N0CALL pass -1 vers AirspaceRadar 0.1.0 filter {filter}\r\n",);
+    let login = credentials.login_line(filter);
     writer.write_all(login.as_bytes())?;
     writer.flush()?;
     Ok(())
@@ -215,14 +251,13 @@ fn authentication_handshake<W: std::io::Write>(
 
 #[cfg(test)]
 mod test {
-    use std::io::Write;
-
-    use rstest;
-
-    use crate::core::ingestor::task::{APRSDataSource, Ingestor, LiveSource, ReplaySource};
-    use crate::core::ingestor::task::{AprsPacket, PbAprsPacket};
+    use crate::core::ingestor::config::SessionConfig;
+    use crate::core::ingestor::errors::{PacketError, SessionError};
+    use crate::core::ingestor::session::{
+        AprsIsSession, CLIENT_KEEPALIVE, LoginCredentials, SessionEvent,
+    };
+    use crate::core::ingestor::task::{APRSDataSource, AprsPacket, Ingestor, LiveSource};
     use crate::core::thread_manager::{SteppableTask, TaskState};
-    use crate::test_utilities::{TestPath, test_path, write_pb_message_to_disk};
 
     struct MockStream {
         incoming_data: std::io::Cursor<Vec<u8>>,
@@ -285,11 +320,37 @@ mod test {
         }
     }
 
-    fn create_writer(
-        output_path: &std::path::Path,
-    ) -> Result<std::io::BufWriter<std::fs::File>, std::io::Error> {
-        std::fs::File::create_new(output_path).map(std::io::BufWriter::new)
+    impl std::io::Write for MockStatefulStream {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    struct MockSilentStream;
+
+    impl std::io::Read for MockSilentStream {
+        fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::WouldBlock,
+                "simulated read timeout",
+            ))
+        }
     }
+
+    impl std::io::Write for MockSilentStream {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
     #[test]
     fn given_connection_to_stream_when_data_received_then_ingestor_sends_correct_data_and_keeps_running()
      {
@@ -350,100 +411,96 @@ mod test {
         assert!(receiver.try_recv().is_err(), "Channel should be empty");
     }
 
-    #[rstest::rstest]
-    #[test_log::test]
-    fn when_ingestor_reads_from_log_file_then_sender_receives_expected_aprs_packet(
-        test_path: TestPath,
-    ) {
-        let log_path = &test_path.path.join("test_ingestor_log.pb");
-
-        let now = std::time::SystemTime::now();
-        let timestamp = prost_types::Timestamp::from(now);
-        let expected_aprs_packet = PbAprsPacket {
-            timestamp: Some(timestamp),
-            message: "aprs\n".into(),
-        };
-
-        {
-            // explicitly flush writer and drop within closure
-            let mut writer = create_writer(log_path).expect("Failed to create writer");
-            let _ = write_pb_message_to_disk(&mut writer, &expected_aprs_packet);
-            writer.flush().unwrap();
-        }
-
+    #[test]
+    fn given_server_comments_in_stream_when_stepped_then_only_packets_are_forwarded_and_events_are_published()
+     {
         let (sender, receiver) = crossbeam_channel::unbounded();
-        let source = ReplaySource::new(log_path).expect("Failed to create data source");
-
+        let (event_sender, event_receiver) = crossbeam_channel::unbounded();
+        let data = "# aprsc 2.1.14-g5e130d9\r\n\
+                    # logresp N0CALL unverified, server GLIDERN2\r\n\
+                    APRS_PACKET_DATA\n\
+                    # aprsc 2.1.14-g5e130d9 22 Mar 2026 10:00:00 GMT GLIDERN2\r\n";
+        let source = LiveSource::new(MockStream::new(data)).with_session_events(event_sender);
         let mut ingestor = Ingestor::new(source, sender, None);
-        let mut cont = true;
-        while cont {
-            cont = matches!(ingestor.step(), TaskState::Running);
-        }
 
-        // drop ingestor to flush writer
-        drop(ingestor);
+        assert!(matches!(ingestor.step(), TaskState::Running));
+        assert!(matches!(ingestor.step(), TaskState::Completed));
 
-        let vec: Vec<AprsPacket> = receiver.iter().collect();
-        assert!(vec.len() == 1);
-        assert_eq!(
-            *vec.first().unwrap(),
-            expected_aprs_packet.try_into().unwrap()
-        );
+        let packets: Vec<AprsPacket> = receiver.try_iter().collect();
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0].message, "APRS_PACKET_DATA\n");
+
+        let events: Vec<SessionEvent> = event_receiver.try_iter().collect();
+        assert!(matches!(events[0], SessionEvent::ServerBanner(_)));
+        assert!(matches!(
+            &events[1],
+            SessionEvent::LoginResponse(response) if !response.verified
+        ));
+        assert!(matches!(events[2], SessionEvent::ServerKeepalive(_)));
     }
 
-    #[rstest::rstest]
-    fn when_reading_from_replay_source_then_delays_are_applied_correctly(test_path: TestPath) {
-        let log_path = test_path.path.join("test_replay_delay.pb");
+    #[test]
+    fn given_logresp_for_other_callsign_when_stepped_then_source_returns_session_error() {
+        let data = "# logresp SOMEONE verified, server GLIDERN2\r\n";
+        let mut source = LiveSource::new(MockStream::new(data));
+
+        assert!(matches!(
+            source.create_aprs_packet(),
+            Err(PacketError::Session(SessionError::CallsignMismatch { .. }))
+        ));
+    }
 
-        let base_time = std::time::SystemTime::now();
-        let time_p1 = base_time;
-        let time_p2 = base_time + std::time::Duration::from_millis(50);
-        let time_p3 = base_time + std::time::Duration::from_millis(100);
+    #[test]
+    fn given_logresp_for_other_callsign_when_ingestor_steps_then_it_errors() {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let data = "# logresp SOMEONE verified, server GLIDERN2\r\nAPRS_PACKET_DATA\n";
+        let mut ingestor = Ingestor::new(LiveSource::new(MockStream::new(data)), sender, None);
 
-        let packet1 = PbAprsPacket {
-            timestamp: Some(prost_types::Timestamp::from(time_p1)),
-            message: "packet 1\n".into(),
-        };
-        let packet2 = PbAprsPacket {
-            timestamp: Some(prost_types::Timestamp::from(time_p2)),
-            message: "packet 2\n".into(),
+        let TaskState::Errored(err) = ingestor.step() else {
+            panic!("a failed logresp check ends the ingestor");
         };
-        let packet3 = PbAprsPacket {
-            timestamp: Some(prost_types::Timestamp::from(time_p3)),
-            message: "packet 3\n".into(),
-        };
-
-        // Write the packets to the mock log file
-        {
-            let mut writer = create_writer(&log_path).expect("Failed to create writer");
-            write_pb_message_to_disk(&mut writer, &packet1).unwrap();
-            write_pb_message_to_disk(&mut writer, &packet2).unwrap();
-            write_pb_message_to_disk(&mut writer, &packet3).unwrap();
-            writer.flush().unwrap();
-        }
 
-        let mut source = ReplaySource::new(&log_path).expect("Failed to open replay source");
-
-        let start = std::time::Instant::now();
-
-        let p1 = source.create_aprs_packet().unwrap();
-        assert_eq!(p1.message, "packet 1\n");
-        let elapsed_p1 = start.elapsed().as_millis();
-        assert!(elapsed_p1.abs_diff(0) <= 5);
+        assert!(err.to_string().contains("SOMEONE"), "{err}");
+        assert!(receiver.try_recv().is_err(), "Channel should be empty");
+    }
 
-        let p2 = source.create_aprs_packet().unwrap();
-        assert_eq!(p2.message, "packet 2\n");
-        let elapsed_p2 = start.elapsed().as_millis();
-        assert!(elapsed_p2 >= 50);
-        assert!(elapsed_p2.abs_diff(50) <= 5);
+    #[test]
+    fn given_keepalive_interval_elapsed_when_stepped_then_keepalive_is_written_to_server() {
+        let mock_stream = MockStream::new("APRS_PACKET_DATA\n");
+        let outgoing_data = mock_stream.outgoing_data.clone();
+        let config = SessionConfig {
+            keepalive_interval_seconds: 0,
+            ..SessionConfig::default()
+        };
+        let mut source = LiveSource::with_session(
+            mock_stream,
+            AprsIsSession::new(&LoginCredentials::default(), &config),
+        );
 
-        let p3 = source.create_aprs_packet().unwrap();
-        assert_eq!(p3.message, "packet 3\n");
-        let elapsed_p3 = start.elapsed().as_millis();
-        assert!(elapsed_p3 >= 100);
-        assert!(elapsed_p3.abs_diff(100) <= 5);
+        assert!(source.create_aprs_packet().is_ok());
+        assert_eq!(*outgoing_data.lock().unwrap(), CLIENT_KEEPALIVE);
+    }
 
-        let p4 = source.create_aprs_packet().is_err();
-        assert!(p4);
+    #[test]
+    fn given_silent_server_when_read_times_out_then_source_is_idle_until_stall_timeout() {
+        let mut idle_source = LiveSource::new(MockSilentStream);
+        assert!(matches!(
+            idle_source.create_aprs_packet(),
+            Err(PacketError::Idle)
+        ));
+
+        let config = SessionConfig {
+            stall_timeout_seconds: 0,
+            ..SessionConfig::default()
+        };
+        let mut stalled_source = LiveSource::with_session(
+            MockSilentStream,
+            AprsIsSession::new(&LoginCredentials::default(), &config),
+        );
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        assert!(matches!(
+            stalled_source.create_aprs_packet(),
+            Err(PacketError::Stalled(_))
+        ));
     }
 }
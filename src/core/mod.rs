@@ -1,5 +1,7 @@
 pub mod airspace;
 pub mod central_disk_logger;
+pub mod channel;
 pub mod ingestor;
 pub mod parser;
+pub mod receivers;
 pub mod thread_manager;
@@ -0,0 +1,225 @@
+use ogn_aprs_parser::{OGNAddressType, OGNAircraftType};
+
+/// Vertical speed in a beacon comment is given in feet per minute.
+const FEET_PER_MINUTE_TO_METRES_PER_SECOND: f64 = 0.00508;
+/// Turn rate in a beacon comment is given in half turns per minute.
+const HALF_TURNS_PER_MINUTE_TO_DEGREES_PER_SECOND: f64 = 3.0;
+
+/// Kind of aircraft or object carrying the tracker, as encoded in the OGN ID prefix.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AircraftType {
+    Reserved,
+    Glider,
+    TowPlane,
+    Helicopter,
+    Parachute,
+    DropPlane,
+    HangGlider,
+    Paraglider,
+    ReciprocatingEngineAircraft,
+    JetTurbopropAircraft,
+    #[default]
+    Unknown,
+    Balloon,
+    Airship,
+    Uav,
+    StaticObstacle,
+}
+impl From<OGNAircraftType> for AircraftType {
+    fn from(aircraft_type: OGNAircraftType) -> Self {
+        match aircraft_type {
+            OGNAircraftType::Reserved => Self::Reserved,
+            OGNAircraftType::Glider => Self::Glider,
+            OGNAircraftType::TowPlane => Self::TowPlane,
+            OGNAircraftType::Helicopter => Self::Helicopter,
+            OGNAircraftType::Parachute => Self::Parachute,
+            OGNAircraftType::DropPlane => Self::DropPlane,
+            OGNAircraftType::HangGlider => Self::HangGlider,
+            OGNAircraftType::Paraglider => Self::Paraglider,
+            OGNAircraftType::ReciprocatingEngineAircraft => Self::ReciprocatingEngineAircraft,
+            OGNAircraftType::JetTurbopropAircraft => Self::JetTurbopropAircraft,
+            OGNAircraftType::Unknown => Self::Unknown,
+            OGNAircraftType::Balloon => Self::Balloon,
+            OGNAircraftType::Airship => Self::Airship,
+            OGNAircraftType::UAVs => Self::Uav,
+            OGNAircraftType::StaticObstacle => Self::StaticObstacle,
+        }
+    }
+}
+impl AircraftType {
+    /// The 4 bit code of the OGN ID prefix.
+    #[must_use]
+    pub fn code(self) -> u8 {
+        match self {
+            Self::Reserved => 0,
+            Self::Glider => 1,
+            Self::TowPlane => 2,
+            Self::Helicopter => 3,
+            Self::Parachute => 4,
+            Self::DropPlane => 5,
+            Self::HangGlider => 6,
+            Self::Paraglider => 7,
+            Self::ReciprocatingEngineAircraft => 8,
+            Self::JetTurbopropAircraft => 9,
+            Self::Unknown => 10,
+            Self::Balloon => 11,
+            Self::Airship => 12,
+            Self::Uav => 13,
+            Self::StaticObstacle => 15,
+        }
+    }
+
+    #[must_use]
+    pub fn from_code(code: u8) -> Option<Self> {
+        OGNAircraftType::from_u8(code).ok().map(Into::into)
+    }
+}
+
+/// Who assigned the 24 bit address of the tracker.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum AddressType {
+    #[default]
+    Unknown,
+    Icao,
+    Flarm,
+    OgnTracker,
+}
+impl From<OGNAddressType> for AddressType {
+    fn from(address_type: OGNAddressType) -> Self {
+        match address_type {
+            OGNAddressType::Unknown => Self::Unknown,
+            OGNAddressType::ICAO => Self::Icao,
+            OGNAddressType::FLARM => Self::Flarm,
+            OGNAddressType::OGNTracker => Self::OgnTracker,
+        }
+    }
+}
+impl AddressType {
+    /// The 2 bit code of the OGN ID prefix.
+    #[must_use]
+    pub fn code(self) -> u8 {
+        match self {
+            Self::Unknown => 0,
+            Self::Icao => 1,
+            Self::Flarm => 2,
+            Self::OgnTracker => 3,
+        }
+    }
+
+    #[must_use]
+    pub fn from_code(code: u8) -> Option<Self> {
+        OGNAddressType::from_u8(code).ok().map(Into::into)
+    }
+}
+
+/// Accuracy of the GPS fix reported by the tracker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct GpsAccuracy {
+    pub horizontal_metres: u16,
+    pub vertical_metres: u16,
+}
+
+/// Values of the optional comment tokens following the position of an OGN aircraft
+/// beacon, e.g. `+2880fpm +0.0rot 8.2dB -1.5kHz gps2x3`, converted to SI units.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BeaconComment {
+    pub climb_rate_metres_per_second: Option<f64>,
+    pub turn_rate_degrees_per_second: Option<f64>,
+    pub signal_quality_db: Option<f64>,
+    pub frequency_offset_khz: Option<f64>,
+    pub gps_accuracy: Option<GpsAccuracy>,
+}
+impl BeaconComment {
+    /// Reads the comment of a raw beacon, the tokens after its `A=` altitude. Tokens that
+    /// are absent or malformed are left unset.
+    #[must_use]
+    pub fn parse(message: &[u8]) -> Self {
+        let mut comment = Self::default();
+        let Some(altitude) = message.windows(3).position(|window| window == b"/A=") else {
+            return comment;
+        };
+        let tokens = String::from_utf8_lossy(&message[altitude..]);
+        // The first token is the rest of the position block, ending with the altitude.
+        for token in tokens.split_ascii_whitespace().skip(1) {
+            if let Some(value) = token.strip_suffix("fpm") {
+                comment.climb_rate_metres_per_second = value
+                    .parse::<f64>()
+                    .ok()
+                    .map(|feet_per_minute| feet_per_minute * FEET_PER_MINUTE_TO_METRES_PER_SECOND);
+            } else if let Some(value) = token.strip_suffix("rot") {
+                comment.turn_rate_degrees_per_second =
+                    value.parse::<f64>().ok().map(|half_turns_per_minute| {
+                        half_turns_per_minute * HALF_TURNS_PER_MINUTE_TO_DEGREES_PER_SECOND
+                    });
+            } else if let Some(value) = token.strip_suffix("dB") {
+                comment.signal_quality_db = value.parse().ok();
+            } else if let Some(value) = token.strip_suffix("kHz") {
+                comment.frequency_offset_khz = value.parse().ok();
+            } else if let Some(value) = token.strip_prefix("gps")
+                && let Some((horizontal, vertical)) = value.split_once('x')
+            {
+                comment.gps_accuracy = horizontal.parse().ok().zip(vertical.parse().ok()).map(
+                    |(horizontal_metres, vertical_metres)| GpsAccuracy {
+                        horizontal_metres,
+                        vertical_metres,
+                    },
+                );
+            }
+        }
+        comment
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: Option<f64>, expected: f64) {
+        let actual = actual.expect("expected a value");
+        assert!((actual - expected).abs() < 1e-9, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn given_flarm_beacon_when_comment_parsed_then_every_token_is_converted() {
+        let message = b"FLRDDA5BA>OGFLR,qAS,LFNW:/163148h4559.85N/00348.19E'/342/A=001345 !W06! id0ADDA5BA -454fpm -1.1rot 8.8dB 0e -4.3kHz gps2x3";
+
+        let comment = BeaconComment::parse(message);
+
+        assert_close(comment.climb_rate_metres_per_second, -2.306_32);
+        assert_close(comment.turn_rate_degrees_per_second, -3.3);
+        assert_close(comment.signal_quality_db, 8.8);
+        assert_close(comment.frequency_offset_khz, -4.3);
+        assert_eq!(
+            comment.gps_accuracy,
+            Some(GpsAccuracy {
+                horizontal_metres: 2,
+                vertical_metres: 3
+            })
+        );
+    }
+
+    #[rstest::rstest]
+    #[case::no_comment(&b"FLRDDA5BA>OGFLR,qAS,LFNW:/163148h4559.85N/00348.19E'/342/A=001345"[..])]
+    #[case::no_altitude(&b"FLRDDA5BA>OGFLR,qAS,LFNW:/163148h4559.85N/00348.19E'/342 +100fpm"[..])]
+    #[case::malformed(&b"FLRDDA5BA>OGFLR,qAS,LFNW:/163148h4559.85N/00348.19E'/342/A=001345 xfpm gps2 gpsAx3"[..])]
+    fn given_beacon_without_valid_tokens_when_comment_parsed_then_values_are_unset(
+        #[case] message: &[u8],
+    ) {
+        assert_eq!(BeaconComment::parse(message), BeaconComment::default());
+    }
+
+    #[test]
+    fn given_every_type_code_when_round_tripped_then_types_are_unchanged() {
+        for code in (0..=15).filter(|code| *code != 14) {
+            assert_eq!(AircraftType::from_code(code).unwrap().code(), code);
+        }
+        for code in 0..=3 {
+            assert_eq!(AddressType::from_code(code).unwrap().code(), code);
+        }
+        assert_eq!(AddressType::from_code(4), None);
+    }
+}
@@ -1,36 +1,267 @@
 use ogn_aprs_parser::{AircraftBeacon, ICAOAddress};
 
-#[derive(Debug, PartialEq, Clone)]
+use crate::core::parser::beacon::{AddressType, AircraftType, BeaconComment, GpsAccuracy};
+use crate::core::parser::errors::BeaconConversionError;
+
+/// Beacons stamped further than this from when they were received are rejected by default.
+pub const DEFAULT_MAX_CLOCK_SKEW: chrono::TimeDelta = chrono::TimeDelta::minutes(5);
+
+// Fields added after the first recordings default when missing, so older JSON lines
+// recordings still load.
+#[derive(Debug, PartialEq, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Aircraft {
     pub callsign: String,
-    pub icao_address: ICAOAddress,
+    /// Missing for trackers whose reports carry no address, which are told apart by
+    /// callsign.
+    #[serde(default, with = "icao_address_hex::option")]
+    pub icao_address: Option<ICAOAddress>,
     pub datetime: chrono::DateTime<chrono::Utc>,
     pub latitude: f64,
     pub longitude: f64,
-    pub ground_track: f64,
-    pub ground_speed: f64,
-    pub gps_altitude: f64,
+    /// Degrees clockwise from true north. Track, speed and altitude are missing when
+    /// a tracker does not report them.
+    pub ground_track: Option<f64>,
+    #[serde(alias = "ground_speed")]
+    pub ground_speed_knots: Option<f64>,
+    /// Above mean sea level.
+    #[serde(alias = "gps_altitude")]
+    pub gps_altitude_feet: Option<f64>,
+    /// The station that heard the beacon and relayed it to APRS-IS.
+    #[serde(default)]
+    pub receiver: String,
+    #[serde(default)]
+    pub aircraft_type: AircraftType,
+    #[serde(default)]
+    pub address_type: AddressType,
+    /// The pilot asked for the aircraft not to be identified on live displays.
+    #[serde(default)]
+    pub stealth: bool,
+    /// The pilot asked for no track of the flight to be kept. The parser drops such
+    /// aircraft before they are recorded or reach the airspace.
+    #[serde(default)]
+    pub no_tracking: bool,
+    /// Positive when climbing.
+    #[serde(default)]
+    pub climb_rate_metres_per_second: Option<f64>,
+    /// Positive when turning clockwise.
+    #[serde(default)]
+    pub turn_rate_degrees_per_second: Option<f64>,
+    /// Signal to noise ratio of the beacon at the receiver.
+    #[serde(default)]
+    pub signal_quality_db: Option<f64>,
+    /// Offset of the tracker's carrier from the receiver's frequency.
+    #[serde(default)]
+    pub frequency_offset_khz: Option<f64>,
+    #[serde(default)]
+    pub gps_accuracy: Option<GpsAccuracy>,
 }
 
+/// Builds an aircraft from a parsed beacon and the raw `message` it was parsed from,
+/// whose comment holds the values the beacon parser skips. The beacon is dated from
+/// `timestamp`, the time it was received, as by [`infer_beacon_datetime`].
 pub fn convert_ogn_aprs_beacon_to_aircraft(
     aircraft_beacon: AircraftBeacon,
+    message: &[u8],
     timestamp: std::time::SystemTime,
-) -> Aircraft {
-    let now: chrono::DateTime<chrono::Utc> = timestamp.into();
-
-    let datetime = chrono::DateTime::<chrono::Utc>::from_naive_utc_and_offset(
-        now.date_naive().and_time(aircraft_beacon.time),
-        chrono::Utc,
-    );
+    max_clock_skew: chrono::TimeDelta,
+) -> Result<Aircraft, BeaconConversionError> {
+    let datetime = infer_beacon_datetime(aircraft_beacon.time, timestamp.into(), max_clock_skew)?;
 
-    Aircraft {
+    let prefix = aircraft_beacon.ogn_beacon_id.prefix;
+    let comment = BeaconComment::parse(message);
+    Ok(Aircraft {
         callsign: aircraft_beacon.callsign,
-        icao_address: aircraft_beacon.ogn_beacon_id.icao_address,
+        icao_address: Some(aircraft_beacon.ogn_beacon_id.icao_address),
         datetime,
         latitude: aircraft_beacon.latitude,
         longitude: aircraft_beacon.longitude,
-        ground_track: aircraft_beacon.ground_track,
-        ground_speed: aircraft_beacon.ground_speed,
-        gps_altitude: aircraft_beacon.gps_altitude,
+        ground_track: Some(aircraft_beacon.ground_track),
+        ground_speed_knots: Some(aircraft_beacon.ground_speed),
+        gps_altitude_feet: Some(aircraft_beacon.gps_altitude),
+        receiver: aircraft_beacon.receiver,
+        aircraft_type: prefix.aircraft_type.into(),
+        address_type: prefix.address_type.into(),
+        stealth: prefix.stealth_mode,
+        no_tracking: prefix.no_track,
+        climb_rate_metres_per_second: comment.climb_rate_metres_per_second,
+        turn_rate_degrees_per_second: comment.turn_rate_degrees_per_second,
+        signal_quality_db: comment.signal_quality_db,
+        frequency_offset_khz: comment.frequency_offset_khz,
+        gps_accuracy: comment.gps_accuracy,
+    })
+}
+
+/// Dates a beacon's UTC time of day on the day before, of or after `received`, whichever
+/// is nearest to `received`, so that beacons sent just before midnight and received
+/// just after keep their date. Fails when even the nearest is more than
+/// `max_clock_skew` away.
+pub fn infer_beacon_datetime(
+    beacon_time: chrono::NaiveTime,
+    received: chrono::DateTime<chrono::Utc>,
+    max_clock_skew: chrono::TimeDelta,
+) -> Result<chrono::DateTime<chrono::Utc>, BeaconConversionError> {
+    let date = received.date_naive();
+    let datetime = [date.pred_opt(), date.succ_opt()]
+        .into_iter()
+        .flatten()
+        .map(|date| date.and_time(beacon_time).and_utc())
+        .fold(
+            date.and_time(beacon_time).and_utc(),
+            |nearest, candidate| {
+                if (candidate - received).abs() < (nearest - received).abs() {
+                    candidate
+                } else {
+                    nearest
+                }
+            },
+        );
+    let skew = datetime - received;
+    if skew.abs() > max_clock_skew {
+        return Err(BeaconConversionError::ClockSkew {
+            beacon_time,
+            received,
+            skew,
+        });
+    }
+    Ok(datetime)
+}
+
+/// Writes ICAO addresses as the 6 digit hex strings used in OGN beacons, e.g. `"DDA5BA"`.
+pub(super) mod icao_address_hex {
+    use ogn_aprs_parser::ICAOAddress;
+
+    pub fn serialize<S: serde::Serializer>(
+        icao_address: &ICAOAddress,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("{:06X}", icao_address.value()))
+    }
+
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<ICAOAddress, D::Error> {
+        let hex = <String as serde::Deserialize>::deserialize(deserializer)?;
+        let value = u32::from_str_radix(&hex, 16).map_err(serde::de::Error::custom)?;
+        ICAOAddress::new(value).map_err(serde::de::Error::custom)
+    }
+
+    /// The same for optional addresses, written as `null` when missing.
+    pub mod option {
+        use ogn_aprs_parser::ICAOAddress;
+
+        pub fn serialize<S: serde::Serializer>(
+            icao_address: &Option<ICAOAddress>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match icao_address {
+                Some(icao_address) => super::serialize(icao_address, serializer),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<ICAOAddress>, D::Error> {
+            #[derive(serde::Deserialize)]
+            struct Hex(#[serde(with = "super")] ICAOAddress);
+
+            let hex = <Option<Hex> as serde::Deserialize>::deserialize(deserializer)?;
+            Ok(hex.map(|Hex(icao_address)| icao_address))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utilities::create_dummy_aircraft_at_time;
+
+    #[test]
+    fn given_aircraft_when_serialized_to_json_and_back_then_it_is_unchanged() {
+        let aircraft = create_dummy_aircraft_at_time(
+            chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+            ICAOAddress::new(0x00DD_A5BA).unwrap(),
+        );
+
+        let json = serde_json::to_string(&aircraft).unwrap();
+
+        assert!(json.contains(r#""icao_address":"DDA5BA""#));
+        assert!(json.contains(r#""datetime":"2023-11-14T22:13:20Z""#));
+        assert_eq!(serde_json::from_str::<Aircraft>(&json).unwrap(), aircraft);
+    }
+
+    #[test]
+    fn given_json_recorded_before_beacon_details_when_deserialized_then_details_default() {
+        let json = r#"{"callsign":"FLRDDA5BA","icao_address":"DDA5BA","datetime":"2023-11-14T22:13:20Z","latitude":45.9975,"longitude":3.8031,"ground_track":342.0,"ground_speed":0.0,"gps_altitude":1345.0}"#;
+
+        let aircraft = serde_json::from_str::<Aircraft>(json).unwrap();
+
+        assert_eq!(aircraft.aircraft_type, AircraftType::Unknown);
+        assert_eq!(aircraft.climb_rate_metres_per_second, None);
+        assert!(!aircraft.stealth);
+    }
+
+    #[test]
+    fn given_beacon_when_converted_then_prefix_receiver_and_comment_are_kept() {
+        let message = b"ICA020113>OGADSB,qAS,AVX1081:/190558h5050.73N/00413.19E^222/262/A=007246 !W06! id25020113 +2880fpm +0.5rot FL079.69 A3:RAM831F Sq7122";
+        let beacon = ogn_aprs_parser::parse_ogn_aprs_aircraft_beacon(message).unwrap();
+
+        let received = chrono::DateTime::parse_from_rfc3339("2024-06-01T19:06:00Z").unwrap();
+
+        let aircraft = convert_ogn_aprs_beacon_to_aircraft(
+            beacon,
+            message,
+            received.into(),
+            DEFAULT_MAX_CLOCK_SKEW,
+        )
+        .unwrap();
+
+        assert_eq!(aircraft.datetime.to_rfc3339(), "2024-06-01T19:05:58+00:00");
+        assert_eq!(aircraft.receiver, "AVX1081");
+        assert_eq!(aircraft.aircraft_type, AircraftType::JetTurbopropAircraft);
+        assert_eq!(aircraft.address_type, AddressType::Icao);
+        assert!(!aircraft.stealth && !aircraft.no_tracking);
+        assert!((aircraft.climb_rate_metres_per_second.unwrap() - 14.6304).abs() < 1e-9);
+        assert_eq!(aircraft.turn_rate_degrees_per_second, Some(1.5));
+        assert_eq!(aircraft.signal_quality_db, None);
+    }
+
+    #[rstest::rstest]
+    #[case::same_day("12:00:00", "2024-06-01T12:00:03Z", "2024-06-01T12:00:00Z")]
+    #[case::sent_before_midnight("23:59:58", "2024-06-02T00:00:01Z", "2024-06-01T23:59:58Z")]
+    #[case::received_before_midnight("00:00:02", "2024-06-01T23:59:59Z", "2024-06-02T00:00:02Z")]
+    #[case::end_of_year("23:59:59", "2025-01-01T00:00:02Z", "2024-12-31T23:59:59Z")]
+    fn given_beacon_time_when_dated_then_nearest_day_to_receive_time_is_chosen(
+        #[case] beacon_time: &str,
+        #[case] received: &str,
+        #[case] expected: &str,
+    ) {
+        let beacon_time = chrono::NaiveTime::parse_from_str(beacon_time, "%H:%M:%S").unwrap();
+        let received = chrono::DateTime::parse_from_rfc3339(received)
+            .unwrap()
+            .to_utc();
+
+        let datetime =
+            infer_beacon_datetime(beacon_time, received, DEFAULT_MAX_CLOCK_SKEW).unwrap();
+
+        assert_eq!(
+            datetime,
+            chrono::DateTime::parse_from_rfc3339(expected).unwrap()
+        );
+    }
+
+    #[test]
+    fn given_beacon_time_beyond_max_skew_when_dated_then_clock_skew_is_reported() {
+        let beacon_time = chrono::NaiveTime::from_hms_opt(12, 10, 0).unwrap();
+        let received = chrono::DateTime::parse_from_rfc3339("2024-06-01T12:00:00Z")
+            .unwrap()
+            .to_utc();
+
+        let result = infer_beacon_datetime(beacon_time, received, DEFAULT_MAX_CLOCK_SKEW);
+
+        assert!(matches!(
+            result,
+            Err(BeaconConversionError::ClockSkew { skew, .. }) if skew == chrono::TimeDelta::minutes(10)
+        ));
     }
 }
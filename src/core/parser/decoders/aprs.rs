@@ -0,0 +1,373 @@
+use chrono::Datelike;
+
+use crate::core::parser::conversion::infer_beacon_datetime;
+use crate::core::parser::decoders::{
+    AprsHeader, PositionDecoder, TrackPoint, TrackerKind, aircraft_type_of_symbol, ogn_id,
+    tracker_address,
+};
+use crate::core::parser::errors::{BeaconConversionError, DecodeError};
+use crate::core::parser::receiver::parse_coordinate;
+
+/// Decodes standard APRS position reports, with or without a timestamp, in either the
+/// uncompressed or the compressed format. Mic-E reports are not decoded.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AprsPositionDecoder;
+
+impl PositionDecoder for AprsPositionDecoder {
+    fn kind(&self) -> TrackerKind {
+        TrackerKind::Aprs
+    }
+
+    /// OGN formats all use destinations starting with `OG`.
+    fn accepts(&self, header: &AprsHeader<'_>) -> bool {
+        !header.destination.starts_with("OG") && header.body.starts_with(['!', '=', '/', '@'])
+    }
+
+    fn decode(
+        &self,
+        header: &AprsHeader<'_>,
+        received: chrono::DateTime<chrono::Utc>,
+        max_clock_skew: chrono::TimeDelta,
+    ) -> Result<TrackPoint, DecodeError> {
+        let position = parse_position_report(header.body, received, max_clock_skew)?;
+        // Older OGN software sends aircraft beacons to the `APRS` destination.
+        let ogn_id = ogn_id(position.comment);
+        let (address, address_type, aircraft_type) =
+            tracker_address(header.callsign, ogn_id.as_ref());
+        Ok(TrackPoint {
+            kind: TrackerKind::Aprs,
+            callsign: header.callsign.to_string(),
+            address,
+            address_type,
+            aircraft_type: aircraft_type
+                .or_else(|| aircraft_type_of_symbol(position.symbol_table, position.symbol))
+                .unwrap_or_default(),
+            tracker_id: None,
+            receiver: header.receiver.to_string(),
+            datetime: position.datetime,
+            latitude: position.latitude,
+            longitude: position.longitude,
+            ground_track: position.ground_track,
+            ground_speed: position.ground_speed,
+            altitude: position.altitude,
+            climb_rate_metres_per_second: None,
+        })
+    }
+}
+
+/// The fields of an APRS position report, shared by every format built on it.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct AprsPosition<'a> {
+    pub(super) datetime: chrono::DateTime<chrono::Utc>,
+    pub(super) latitude: f64,
+    pub(super) longitude: f64,
+    pub(super) symbol_table: char,
+    pub(super) symbol: char,
+    pub(super) ground_track: Option<f64>,
+    pub(super) ground_speed: Option<f64>,
+    pub(super) altitude: Option<f64>,
+    /// Whatever follows the position and its course and speed.
+    pub(super) comment: &'a str,
+}
+
+/// Parses the body of a position report: a `!` or `=` report dated `received`, or a `/`
+/// or `@` report with a `HHMMSSh` or `DDHHMMz` timestamp.
+pub(super) fn parse_position_report(
+    body: &str,
+    received: chrono::DateTime<chrono::Utc>,
+    max_clock_skew: chrono::TimeDelta,
+) -> Result<AprsPosition<'_>, DecodeError> {
+    let mut chars = body.chars();
+    let kind = chars.next();
+    let rest = chars.as_str();
+    let (datetime, rest) = match kind {
+        Some('!' | '=') => (received, rest),
+        Some('/' | '@') => {
+            let timestamp = rest
+                .get(..7)
+                .ok_or_else(|| DecodeError::InvalidTimestamp(rest.to_string()))?;
+            (
+                parse_timestamp(timestamp, received, max_clock_skew)?,
+                &rest[7..],
+            )
+        }
+        _ => return Err(DecodeError::Unsupported(body.to_string())),
+    };
+    let mut position = if rest.starts_with(|first: char| first.is_ascii_digit()) {
+        parse_uncompressed(rest)
+    } else {
+        parse_compressed(rest)
+    }
+    .ok_or_else(|| DecodeError::InvalidPosition(rest.to_string()))?;
+    position.datetime = datetime;
+    if position.altitude.is_none() {
+        position.altitude = position
+            .comment
+            .split_once("/A=")
+            .and_then(|(_, altitude)| altitude.get(..6).and_then(|feet| feet.parse::<f64>().ok()));
+    }
+    apply_precision_enhancement(&mut position);
+    Ok(position)
+}
+
+fn parse_timestamp(
+    timestamp: &str,
+    received: chrono::DateTime<chrono::Utc>,
+    max_clock_skew: chrono::TimeDelta,
+) -> Result<chrono::DateTime<chrono::Utc>, DecodeError> {
+    let invalid = || DecodeError::InvalidTimestamp(timestamp.to_string());
+    let digits = timestamp
+        .get(..6)
+        .filter(|digits| digits.bytes().all(|b| b.is_ascii_digit()));
+    match (digits, timestamp.get(6..)) {
+        (Some(digits), Some("h")) => {
+            let time =
+                chrono::NaiveTime::parse_from_str(digits, "%H%M%S").map_err(|_| invalid())?;
+            Ok(infer_beacon_datetime(time, received, max_clock_skew)?)
+        }
+        (Some(digits), Some("z")) => {
+            let day = digits[..2].parse().map_err(|_| invalid())?;
+            let time =
+                chrono::NaiveTime::parse_from_str(&digits[2..], "%H%M").map_err(|_| invalid())?;
+            let datetime = nearest_month_datetime(day, time, received).ok_or_else(invalid)?;
+            let skew = datetime - received;
+            if skew.abs() > max_clock_skew {
+                return Err(BeaconConversionError::ClockSkew {
+                    beacon_time: time,
+                    received,
+                    skew,
+                }
+                .into());
+            }
+            Ok(datetime)
+        }
+        // Local time `DDHHMM/` stamps cannot be placed without the sender's time zone.
+        _ => Err(invalid()),
+    }
+}
+
+/// Dates a day of month and UTC time on the month before, of or after `received`,
+/// whichever is nearest, as [`infer_beacon_datetime`] does with days. `None` when the
+/// day exists in none of them.
+fn nearest_month_datetime(
+    day: u32,
+    time: chrono::NaiveTime,
+    received: chrono::DateTime<chrono::Utc>,
+) -> Option<chrono::DateTime<chrono::Utc>> {
+    let month = received.date_naive().with_day(1)?;
+    let one_month = chrono::Months::new(1);
+    [
+        month.checked_sub_months(one_month),
+        Some(month),
+        month.checked_add_months(one_month),
+    ]
+    .into_iter()
+    .flatten()
+    .filter_map(|month| month.with_day(day))
+    .map(|date| date.and_time(time).and_utc())
+    .min_by_key(|candidate| (*candidate - received).abs())
+}
+
+/// `DDMM.mmN`, symbol table, `DDDMM.mmE`, symbol, then an optional `CSE/SPD` in degrees
+/// and knots.
+fn parse_uncompressed(position: &str) -> Option<AprsPosition<'_>> {
+    let latitude = parse_coordinate(position.get(..8)?, 2, 'N', 'S')?;
+    let symbol_table = position.get(8..9)?.chars().next()?;
+    let longitude = parse_coordinate(position.get(9..18)?, 3, 'E', 'W')?;
+    let symbol = position.get(18..19)?.chars().next()?;
+    let rest = &position[19..];
+    let course_speed = rest
+        .get(..7)
+        .filter(|course_speed| course_speed.as_bytes()[3] == b'/')
+        .and_then(|course_speed| {
+            Some((
+                f64::from(course_speed[..3].parse::<u16>().ok()?),
+                f64::from(course_speed[4..].parse::<u16>().ok()?),
+            ))
+        });
+    let (ground_track, ground_speed, comment) = match course_speed {
+        Some((track, speed)) => (Some(track), Some(speed), &rest[7..]),
+        None => (None, None, rest),
+    };
+    Some(AprsPosition {
+        datetime: chrono::DateTime::UNIX_EPOCH,
+        latitude,
+        longitude,
+        symbol_table,
+        symbol,
+        ground_track,
+        ground_speed,
+        altitude: None,
+        comment,
+    })
+}
+
+/// Symbol table, base 91 latitude and longitude, symbol, then either course and speed or
+/// altitude, and the compression type.
+fn parse_compressed(position: &str) -> Option<AprsPosition<'_>> {
+    let bytes = position.get(..13)?.as_bytes();
+    if !bytes.is_ascii() {
+        return None;
+    }
+    let base91 = |digits: &[u8]| {
+        digits.iter().try_fold(0_u32, |value, digit| {
+            (33..=123)
+                .contains(digit)
+                .then(|| value * 91 + u32::from(digit - 33))
+        })
+    };
+    let latitude = 90.0 - f64::from(base91(&bytes[1..5])?) / 380_926.0;
+    let longitude = -180.0 + f64::from(base91(&bytes[5..9])?) / 190_463.0;
+    let (c, s, compression_type) = (bytes[10], bytes[11], bytes[12]);
+    let mut position = AprsPosition {
+        datetime: chrono::DateTime::UNIX_EPOCH,
+        latitude,
+        longitude,
+        symbol_table: char::from(bytes[0]),
+        symbol: char::from(bytes[9]),
+        ground_track: None,
+        ground_speed: None,
+        altitude: None,
+        comment: &position[13..],
+    };
+    if c == b' ' || c < 33 || s < 33 || compression_type < 33 {
+        return Some(position);
+    }
+    // The compression type says whether the two bytes hold an altitude from a GGA sentence.
+    if (compression_type - 33) & 0x18 == 0x10 {
+        position.altitude = Some(1.002_f64.powi(i32::from(c - 33) * 91 + i32::from(s - 33)));
+    } else if c <= b'z' {
+        position.ground_track = Some(f64::from(c - 33) * 4.0);
+        position.ground_speed = Some(1.08_f64.powi(i32::from(s - 33)) - 1.0);
+    }
+    Some(position)
+}
+
+/// Adds the extra digit of minutes from a `!Wab!` comment token to each coordinate.
+fn apply_precision_enhancement(position: &mut AprsPosition<'_>) {
+    let Some((_, rest)) = position.comment.split_once("!W") else {
+        return;
+    };
+    let digits = rest.as_bytes();
+    if digits.len() < 3 || digits[2] != b'!' || !digits[..2].iter().all(u8::is_ascii_digit) {
+        return;
+    }
+    let extra_degrees = |digit: u8| f64::from(digit - b'0') / 1000.0 / 60.0;
+    position.latitude += extra_degrees(digits[0]).copysign(position.latitude);
+    position.longitude += extra_degrees(digits[1]).copysign(position.longitude);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::parser::conversion::DEFAULT_MAX_CLOCK_SKEW;
+    use crate::core::parser::{AddressType, AircraftType};
+
+    fn received() -> chrono::DateTime<chrono::Utc> {
+        chrono::DateTime::parse_from_rfc3339("2024-06-01T16:31:50Z")
+            .unwrap()
+            .to_utc()
+    }
+
+    fn decode(message: &str) -> Result<TrackPoint, DecodeError> {
+        let header = AprsHeader::parse(message).unwrap();
+        assert!(AprsPositionDecoder.accepts(&header));
+        AprsPositionDecoder.decode(&header, received(), DEFAULT_MAX_CLOCK_SKEW)
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-3, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn given_balloon_position_without_timestamp_when_decoded_then_it_is_dated_on_receipt() {
+        let point = decode(
+            "N0CALL-11>APRS,WIDE2-1,qAR,DB0XYZ:!4903.50N/07201.75WO090/012/A=012345 Balloon",
+        )
+        .unwrap();
+
+        assert_eq!(point.datetime, received());
+        assert_close(point.latitude, 49.058_333);
+        assert_close(point.longitude, -72.029_166);
+        assert_eq!(point.aircraft_type, AircraftType::Balloon);
+        assert_eq!(
+            (point.ground_track, point.ground_speed, point.altitude),
+            (Some(90.0), Some(12.0), Some(12345.0))
+        );
+        assert_eq!(point.receiver, "DB0XYZ");
+    }
+
+    #[rstest::rstest]
+    #[case::hms("/163148h", "2024-06-01T16:31:48Z")]
+    #[case::dhm("@011630z", "2024-06-01T16:30:00Z")]
+    #[case::dhm_last_month("@312359z", "2024-05-31T23:59:00Z")]
+    fn given_timestamped_position_when_decoded_then_timestamp_is_dated_near_receipt(
+        #[case] timestamp: &str,
+        #[case] expected: &str,
+    ) {
+        let skew = chrono::TimeDelta::days(2);
+        let message = format!("N0CALL-9>APRS,qAR,DB0XYZ:{timestamp}4903.50N/07201.75W>");
+        let header = AprsHeader::parse(&message).unwrap();
+
+        let point = AprsPositionDecoder
+            .decode(&header, received(), skew)
+            .unwrap();
+
+        assert_eq!(point.datetime.to_rfc3339(), expected.replace('Z', "+00:00"));
+        assert_eq!(point.ground_track, None);
+    }
+
+    #[test]
+    fn given_compressed_position_when_decoded_then_course_and_speed_are_expanded() {
+        let point = decode("N0CALL>APRS,qAR,DB0XYZ:=/5L!!<*e7>7P[").unwrap();
+
+        assert_close(point.latitude, 49.5);
+        assert_close(point.longitude, -72.75);
+        assert_close(point.ground_track.unwrap(), 88.0);
+        assert!((point.ground_speed.unwrap() - 36.2).abs() < 0.1);
+    }
+
+    #[test]
+    fn given_old_ogn_beacon_to_aprs_destination_when_decoded_then_its_id_is_used() {
+        let point = decode(
+            "FLRDDA5BA>APRS,qAS,LFNW:/163148h4559.85N/00348.19E'342/012/A=001345 !W06! id06DDA5BA",
+        )
+        .unwrap();
+
+        assert_eq!(
+            point.address.map(|address| address.value()),
+            Some(0x00DD_A5BA)
+        );
+        assert_eq!(point.address_type, AddressType::Flarm);
+        assert_eq!(point.aircraft_type, AircraftType::Glider);
+        assert_close(point.latitude, 45.997_5);
+        assert_close(point.longitude, 3.803_1);
+    }
+
+    #[rstest::rstest]
+    #[case::local_time("/011630/4903.50N/07201.75W>", "timestamp")]
+    #[case::non_ascii_stamp("@1é630z4903.50N/07201.75W>", "timestamp")]
+    #[case::garbled_latitude("!49X3.50N/07201.75W>", "position")]
+    #[case::truncated("!4903.50N/0720", "position")]
+    fn given_malformed_report_when_decoded_then_faulty_part_is_reported(
+        #[case] body: &str,
+        #[case] faulty_part: &str,
+    ) {
+        let error = decode(&format!("N0CALL>APRS,qAR,DB0XYZ:{body}")).unwrap_err();
+
+        assert!(
+            error.to_string().contains(faulty_part),
+            "{error} does not mention {faulty_part}"
+        );
+    }
+
+    #[test]
+    fn given_ogn_or_mic_e_report_when_checked_then_it_is_not_accepted() {
+        for message in [
+            "FLRDDA5BA>OGFLR,qAS,LFNW:/163148h4559.85N/00348.19E'342/012/A=001345",
+            "N0CALL>T2SP0W,qAR,DB0XYZ:`(_fn\"Oj/",
+        ] {
+            assert!(!AprsPositionDecoder.accepts(&AprsHeader::parse(message).unwrap()));
+        }
+    }
+}
@@ -0,0 +1,221 @@
+use ogn_aprs_parser::{ICAOAddress, OGNBeaconID};
+
+use crate::core::parser::errors::DecodeError;
+use crate::core::parser::{AddressType, Aircraft, AircraftType};
+
+mod aprs;
+mod ogn_relay;
+
+pub use aprs::AprsPositionDecoder;
+pub use ogn_relay::OgnRelayDecoder;
+
+/// The kinds of trackers whose position reports are decoded besides OGN aircraft beacons.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrackerKind {
+    /// SPOT satellite messengers, relayed by OGN as `OGSPOT`.
+    Spot,
+    /// Garmin inReach satellite messengers, relayed by OGN as `OGINREACH`.
+    InReach,
+    /// The Skylines live tracking service, relayed by OGN as `OGSKYL`.
+    Skylines,
+    /// The LiveTrack24 live tracking service, relayed by OGN as `OGLT24`.
+    LiveTrack24,
+    /// FANET radios, relayed by their ground stations as `OGNFNT`.
+    Fanet,
+    /// PilotAware radios, relayed by their ground stations as `OGPAW`.
+    PilotAware,
+    /// Standard APRS position reports (`!`, `=`, `/` and `@`), e.g. from balloons and
+    /// ground vehicles.
+    Aprs,
+}
+impl TrackerKind {
+    /// A decoder for the reports of this kind of tracker.
+    #[must_use]
+    pub fn decoder(self) -> Box<dyn PositionDecoder> {
+        match self {
+            Self::Aprs => Box::new(AprsPositionDecoder),
+            relayed => Box::new(OgnRelayDecoder::new(relayed)),
+        }
+    }
+}
+
+/// Turns the APRS position reports of one kind of tracker into [`TrackPoint`]s.
+pub trait PositionDecoder: Send {
+    fn kind(&self) -> TrackerKind;
+
+    /// Whether `header` starts a report of this decoder's trackers. Only then is
+    /// [`Self::decode`] called.
+    fn accepts(&self, header: &AprsHeader<'_>) -> bool;
+
+    /// Decodes a report received at `received`. Report times are dated as by
+    /// [`infer_beacon_datetime`](crate::core::parser::conversion::infer_beacon_datetime).
+    fn decode(
+        &self,
+        header: &AprsHeader<'_>,
+        received: chrono::DateTime<chrono::Utc>,
+        max_clock_skew: chrono::TimeDelta,
+    ) -> Result<TrackPoint, DecodeError>;
+}
+
+/// The parts of an APRS line around its `:`, e.g. `FNT1103CE>OGNFNT,qAS,FNB1103CE:/...`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AprsHeader<'a> {
+    pub callsign: &'a str,
+    /// The first hop of the path, naming the format of the report for OGN relays.
+    pub destination: &'a str,
+    /// The last hop of the path, the station that put the report on APRS-IS.
+    pub receiver: &'a str,
+    /// Everything after the `:`, without the line ending.
+    pub body: &'a str,
+}
+impl<'a> AprsHeader<'a> {
+    #[must_use]
+    pub fn parse(message: &'a str) -> Option<Self> {
+        let (header, body) = message.split_once(':')?;
+        let (callsign, path) = header.split_once('>')?;
+        let destination = path.split(',').next()?;
+        Some(Self {
+            callsign,
+            destination,
+            receiver: path.rsplit(',').next().unwrap_or_default(),
+            body: body.trim_end_matches(['\r', '\n']),
+        })
+    }
+}
+
+/// A position of any kind of tracker, in the units of [`Aircraft`].
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct TrackPoint {
+    pub kind: TrackerKind,
+    pub callsign: String,
+    /// The address of the tracker, when its report carries one, see [`tracker_address`].
+    #[serde(with = "crate::core::parser::conversion::icao_address_hex::option")]
+    pub address: Option<ICAOAddress>,
+    pub address_type: AddressType,
+    pub aircraft_type: AircraftType,
+    /// Identifier of the tracker in its own network, e.g. a SPOT ESN or a Skylines
+    /// user ID, when reported.
+    pub tracker_id: Option<String>,
+    pub receiver: String,
+    pub datetime: chrono::DateTime<chrono::Utc>,
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Degrees clockwise from true north.
+    pub ground_track: Option<f64>,
+    /// Knots.
+    pub ground_speed: Option<f64>,
+    /// Feet above mean sea level.
+    pub altitude: Option<f64>,
+    pub climb_rate_metres_per_second: Option<f64>,
+}
+
+impl From<TrackPoint> for Aircraft {
+    fn from(track_point: TrackPoint) -> Self {
+        Aircraft {
+            callsign: track_point.callsign,
+            icao_address: track_point.address,
+            datetime: track_point.datetime,
+            latitude: track_point.latitude,
+            longitude: track_point.longitude,
+            ground_track: track_point.ground_track,
+            ground_speed_knots: track_point.ground_speed,
+            gps_altitude_feet: track_point.altitude,
+            receiver: track_point.receiver,
+            aircraft_type: track_point.aircraft_type,
+            address_type: track_point.address_type,
+            stealth: false,
+            no_tracking: false,
+            climb_rate_metres_per_second: track_point.climb_rate_metres_per_second,
+            turn_rate_degrees_per_second: None,
+            signal_quality_db: None,
+            frequency_offset_khz: None,
+            gps_accuracy: None,
+        }
+    }
+}
+
+/// The 24 bit address of a tracker, if its report carries one. An OGN `id` token such as
+/// `id1E1103CE` gives it directly; callsigns such as `FNT1103CE`, three letters then six
+/// hex digits, carry one with an unknown address type. Other trackers have no address
+/// and are told apart by callsign.
+#[must_use]
+pub fn tracker_address(
+    callsign: &str,
+    ogn_id: Option<&OGNBeaconID>,
+) -> (Option<ICAOAddress>, AddressType, Option<AircraftType>) {
+    if let Some(ogn_id) = ogn_id {
+        return (
+            Some(ogn_id.icao_address),
+            ogn_id.prefix.address_type.into(),
+            Some(ogn_id.prefix.aircraft_type.into()),
+        );
+    }
+    let address = callsign
+        .get(3..)
+        .filter(|suffix| suffix.len() == 6 && callsign[..3].bytes().all(|b| b.is_ascii_uppercase()))
+        .and_then(|suffix| u32::from_str_radix(suffix, 16).ok())
+        .and_then(|address| ICAOAddress::new(address).ok());
+    (address, AddressType::Unknown, None)
+}
+
+/// The OGN ID of an `idXXYYYYYY` comment token, if there is one.
+fn ogn_id(comment: &str) -> Option<OGNBeaconID> {
+    comment
+        .split_ascii_whitespace()
+        .filter_map(|token| token.strip_prefix("id"))
+        .find_map(|id| id.parse().ok())
+}
+
+/// The aircraft type shown by an APRS symbol of the primary table, if it shows one.
+fn aircraft_type_of_symbol(table: char, symbol: char) -> Option<AircraftType> {
+    if table != '/' {
+        return None;
+    }
+    match symbol {
+        '\'' => Some(AircraftType::ReciprocatingEngineAircraft),
+        '^' => Some(AircraftType::JetTurbopropAircraft),
+        'g' => Some(AircraftType::Glider),
+        'O' => Some(AircraftType::Balloon),
+        'X' => Some(AircraftType::Helicopter),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rstest::rstest]
+    #[case::ogn_id("FNT1103CE", Some("1E1103CE"), 0x0011_03CE, AddressType::Flarm)]
+    #[case::hex_callsign("ICA3E7540", None, 0x003E_7540, AddressType::Unknown)]
+    fn given_tracker_with_address_when_address_derived_then_it_is_kept(
+        #[case] callsign: &str,
+        #[case] ogn_id: Option<&str>,
+        #[case] expected: u32,
+        #[case] address_type: AddressType,
+    ) {
+        let ogn_id = ogn_id.map(|id| id.parse::<OGNBeaconID>().unwrap());
+
+        let (address, derived_type, _) = tracker_address(callsign, ogn_id.as_ref());
+
+        assert_eq!(
+            (address.map(|address| address.value()), derived_type),
+            (Some(expected), address_type)
+        );
+    }
+
+    #[rstest::rstest]
+    #[case::plain("N0CALL-11")]
+    #[case::lowercase_hex_callsign("icA3E7540")]
+    fn given_callsign_without_address_when_address_derived_then_there_is_none(
+        #[case] callsign: &str,
+    ) {
+        let (address, address_type, aircraft_type) = tracker_address(callsign, None);
+
+        assert_eq!(
+            (address, address_type, aircraft_type),
+            (None, AddressType::Unknown, None)
+        );
+    }
+}
@@ -0,0 +1,191 @@
+use crate::core::parser::beacon::BeaconComment;
+use crate::core::parser::decoders::aprs::parse_position_report;
+use crate::core::parser::decoders::{
+    AprsHeader, PositionDecoder, TrackPoint, TrackerKind, aircraft_type_of_symbol, ogn_id,
+    tracker_address,
+};
+use crate::core::parser::errors::DecodeError;
+
+/// Decodes the positions that OGN relays from other tracking networks. They use the
+/// aircraft beacon layout with their own destination and `id` token, e.g.
+/// `FNT1103CE>OGNFNT,qAS,FNB1103CE:/183727h5057.94N/00801.00Eg355/002/A=001042 !W10! id1E1103CE +03fpm`.
+#[derive(Debug, Clone, Copy)]
+pub struct OgnRelayDecoder {
+    kind: TrackerKind,
+}
+impl OgnRelayDecoder {
+    /// A decoder for `kind`, which accepts nothing when `kind` is not relayed by OGN.
+    #[must_use]
+    pub fn new(kind: TrackerKind) -> Self {
+        Self { kind }
+    }
+
+    /// The APRS destination OGN relays `kind` to.
+    #[must_use]
+    pub fn destination(kind: TrackerKind) -> Option<&'static str> {
+        match kind {
+            TrackerKind::Spot => Some("OGSPOT"),
+            TrackerKind::InReach => Some("OGINREACH"),
+            TrackerKind::Skylines => Some("OGSKYL"),
+            TrackerKind::LiveTrack24 => Some("OGLT24"),
+            TrackerKind::Fanet => Some("OGNFNT"),
+            TrackerKind::PilotAware => Some("OGPAW"),
+            TrackerKind::Aprs => None,
+        }
+    }
+}
+
+impl PositionDecoder for OgnRelayDecoder {
+    fn kind(&self) -> TrackerKind {
+        self.kind
+    }
+
+    fn accepts(&self, header: &AprsHeader<'_>) -> bool {
+        Self::destination(self.kind) == Some(header.destination)
+    }
+
+    fn decode(
+        &self,
+        header: &AprsHeader<'_>,
+        received: chrono::DateTime<chrono::Utc>,
+        max_clock_skew: chrono::TimeDelta,
+    ) -> Result<TrackPoint, DecodeError> {
+        let position = parse_position_report(header.body, received, max_clock_skew)?;
+        // FANET and PilotAware ids are OGN IDs; the satellite and internet services use
+        // their own, e.g. `id0-2860357` for SPOT.
+        let ogn_id = ogn_id(position.comment);
+        let (address, address_type, aircraft_type) =
+            tracker_address(header.callsign, ogn_id.as_ref());
+        let tracker_id = position
+            .comment
+            .split_ascii_whitespace()
+            .find_map(|token| token.strip_prefix("id"))
+            .filter(|id| !id.is_empty())
+            .map(str::to_string);
+        Ok(TrackPoint {
+            kind: self.kind,
+            callsign: header.callsign.to_string(),
+            address,
+            address_type,
+            aircraft_type: aircraft_type
+                .or_else(|| aircraft_type_of_symbol(position.symbol_table, position.symbol))
+                .unwrap_or_default(),
+            tracker_id,
+            receiver: header.receiver.to_string(),
+            datetime: position.datetime,
+            latitude: position.latitude,
+            longitude: position.longitude,
+            ground_track: position.ground_track,
+            ground_speed: position.ground_speed,
+            altitude: position.altitude,
+            climb_rate_metres_per_second: BeaconComment::parse(header.body.as_bytes())
+                .climb_rate_metres_per_second,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::parser::conversion::DEFAULT_MAX_CLOCK_SKEW;
+    use crate::core::parser::{AddressType, AircraftType};
+
+    fn received() -> chrono::DateTime<chrono::Utc> {
+        chrono::DateTime::parse_from_rfc3339("2024-06-01T18:37:30Z")
+            .unwrap()
+            .to_utc()
+    }
+
+    #[rstest::rstest]
+    #[case::fanet(
+        TrackerKind::Fanet,
+        "FNT1103CE>OGNFNT,qAS,FNB1103CE:/183727h5057.94N/00801.00Eg355/002/A=001042 !W10! id1E1103CE +03fpm",
+        Some("1E1103CE"),
+        0x0011_03CE
+    )]
+    #[case::pilot_aware(
+        TrackerKind::PilotAware,
+        "PAW404662>OGPAW,qAS,UKBFD:/183727h5134.15N/00122.03W'000/000/A=000502 !W75! id21404662 12.8dB +0.0kHz",
+        Some("21404662"),
+        0x0040_4662
+    )]
+    #[case::spot(
+        TrackerKind::Spot,
+        "ICA3E7540>OGSPOT,qAS,SPOT:/183727h1448.35S/04610.86W'000/000/A=008677 id0-2860357 SPOT3 GOOD",
+        Some("0-2860357"),
+        0x003E_7540
+    )]
+    #[case::in_reach(
+        TrackerKind::InReach,
+        "OGN8A0749>OGINREACH,qAS,InReach:/183727h0448.38N/07600.74W'000/000/A=004583 id300434060496190 inReac True",
+        Some("300434060496190"),
+        0x008A_0749
+    )]
+    #[case::skylines(
+        TrackerKind::Skylines,
+        "FLRDDE48A>OGSKYL,qAS,SKYLINES:/183727h4225.90N/00144.83E'000/000/A=008438 id2816 +000fpm",
+        Some("2816"),
+        0x00DD_E48A
+    )]
+    #[case::live_track24(
+        TrackerKind::LiveTrack24,
+        "FLRDDE48A>OGLT24,qAS,LT24:/183727h4030.47N/00338.38W'000/018/A=002267 id25387 +000fpm GPS",
+        Some("25387"),
+        0x00DD_E48A
+    )]
+    fn given_relayed_tracker_report_when_decoded_then_it_becomes_a_track_point(
+        #[case] kind: TrackerKind,
+        #[case] message: &str,
+        #[case] tracker_id: Option<&str>,
+        #[case] address: u32,
+    ) {
+        let header = AprsHeader::parse(message).unwrap();
+        let decoder = kind.decoder();
+        assert!(decoder.accepts(&header));
+
+        let point = decoder
+            .decode(&header, received(), DEFAULT_MAX_CLOCK_SKEW)
+            .unwrap();
+
+        assert_eq!(point.kind, kind);
+        assert_eq!(point.tracker_id.as_deref(), tracker_id);
+        assert_eq!(
+            point.address.map(|point_address| point_address.value()),
+            Some(address)
+        );
+        assert_eq!(
+            point.datetime,
+            chrono::DateTime::parse_from_rfc3339("2024-06-01T18:37:27Z").unwrap()
+        );
+    }
+
+    #[test]
+    fn given_fanet_report_when_decoded_then_ogn_id_and_comment_are_used() {
+        let header = AprsHeader::parse("FNT1103CE>OGNFNT,qAS,FNB1103CE:/183727h5057.94N/00801.00Eg355/002/A=001042 !W10! id1E1103CE +03fpm").unwrap();
+
+        let point = OgnRelayDecoder::new(TrackerKind::Fanet)
+            .decode(&header, received(), DEFAULT_MAX_CLOCK_SKEW)
+            .unwrap();
+
+        assert_eq!(point.address_type, AddressType::Flarm);
+        assert_eq!(point.aircraft_type, AircraftType::Paraglider);
+        assert!((point.latitude - (50.0 + 57.941 / 60.0)).abs() < 1e-9);
+        assert_eq!(
+            (point.ground_track, point.ground_speed, point.altitude),
+            (Some(355.0), Some(2.0), Some(1042.0))
+        );
+        assert!((point.climb_rate_metres_per_second.unwrap() - 0.015_24).abs() < 1e-9);
+    }
+
+    #[test]
+    fn given_other_destination_when_checked_then_relay_decoders_do_not_accept_it() {
+        let header = AprsHeader::parse(
+            "FLRDDA5BA>OGFLR,qAS,LFNW:/163148h4559.85N/00348.19E'342/012/A=001345 id06DDA5BA",
+        )
+        .unwrap();
+
+        for kind in [TrackerKind::Fanet, TrackerKind::Spot, TrackerKind::Aprs] {
+            assert!(!OgnRelayDecoder::new(kind).accepts(&header));
+        }
+    }
+}
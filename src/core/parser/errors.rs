@@ -0,0 +1,52 @@
+#[derive(Debug, thiserror::Error)]
+pub enum AircraftConversionError {
+    #[error("Missing datetime field")]
+    MissingDatetime,
+    #[error("Datetime out of range: {seconds}s {nanos}ns")]
+    InvalidDatetime { seconds: i64, nanos: i32 },
+    #[error("Invalid aircraft type code: {0}")]
+    InvalidAircraftType(u32),
+    #[error("Invalid address type code: {0}")]
+    InvalidAddressType(u32),
+    #[error("GPS accuracy out of range: {horizontal_metres}m x {vertical_metres}m")]
+    InvalidGpsAccuracy {
+        horizontal_metres: u32,
+        vertical_metres: u32,
+    },
+    #[error("Invalid ICAO address: {0}")]
+    InvalidIcaoAddress(#[from] ogn_aprs_parser::errors::ICAOAddressError),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum BeaconConversionError {
+    #[error("Beacon time {beacon_time} is {skew} away from its receive time {received}")]
+    ClockSkew {
+        beacon_time: chrono::NaiveTime,
+        received: chrono::DateTime<chrono::Utc>,
+        skew: chrono::TimeDelta,
+    },
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ReceiverBeaconError {
+    #[error("Not a receiver beacon")]
+    NotReceiverBeacon,
+    #[error("Invalid receiver beacon timestamp: {0}")]
+    InvalidTimestamp(String),
+    #[error("Invalid receiver position: {0}")]
+    InvalidPosition(String),
+    #[error(transparent)]
+    Conversion(#[from] BeaconConversionError),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DecodeError {
+    #[error("Unsupported position report: {0}")]
+    Unsupported(String),
+    #[error("Invalid position report timestamp: {0}")]
+    InvalidTimestamp(String),
+    #[error("Invalid position report position: {0}")]
+    InvalidPosition(String),
+    #[error(transparent)]
+    Conversion(#[from] BeaconConversionError),
+}
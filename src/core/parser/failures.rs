@@ -0,0 +1,162 @@
+use ogn_aprs_parser::errors::{APRSMessageParseError, AircraftParseError};
+
+use crate::core::ingestor::AprsPacket;
+use crate::core::parser::errors::DecodeError;
+
+/// Raw lines kept per class of failure when no other capacity is given.
+pub const DEFAULT_FAILURE_SAMPLE_CAPACITY: usize = 20;
+
+/// Why a line from APRS-IS could not be turned into an aircraft or a receiver beacon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParseFailureKind {
+    /// A `#` line from the APRS-IS server rather than a beacon.
+    ServerComment,
+    /// A ground station beacon that did not parse.
+    ReceiverBeacon,
+    /// A beacon in a format neither the OGN parser nor a position decoder handles, e.g.
+    /// another destination, q-construct or ID format.
+    UnsupportedBeacon,
+    /// A beacon whose coordinates, track, speed or altitude do not parse.
+    MalformedPosition,
+    /// A beacon whose timestamp does not parse.
+    BadTimestamp,
+}
+impl ParseFailureKind {
+    pub const ALL: [Self; 5] = [
+        Self::ServerComment,
+        Self::ReceiverBeacon,
+        Self::UnsupportedBeacon,
+        Self::MalformedPosition,
+        Self::BadTimestamp,
+    ];
+
+    /// Position of the kind in [`Self::ALL`].
+    pub(super) fn index(self) -> usize {
+        match self {
+            Self::ServerComment => 0,
+            Self::ReceiverBeacon => 1,
+            Self::UnsupportedBeacon => 2,
+            Self::MalformedPosition => 3,
+            Self::BadTimestamp => 4,
+        }
+    }
+
+    /// Classifies an error of the OGN aircraft beacon parser.
+    #[must_use]
+    pub fn of_aircraft_error(error: &AircraftParseError) -> Self {
+        match error {
+            AircraftParseError::ParseError(
+                APRSMessageParseError::InvalidLatitude(_)
+                | APRSMessageParseError::InvalidLongitude(_)
+                | APRSMessageParseError::InvalidGroundTrack(_)
+                | APRSMessageParseError::InvalidGroundSpeed(_)
+                | APRSMessageParseError::InvalidGPSAltitude(_),
+            ) => Self::MalformedPosition,
+            AircraftParseError::ParseError(APRSMessageParseError::InvalidTimestamp(_)) => {
+                Self::BadTimestamp
+            }
+            AircraftParseError::ParseError(_) | AircraftParseError::MissingOGNBeaconID => {
+                Self::UnsupportedBeacon
+            }
+        }
+    }
+
+    /// Classifies an error of a [`PositionDecoder`](crate::core::parser::decoders::PositionDecoder),
+    /// or `None` for a report rejected for clock skew, which is counted apart.
+    #[must_use]
+    pub fn of_decode_error(error: &DecodeError) -> Option<Self> {
+        match error {
+            DecodeError::Unsupported(_) => Some(Self::UnsupportedBeacon),
+            DecodeError::InvalidTimestamp(_) => Some(Self::BadTimestamp),
+            DecodeError::InvalidPosition(_) => Some(Self::MalformedPosition),
+            DecodeError::Conversion(_) => None,
+        }
+    }
+}
+impl std::fmt::Display for ParseFailureKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::ServerComment => "server comment",
+            Self::ReceiverBeacon => "receiver beacon",
+            Self::UnsupportedBeacon => "unsupported beacon",
+            Self::MalformedPosition => "malformed position",
+            Self::BadTimestamp => "bad timestamp",
+        })
+    }
+}
+
+/// A line that failed to parse, as kept in the sample buffers and written to the
+/// quarantine file.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ParseFailure {
+    pub kind: ParseFailureKind,
+    pub received: chrono::DateTime<chrono::Utc>,
+    /// Name of the ingestor source the line came from.
+    pub source: String,
+    /// The raw line, without its line ending.
+    pub line: String,
+    pub error: String,
+}
+impl ParseFailure {
+    #[must_use]
+    pub fn new(kind: ParseFailureKind, aprs_packet: &AprsPacket, error: impl ToString) -> Self {
+        Self {
+            kind,
+            received: aprs_packet.timestamp.into(),
+            source: aprs_packet.source.to_string(),
+            line: String::from_utf8_lossy(&aprs_packet.message)
+                .trim_end_matches(['\r', '\n'])
+                .to_owned(),
+            error: error.to_string(),
+        }
+    }
+}
+
+/// Number of failures of each kind.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseFailureCounts([u64; ParseFailureKind::ALL.len()]);
+impl ParseFailureCounts {
+    pub(super) fn from_counts(counts: [u64; ParseFailureKind::ALL.len()]) -> Self {
+        Self(counts)
+    }
+
+    #[must_use]
+    pub fn get(&self, kind: ParseFailureKind) -> u64 {
+        self.0[kind.index()]
+    }
+
+    #[must_use]
+    pub fn total(&self) -> u64 {
+        self.0.iter().sum()
+    }
+}
+impl std::fmt::Display for ParseFailureCounts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut separator = "";
+        for kind in ParseFailureKind::ALL {
+            write!(f, "{separator}{} {kind}", self.get(kind))?;
+            separator = ", ";
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rstest::rstest]
+    #[case::position(&b"FLRDDA5BA>OGFLR,qAS,LFNW:/163148h45X9.85N/00348.19E'342/012/A=001345 !W06! id0ADDA5BA"[..], ParseFailureKind::MalformedPosition)]
+    #[case::timestamp(&b"FLRDDA5BA>OGFLR,qAS,LFNW:/16x148h4559.85N/00348.19E'342/012/A=001345 !W06! id0ADDA5BA"[..], ParseFailureKind::BadTimestamp)]
+    #[case::no_id(&b"FLRDDA5BA>OGFLR,qAS,LFNW:/163148h4559.85N/00348.19E'342/012/A=001345 !W06!"[..], ParseFailureKind::UnsupportedBeacon)]
+    #[case::not_aprs(&b"not a beacon"[..], ParseFailureKind::UnsupportedBeacon)]
+    fn given_malformed_aircraft_beacon_when_classified_then_kind_names_the_faulty_part(
+        #[case] message: &[u8],
+        #[case] expected: ParseFailureKind,
+    ) {
+        let error = ogn_aprs_parser::parse_ogn_aprs_aircraft_beacon(message).unwrap_err();
+
+        assert_eq!(ParseFailureKind::of_aircraft_error(&error), expected);
+    }
+}
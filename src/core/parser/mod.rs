@@ -1,5 +1,24 @@
+mod beacon;
 mod conversion;
+pub mod decoders;
+pub mod errors;
+mod failures;
+mod pool;
+pub mod protobuf;
+mod receiver;
+mod stats;
 mod task;
 
+pub use beacon::{AddressType, AircraftType, GpsAccuracy};
 pub use conversion::Aircraft;
+pub use failures::{
+    DEFAULT_FAILURE_SAMPLE_CAPACITY, ParseFailure, ParseFailureCounts, ParseFailureKind,
+};
+pub use pool::{ParserDispatcher, shard_of};
+pub use protobuf::PbAircraft;
+pub use receiver::{
+    ReceiverBeacon, ReceiverPosition, ReceiverReport, ReceiverStatus, is_receiver_beacon,
+    parse_receiver_beacon,
+};
+pub use stats::{ParserMonitor, ParserStats};
 pub use task::AircraftParser;
@@ -0,0 +1,119 @@
+use std::hash::{Hash, Hasher};
+
+use crate::core::channel::{ChannelReceiver, ChannelSender};
+use crate::core::ingestor::AprsPacket;
+use crate::core::thread_manager::{SteppableTask, TaskState, Wakeup};
+
+/// Spreads packets over several [`AircraftParser`](crate::core::parser::AircraftParser)
+/// workers, each fed by its own channel.
+///
+/// Packets are routed on a hash of their sender callsign, so every beacon of an aircraft
+/// or station goes to the same worker and is parsed in the order it was received. The
+/// workers share one output channel, where beacons of different senders interleave.
+pub struct ParserDispatcher {
+    receiver: ChannelReceiver<AprsPacket>,
+    workers: Vec<ChannelSender<AprsPacket>>,
+}
+impl ParserDispatcher {
+    /// # Panics
+    ///
+    /// If `workers` is empty.
+    #[must_use]
+    pub fn new(
+        receiver: impl Into<ChannelReceiver<AprsPacket>>,
+        workers: impl IntoIterator<Item = impl Into<ChannelSender<AprsPacket>>>,
+    ) -> Self {
+        let workers: Vec<_> = workers.into_iter().map(Into::into).collect();
+        assert!(!workers.is_empty(), "ParserDispatcher needs a worker");
+        Self {
+            receiver: receiver.into(),
+            workers,
+        }
+    }
+}
+
+impl SteppableTask for ParserDispatcher {
+    fn step(&mut self) -> TaskState {
+        let packet = match self.receiver.try_recv() {
+            Ok(packet) => packet,
+            Err(crossbeam_channel::TryRecvError::Empty) => return TaskState::Running,
+            Err(crossbeam_channel::TryRecvError::Disconnected) => {
+                // Dropping the worker senders with the task lets the workers complete too.
+                log::info!("ParserDispatcher upstream disconnected. Task complete");
+                return TaskState::Completed;
+            }
+        };
+
+        let worker = shard_of(&packet.message, self.workers.len());
+        if let Err(err) = self.workers[worker].send(packet) {
+            log::error!("ParserDispatcher: Failed to send to worker {worker}: {err}");
+        }
+        TaskState::Running
+    }
+
+    fn wakeup(&self) -> Option<Wakeup<'_>> {
+        Some(Wakeup::on(&self.receiver))
+    }
+}
+
+/// The worker out of `shards` parsing `message`, picked from its sender callsign.
+#[must_use]
+pub fn shard_of(message: &[u8], shards: usize) -> usize {
+    let callsign = message.split(|&b| b == b'>').next().unwrap_or_default();
+    let mut hasher = std::hash::DefaultHasher::new();
+    callsign.hash(&mut hasher);
+    // The remainder is below `shards`, so it fits back in a usize.
+    #[allow(clippy::cast_possible_truncation)]
+    let shard = (hasher.finish() % shards as u64) as usize;
+    shard
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packet(callsign: &str, sequence: u32) -> AprsPacket {
+        AprsPacket {
+            timestamp: std::time::UNIX_EPOCH,
+            message: format!("{callsign}>OGFLR,qAS,LFNW:/{sequence:06}h").into(),
+            source: "test".into(),
+        }
+    }
+
+    #[test]
+    fn given_packets_of_many_senders_when_dispatched_then_each_sender_stays_on_one_worker_in_order()
+    {
+        let (packet_sender, packet_receiver) = crossbeam_channel::unbounded();
+        let (worker_senders, worker_receivers): (Vec<_>, Vec<_>) =
+            (0..4).map(|_| crossbeam_channel::unbounded()).unzip();
+        let mut dispatcher = ParserDispatcher::new(packet_receiver, worker_senders);
+        let callsigns: Vec<_> = (0..32).map(|index| format!("FLR{index:06X}")).collect();
+        for sequence in 0..10 {
+            for callsign in &callsigns {
+                packet_sender.send(packet(callsign, sequence)).unwrap();
+            }
+        }
+        drop(packet_sender);
+
+        while matches!(dispatcher.step(), TaskState::Running) {}
+
+        let mut busy_workers = 0;
+        for (worker, receiver) in worker_receivers.iter().enumerate() {
+            let packets: Vec<_> = receiver.try_iter().collect();
+            busy_workers += usize::from(!packets.is_empty());
+            for callsign in &callsigns {
+                let sequence: Vec<_> = packets
+                    .iter()
+                    .filter(|packet| packet.message.starts_with(callsign.as_bytes()))
+                    .collect();
+                if shard_of(callsign.as_bytes(), 4) == worker {
+                    let expected: Vec<_> = (0..10).map(|index| packet(callsign, index)).collect();
+                    assert_eq!(sequence, expected.iter().collect::<Vec<_>>());
+                } else {
+                    assert!(sequence.is_empty());
+                }
+            }
+        }
+        assert_eq!(busy_workers, 4);
+    }
+}
@@ -0,0 +1,186 @@
+use ogn_aprs_parser::ICAOAddress;
+
+use crate::core::parser::errors::AircraftConversionError;
+use crate::core::parser::protobuf::{PbAircraft, PbGpsAccuracy};
+use crate::core::parser::{AddressType, Aircraft, AircraftType, GpsAccuracy};
+
+/// Converts a protobuf timestamp to a UTC datetime, rejecting timestamps chrono cannot represent.
+pub fn datetime_from_timestamp(
+    timestamp: Option<prost_types::Timestamp>,
+) -> Result<chrono::DateTime<chrono::Utc>, AircraftConversionError> {
+    let prost_types::Timestamp { seconds, nanos } =
+        timestamp.ok_or(AircraftConversionError::MissingDatetime)?;
+    u32::try_from(nanos)
+        .ok()
+        .and_then(|subsec_nanos| chrono::DateTime::from_timestamp(seconds, subsec_nanos))
+        .ok_or(AircraftConversionError::InvalidDatetime { seconds, nanos })
+}
+
+#[must_use]
+pub fn timestamp_from_datetime(datetime: chrono::DateTime<chrono::Utc>) -> prost_types::Timestamp {
+    prost_types::Timestamp {
+        seconds: datetime.timestamp(),
+        nanos: i32::try_from(datetime.timestamp_subsec_nanos()).unwrap_or(i32::MAX),
+    }
+}
+
+impl TryFrom<PbAircraft> for Aircraft {
+    type Error = AircraftConversionError;
+    fn try_from(aircraft: PbAircraft) -> Result<Self, Self::Error> {
+        Ok(Self {
+            callsign: aircraft.callsign,
+            icao_address: aircraft.icao_address.map(ICAOAddress::new).transpose()?,
+            datetime: datetime_from_timestamp(aircraft.datetime)?,
+            latitude: aircraft.latitude,
+            longitude: aircraft.longitude,
+            ground_track: aircraft.ground_track,
+            ground_speed_knots: aircraft.ground_speed_knots,
+            gps_altitude_feet: aircraft.gps_altitude_feet,
+            receiver: aircraft.receiver,
+            aircraft_type: u8::try_from(aircraft.aircraft_type)
+                .ok()
+                .and_then(AircraftType::from_code)
+                .ok_or(AircraftConversionError::InvalidAircraftType(
+                    aircraft.aircraft_type,
+                ))?,
+            address_type: u8::try_from(aircraft.address_type)
+                .ok()
+                .and_then(AddressType::from_code)
+                .ok_or(AircraftConversionError::InvalidAddressType(
+                    aircraft.address_type,
+                ))?,
+            stealth: aircraft.stealth,
+            no_tracking: aircraft.no_tracking,
+            climb_rate_metres_per_second: aircraft.climb_rate_metres_per_second,
+            turn_rate_degrees_per_second: aircraft.turn_rate_degrees_per_second,
+            signal_quality_db: aircraft.signal_quality_db,
+            frequency_offset_khz: aircraft.frequency_offset_khz,
+            gps_accuracy: aircraft
+                .gps_accuracy
+                .map(GpsAccuracy::try_from)
+                .transpose()?,
+        })
+    }
+}
+
+impl TryFrom<PbGpsAccuracy> for GpsAccuracy {
+    type Error = AircraftConversionError;
+    fn try_from(accuracy: PbGpsAccuracy) -> Result<Self, Self::Error> {
+        let PbGpsAccuracy {
+            horizontal_metres,
+            vertical_metres,
+        } = accuracy;
+        u16::try_from(horizontal_metres)
+            .ok()
+            .zip(u16::try_from(vertical_metres).ok())
+            .map(|(horizontal_metres, vertical_metres)| Self {
+                horizontal_metres,
+                vertical_metres,
+            })
+            .ok_or(AircraftConversionError::InvalidGpsAccuracy {
+                horizontal_metres,
+                vertical_metres,
+            })
+    }
+}
+
+impl From<Aircraft> for PbAircraft {
+    fn from(aircraft: Aircraft) -> Self {
+        Self {
+            callsign: aircraft.callsign,
+            icao_address: aircraft
+                .icao_address
+                .map(|icao_address| icao_address.value()),
+            datetime: Some(timestamp_from_datetime(aircraft.datetime)),
+            latitude: aircraft.latitude,
+            longitude: aircraft.longitude,
+            ground_track: aircraft.ground_track,
+            ground_speed_knots: aircraft.ground_speed_knots,
+            gps_altitude_feet: aircraft.gps_altitude_feet,
+            receiver: aircraft.receiver,
+            aircraft_type: aircraft.aircraft_type.code().into(),
+            address_type: aircraft.address_type.code().into(),
+            stealth: aircraft.stealth,
+            no_tracking: aircraft.no_tracking,
+            climb_rate_metres_per_second: aircraft.climb_rate_metres_per_second,
+            turn_rate_degrees_per_second: aircraft.turn_rate_degrees_per_second,
+            signal_quality_db: aircraft.signal_quality_db,
+            frequency_offset_khz: aircraft.frequency_offset_khz,
+            gps_accuracy: aircraft.gps_accuracy.map(|accuracy| PbGpsAccuracy {
+                horizontal_metres: accuracy.horizontal_metres.into(),
+                vertical_metres: accuracy.vertical_metres.into(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prost::Message;
+
+    use super::*;
+    use crate::test_utilities::create_dummy_aircraft_at_time;
+
+    #[test]
+    fn given_aircraft_when_converted_to_proto_and_back_then_it_is_unchanged() {
+        let aircraft = Aircraft {
+            latitude: 45.9975,
+            longitude: 3.8031,
+            gps_altitude_feet: Some(410.0),
+            receiver: "LFNW".into(),
+            aircraft_type: AircraftType::Glider,
+            address_type: AddressType::Flarm,
+            no_tracking: true,
+            climb_rate_metres_per_second: Some(-2.3),
+            signal_quality_db: Some(8.8),
+            gps_accuracy: Some(GpsAccuracy {
+                horizontal_metres: 2,
+                vertical_metres: 3,
+            }),
+            ..create_dummy_aircraft_at_time(
+                chrono::DateTime::from_timestamp(1_700_000_000, 123_000_000).unwrap(),
+                ICAOAddress::new(0x00DD_A5BA).unwrap(),
+            )
+        };
+
+        let encoded = PbAircraft::from(aircraft.clone()).encode_to_vec();
+        let decoded = Aircraft::try_from(PbAircraft::decode(encoded.as_slice()).unwrap()).unwrap();
+
+        assert_eq!(decoded, aircraft);
+    }
+
+    #[test]
+    fn given_tracker_without_address_or_motion_when_converted_to_proto_and_back_then_they_stay_missing()
+     {
+        let aircraft = Aircraft {
+            icao_address: None,
+            ground_track: None,
+            ground_speed_knots: None,
+            gps_altitude_feet: None,
+            ..create_dummy_aircraft_at_time(chrono::Utc::now(), ICAOAddress::new(1).unwrap())
+        };
+
+        let encoded = PbAircraft::from(aircraft.clone()).encode_to_vec();
+        let decoded = Aircraft::try_from(PbAircraft::decode(encoded.as_slice()).unwrap()).unwrap();
+
+        assert_eq!(decoded, aircraft);
+    }
+
+    #[rstest::rstest]
+    #[case(PbAircraft { icao_address: Some(0x0100_0000), ..valid_proto() })]
+    #[case(PbAircraft { datetime: None, ..valid_proto() })]
+    #[case(PbAircraft { aircraft_type: 16, ..valid_proto() })]
+    #[case(PbAircraft { address_type: 4, ..valid_proto() })]
+    #[case(PbAircraft { gps_accuracy: Some(PbGpsAccuracy { horizontal_metres: 70_000, vertical_metres: 3 }), ..valid_proto() })]
+    #[case(PbAircraft { datetime: Some(prost_types::Timestamp { seconds: 0, nanos: -1 }), ..valid_proto() })]
+    fn given_invalid_proto_when_converted_then_returns_error(#[case] proto: PbAircraft) {
+        assert!(Aircraft::try_from(proto).is_err());
+    }
+
+    fn valid_proto() -> PbAircraft {
+        PbAircraft::from(create_dummy_aircraft_at_time(
+            chrono::Utc::now(),
+            ICAOAddress::new(1).unwrap(),
+        ))
+    }
+}
@@ -0,0 +1,3 @@
+include!(concat!(env!("OUT_DIR"), "/parser.rs"));
+
+pub mod conversion;
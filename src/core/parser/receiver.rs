@@ -0,0 +1,309 @@
+use crate::core::parser::conversion::infer_beacon_datetime;
+use crate::core::parser::errors::ReceiverBeaconError;
+
+const FEET_TO_METRES: f64 = 0.3048;
+
+/// A beacon sent by an OGN ground station about itself, either where it is or how it is
+/// doing.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ReceiverBeacon {
+    /// Callsign of the station.
+    pub name: String,
+    /// The APRS-IS server the station is connected to, e.g. `GLIDERN2`.
+    pub server: String,
+    pub datetime: chrono::DateTime<chrono::Utc>,
+    pub report: ReceiverReport,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiverReport {
+    Position(ReceiverPosition),
+    Status(ReceiverStatus),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ReceiverPosition {
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Above mean sea level, when reported.
+    pub altitude_metres: Option<f64>,
+}
+
+/// Health of a station as reported by its status beacon. Each value is unset when the
+/// station's software does not report it.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ReceiverStatus {
+    /// Version of the receiver software, e.g. `0.2.8`.
+    pub version: Option<String>,
+    /// Hardware or build the software runs on, e.g. `RPI-GPU`.
+    pub platform: Option<String>,
+    pub cpu_load: Option<f64>,
+    pub ram_free_mb: Option<f64>,
+    pub ram_total_mb: Option<f64>,
+    pub ntp_offset_ms: Option<f64>,
+    pub ntp_correction_ppm: Option<f64>,
+    pub voltage_volts: Option<f64>,
+    pub current_amperes: Option<f64>,
+    pub cpu_temperature_celsius: Option<f64>,
+    /// Aircraft heard in the last hour, and how many of them were seen in range.
+    pub visible_senders: Option<u32>,
+    pub senders: Option<u32>,
+    /// Noise level at the receiver's input.
+    pub rf_noise_db: Option<f64>,
+}
+
+/// Whether `message` comes from a ground station rather than an aircraft: stations use
+/// the `OGNSDR` destination, or, with older software, log in to APRS-IS themselves, so
+/// their path holds `TCPIP*`, and send `>` statuses or `/` positions with the igate
+/// symbol (`I&`), stamped `HHMMSSh`. Other clients logged in directly, such as APRSdroid
+/// phones, send other reports.
+#[must_use]
+pub fn is_receiver_beacon(message: &[u8]) -> bool {
+    let Some(colon) = message.iter().position(|byte| *byte == b':') else {
+        return false;
+    };
+    let (header, body) = (&message[..colon], &message[colon + 1..]);
+    let Some(path_start) = header.iter().position(|byte| *byte == b'>') else {
+        return false;
+    };
+    let mut path = header[path_start + 1..].split(|byte| *byte == b',');
+    if path.next() == Some(b"OGNSDR") {
+        return true;
+    }
+    if !path.any(|hop| hop == b"TCPIP*")
+        || body.len() < 8
+        || !body[1..7].iter().all(u8::is_ascii_digit)
+        || body[7] != b'h'
+    {
+        return false;
+    }
+    match body[0] {
+        // The symbol table follows the 8 byte latitude, the symbol the 9 byte longitude.
+        b'/' => body.get(16) == Some(&b'I') && body.get(26) == Some(&b'&'),
+        b'>' => true,
+        _ => false,
+    }
+}
+
+/// Parses a receiver position (`/`) or status (`>`) beacon received at `received`. Its
+/// time of day is dated as by [`infer_beacon_datetime`].
+pub fn parse_receiver_beacon(
+    message: &[u8],
+    received: chrono::DateTime<chrono::Utc>,
+    max_clock_skew: chrono::TimeDelta,
+) -> Result<ReceiverBeacon, ReceiverBeaconError> {
+    if !is_receiver_beacon(message) {
+        return Err(ReceiverBeaconError::NotReceiverBeacon);
+    }
+    let message = String::from_utf8_lossy(message);
+    let (header, body) = message
+        .split_once(':')
+        .ok_or(ReceiverBeaconError::NotReceiverBeacon)?;
+    let (name, path) = header
+        .split_once('>')
+        .ok_or(ReceiverBeaconError::NotReceiverBeacon)?;
+    let server = path.rsplit(',').next().unwrap_or_default();
+
+    let mut chars = body.chars();
+    let kind = chars.next();
+    let body = chars.as_str();
+    let time = body
+        .get(..7)
+        .filter(|time| time.ends_with('h'))
+        .and_then(|time| chrono::NaiveTime::parse_from_str(&time[..6], "%H%M%S").ok())
+        .ok_or_else(|| ReceiverBeaconError::InvalidTimestamp(body.to_string()))?;
+    let rest = &body[7..];
+    let report = match kind {
+        Some('/') => ReceiverReport::Position(parse_position(rest)?),
+        Some('>') => ReceiverReport::Status(parse_status(rest)),
+        _ => return Err(ReceiverBeaconError::NotReceiverBeacon),
+    };
+    Ok(ReceiverBeacon {
+        name: name.to_string(),
+        server: server.to_string(),
+        datetime: infer_beacon_datetime(time, received, max_clock_skew)?,
+        report,
+    })
+}
+
+/// Parses `DDMM.mmN`, a symbol table character, `DDDMM.mmE`, a symbol character, then an
+/// optional `/A=` altitude in feet.
+fn parse_position(position: &str) -> Result<ReceiverPosition, ReceiverBeaconError> {
+    let invalid = || ReceiverBeaconError::InvalidPosition(position.to_string());
+    let latitude = position
+        .get(..8)
+        .and_then(|latitude| parse_coordinate(latitude, 2, 'N', 'S'))
+        .ok_or_else(invalid)?;
+    let longitude = position
+        .get(9..18)
+        .and_then(|longitude| parse_coordinate(longitude, 3, 'E', 'W'))
+        .ok_or_else(invalid)?;
+    let altitude_metres = position.split_once("A=").and_then(|(_, altitude)| {
+        altitude
+            .get(..6)
+            .and_then(|feet| feet.parse::<f64>().ok())
+            .map(|feet| feet * FEET_TO_METRES)
+    });
+    Ok(ReceiverPosition {
+        latitude,
+        longitude,
+        altitude_metres,
+    })
+}
+
+pub(super) fn parse_coordinate(
+    coordinate: &str,
+    degree_digits: usize,
+    positive: char,
+    negative: char,
+) -> Option<f64> {
+    let hemisphere = coordinate.chars().last()?;
+    let value = &coordinate[..coordinate.len() - hemisphere.len_utf8()];
+    let degrees = value.get(..degree_digits)?.parse::<f64>().ok()?;
+    let minutes = value.get(degree_digits..)?.parse::<f64>().ok()?;
+    let magnitude = degrees + minutes / 60.0;
+    match hemisphere {
+        hemisphere if hemisphere == positive => Some(magnitude),
+        hemisphere if hemisphere == negative => Some(-magnitude),
+        _ => None,
+    }
+}
+
+/// Reads the whitespace separated tokens of a status beacon, e.g.
+/// `v0.2.8.RPI-GPU CPU:0.7 RAM:770.2/972.2MB NTP:0.4ms/-6.1ppm 4.963V 0.492A +49.4C
+/// 2/3Acfts[1h] RF:+53-2.2ppm/+4.17dB`. Unknown or malformed tokens are skipped.
+fn parse_status(status: &str) -> ReceiverStatus {
+    let mut parsed = ReceiverStatus::default();
+    for token in status.split_ascii_whitespace() {
+        if let Some(version) = token.strip_prefix('v')
+            && version.starts_with(|first: char| first.is_ascii_digit())
+        {
+            // The version is made of the leading numeric parts, the platform of the rest.
+            let version_len = version
+                .split('.')
+                .take_while(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()))
+                .map(|part| part.len() + 1)
+                .sum::<usize>()
+                .saturating_sub(1);
+            parsed.version =
+                Some(version[..version_len].to_string()).filter(|version| !version.is_empty());
+            parsed.platform = version
+                .get(version_len + 1..)
+                .filter(|platform| !platform.is_empty())
+                .map(str::to_string);
+        } else if let Some(cpu) = token.strip_prefix("CPU:") {
+            parsed.cpu_load = cpu.parse().ok();
+        } else if let Some(ram) = token.strip_prefix("RAM:") {
+            let (free, total) = split_pair(ram.trim_end_matches("MB"));
+            parsed.ram_free_mb = free;
+            parsed.ram_total_mb = total;
+        } else if let Some(ntp) = token.strip_prefix("NTP:") {
+            let (offset, correction) = ntp.split_once('/').unwrap_or((ntp, ""));
+            parsed.ntp_offset_ms = offset.trim_end_matches("ms").parse().ok();
+            parsed.ntp_correction_ppm = correction.trim_end_matches("ppm").parse().ok();
+        } else if let Some(rf) = token.strip_prefix("RF:") {
+            parsed.rf_noise_db = rf
+                .split('/')
+                .nth(1)
+                .and_then(|noise| noise.strip_suffix("dB"))
+                .and_then(|noise| noise.parse().ok());
+        } else if let Some((senders, _)) = token.split_once("Acfts") {
+            let (visible, total) = split_pair(senders);
+            parsed.visible_senders = visible;
+            parsed.senders = total;
+        } else if let Some(volts) = token.strip_suffix('V') {
+            parsed.voltage_volts = volts.parse().ok();
+        } else if let Some(amperes) = token.strip_suffix('A') {
+            parsed.current_amperes = amperes.parse().ok();
+        } else if let Some(celsius) = token.strip_suffix('C') {
+            parsed.cpu_temperature_celsius = celsius.parse().ok();
+        }
+    }
+    parsed
+}
+
+fn split_pair<T: std::str::FromStr>(pair: &str) -> (Option<T>, Option<T>) {
+    let (first, second) = pair.split_once('/').unwrap_or((pair, ""));
+    (first.parse().ok(), second.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::parser::conversion::DEFAULT_MAX_CLOCK_SKEW;
+
+    fn received() -> chrono::DateTime<chrono::Utc> {
+        chrono::DateTime::parse_from_rfc3339("2024-06-01T16:31:30Z")
+            .unwrap()
+            .to_utc()
+    }
+
+    #[rstest::rstest]
+    #[case::sdr(&b"LFNW>OGNSDR,TCPIP*,qAC,GLIDERN2:/163125h4545.85NI00404.72E&/A=001362"[..], true)]
+    #[case::legacy(&b"LFNW>APRS,TCPIP*,qAC,GLIDERN2:>163125h v0.2.8.RPI-GPU"[..], true)]
+    #[case::legacy_position(&b"LFNW>APRS,TCPIP*,qAC,GLIDERN2:/163125h4545.85NI00404.72E&/A=001362"[..], true)]
+    #[case::aprs_timestamped_position(&b"N0CALL-10>APDR16,TCPIP*,qAC,T2POLAND:/163125h5050.73N/00413.19E$/A=000100"[..], false)]
+    #[case::aircraft(&b"FLRDDA5BA>OGFLR,qAS,LFNW:/163148h4559.85N/00348.19E'342/012/A=001345"[..], false)]
+    #[case::server(&b"# aprsc 2.1.4-g408ed49"[..], false)]
+    #[case::aprsdroid(&b"N0CALL-10>APDR16,TCPIP*,qAC,T2POLAND:=5050.73N/00413.19E$/A=000100 https://aprsdroid.org/"[..], false)]
+    #[case::aprs_status(&b"N0CALL-10>APDR16,TCPIP*,qAC,T2POLAND:>APRSdroid status"[..], false)]
+    fn given_message_when_checked_then_receiver_beacons_are_recognised(
+        #[case] message: &[u8],
+        #[case] expected: bool,
+    ) {
+        assert_eq!(is_receiver_beacon(message), expected);
+    }
+
+    #[test]
+    fn given_position_beacon_when_parsed_then_location_and_altitude_are_read() {
+        let message = b"LFNW>OGNSDR,TCPIP*,qAC,GLIDERN2:/163125h4545.85NI00404.72W&/A=001362";
+
+        let beacon = parse_receiver_beacon(message, received(), DEFAULT_MAX_CLOCK_SKEW).unwrap();
+
+        assert_eq!(beacon.name, "LFNW");
+        assert_eq!(beacon.server, "GLIDERN2");
+        assert_eq!(beacon.datetime.to_rfc3339(), "2024-06-01T16:31:25+00:00");
+        let ReceiverReport::Position(position) = beacon.report else {
+            panic!("expected a position, got {:?}", beacon.report);
+        };
+        assert!((position.latitude - 45.764_166_666).abs() < 1e-6);
+        assert!((position.longitude + 4.078_666_666).abs() < 1e-6);
+        assert!((position.altitude_metres.unwrap() - 415.137_6).abs() < 1e-6);
+    }
+
+    #[test]
+    fn given_status_beacon_when_parsed_then_health_metrics_are_read() {
+        let message = b"LFNW>OGNSDR,TCPIP*,qAC,GLIDERN2:>163125h v0.2.8.RPI-GPU CPU:0.7 RAM:770.2/972.2MB NTP:0.4ms/-6.1ppm 4.963V 0.492A +49.4C 2/3Acfts[1h] RF:+53-2.2ppm/+4.17dB/+2.1dB@10km[3245]/+8.8dB@10km[5/9]";
+
+        let beacon = parse_receiver_beacon(message, received(), DEFAULT_MAX_CLOCK_SKEW).unwrap();
+
+        assert_eq!(
+            beacon.report,
+            ReceiverReport::Status(ReceiverStatus {
+                version: Some("0.2.8".into()),
+                platform: Some("RPI-GPU".into()),
+                cpu_load: Some(0.7),
+                ram_free_mb: Some(770.2),
+                ram_total_mb: Some(972.2),
+                ntp_offset_ms: Some(0.4),
+                ntp_correction_ppm: Some(-6.1),
+                voltage_volts: Some(4.963),
+                current_amperes: Some(0.492),
+                cpu_temperature_celsius: Some(49.4),
+                visible_senders: Some(2),
+                senders: Some(3),
+                rf_noise_db: Some(4.17),
+            })
+        );
+    }
+
+    #[rstest::rstest]
+    #[case::aircraft(&b"FLRDDA5BA>OGFLR,qAS,LFNW:/163148h4559.85N/00348.19E'342/012/A=001345"[..])]
+    #[case::bad_time(&b"LFNW>OGNSDR,TCPIP*,qAC,GLIDERN2:/16312x4545.85NI00404.72E&"[..])]
+    #[case::bad_position(&b"LFNW>OGNSDR,TCPIP*,qAC,GLIDERN2:/163125h4545.85XI00404.72E&"[..])]
+    #[case::skewed(&b"LFNW>OGNSDR,TCPIP*,qAC,GLIDERN2:>183125h v0.2.8"[..])]
+    fn given_unusable_message_when_parsed_then_error_is_returned(#[case] message: &[u8]) {
+        assert!(parse_receiver_beacon(message, received(), DEFAULT_MAX_CLOCK_SKEW).is_err());
+    }
+}
@@ -0,0 +1,138 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::core::parser::failures::{
+    DEFAULT_FAILURE_SAMPLE_CAPACITY, ParseFailure, ParseFailureCounts, ParseFailureKind,
+};
+
+#[derive(Debug, Default)]
+struct Counters {
+    parsed: AtomicU64,
+    receiver_beacons: AtomicU64,
+    no_tracking: AtomicU64,
+    clock_skew: AtomicU64,
+    failures: [AtomicU64; ParseFailureKind::ALL.len()],
+}
+
+/// The latest lines that failed to parse, per kind of failure.
+#[derive(Debug)]
+struct FailureSamples {
+    capacity: usize,
+    samples: Mutex<[VecDeque<ParseFailure>; ParseFailureKind::ALL.len()]>,
+}
+
+/// Reads the counters of an [`AircraftParser`](crate::core::parser::AircraftParser) from
+/// other threads.
+#[derive(Debug, Clone)]
+pub struct ParserMonitor {
+    counters: Arc<Counters>,
+    samples: Arc<FailureSamples>,
+}
+impl Default for ParserMonitor {
+    fn default() -> Self {
+        Self::with_sample_capacity(DEFAULT_FAILURE_SAMPLE_CAPACITY)
+    }
+}
+impl ParserMonitor {
+    /// Keeps the latest `capacity` failed lines of each kind.
+    #[must_use]
+    pub fn with_sample_capacity(capacity: usize) -> Self {
+        Self {
+            counters: Arc::default(),
+            samples: Arc::new(FailureSamples {
+                capacity,
+                samples: Mutex::default(),
+            }),
+        }
+    }
+
+    #[must_use]
+    pub fn stats(&self) -> ParserStats {
+        ParserStats {
+            parsed: self.counters.parsed.load(Ordering::Relaxed),
+            receiver_beacons: self.counters.receiver_beacons.load(Ordering::Relaxed),
+            no_tracking: self.counters.no_tracking.load(Ordering::Relaxed),
+            clock_skew: self.counters.clock_skew.load(Ordering::Relaxed),
+            failures: ParseFailureCounts::from_counts(std::array::from_fn(|index| {
+                self.counters.failures[index].load(Ordering::Relaxed)
+            })),
+        }
+    }
+
+    /// The latest lines that failed with `kind`, oldest first.
+    #[must_use]
+    pub fn failure_samples(&self, kind: ParseFailureKind) -> Vec<ParseFailure> {
+        self.samples
+            .samples
+            .lock()
+            .expect("failure samples poisoned")[kind.index()]
+        .iter()
+        .cloned()
+        .collect()
+    }
+
+    pub(super) fn record_parsed(&self) {
+        self.counters.parsed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(super) fn record_receiver_beacon(&self) {
+        self.counters
+            .receiver_beacons
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(super) fn record_no_tracking(&self) {
+        self.counters.no_tracking.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(super) fn record_clock_skew(&self) {
+        self.counters.clock_skew.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(super) fn record_failure(&self, failure: &ParseFailure) {
+        let index = failure.kind.index();
+        self.counters.failures[index].fetch_add(1, Ordering::Relaxed);
+        if self.samples.capacity == 0 {
+            return;
+        }
+        let mut samples = self
+            .samples
+            .samples
+            .lock()
+            .expect("failure samples poisoned");
+        let samples = &mut samples[index];
+        if samples.len() == self.samples.capacity {
+            samples.pop_front();
+        }
+        samples.push_back(failure.clone());
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParserStats {
+    /// Aircraft beacons and other trackers' position reports turned into aircraft.
+    pub parsed: u64,
+    /// Receiver position and status beacons parsed.
+    pub receiver_beacons: u64,
+    /// Aircraft dropped because their sender asked for them not to be tracked.
+    pub no_tracking: u64,
+    /// Aircraft and receiver beacons rejected as stamped too far from their receive time.
+    pub clock_skew: u64,
+    /// Lines that did not parse, by kind of failure.
+    pub failures: ParseFailureCounts,
+}
+impl std::fmt::Display for ParserStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} aircraft and {} receiver beacons parsed, {} dropped for no tracking, {} rejected for clock skew, {} failed ({})",
+            self.parsed,
+            self.receiver_beacons,
+            self.no_tracking,
+            self.clock_skew,
+            self.failures.total(),
+            self.failures
+        )
+    }
+}
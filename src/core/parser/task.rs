@@ -1,45 +1,424 @@
 use ogn_aprs_parser::parse_ogn_aprs_aircraft_beacon;
 
+use crate::core::central_disk_logger::{AnyFormatLoggerHandle, JsonlLoggerHandle, LogSender};
+use crate::core::channel::{ChannelReceiver, ChannelSender};
 use crate::core::ingestor::AprsPacket;
-use crate::core::parser::Aircraft;
-use crate::core::parser::conversion::convert_ogn_aprs_beacon_to_aircraft;
-use crate::core::thread_manager::{SteppableTask, TaskState};
+use crate::core::parser::conversion::{
+    DEFAULT_MAX_CLOCK_SKEW, convert_ogn_aprs_beacon_to_aircraft,
+};
+use crate::core::parser::decoders::{AprsHeader, PositionDecoder};
+use crate::core::parser::errors::{BeaconConversionError, ReceiverBeaconError};
+use crate::core::parser::{
+    Aircraft, ParseFailure, ParseFailureKind, ParserMonitor, PbAircraft, ReceiverBeacon,
+    is_receiver_beacon, parse_receiver_beacon,
+};
+use crate::core::thread_manager::{SteppableTask, TaskState, Wakeup};
 
 pub struct AircraftParser {
-    receiver: crossbeam_channel::Receiver<AprsPacket>,
-    sender: crossbeam_channel::Sender<Aircraft>,
+    receiver: ChannelReceiver<AprsPacket>,
+    sender: ChannelSender<Aircraft>,
+    receiver_sender: Option<ChannelSender<ReceiverBeacon>>,
+    logger: Option<AnyFormatLoggerHandle<PbAircraft, Aircraft>>,
+    quarantine: Option<JsonlLoggerHandle<ParseFailure>>,
+    decoders: Vec<Box<dyn PositionDecoder>>,
+    max_clock_skew: chrono::TimeDelta,
+    monitor: ParserMonitor,
 }
 impl AircraftParser {
     #[must_use]
     pub fn new(
-        messages_receiver: crossbeam_channel::Receiver<AprsPacket>,
-        aircraft_sender: crossbeam_channel::Sender<Aircraft>,
+        messages_receiver: impl Into<ChannelReceiver<AprsPacket>>,
+        aircraft_sender: impl Into<ChannelSender<Aircraft>>,
     ) -> Self {
         AircraftParser {
-            receiver: messages_receiver,
-            sender: aircraft_sender,
+            receiver: messages_receiver.into(),
+            sender: aircraft_sender.into(),
+            receiver_sender: None,
+            logger: None,
+            quarantine: None,
+            decoders: Vec::new(),
+            max_clock_skew: DEFAULT_MAX_CLOCK_SKEW,
+            monitor: ParserMonitor::default(),
+        }
+    }
+
+    /// Sends the position and status beacons of ground stations to `receiver_sender`
+    /// instead of dropping them.
+    #[must_use]
+    pub fn with_receiver_sender(
+        mut self,
+        receiver_sender: impl Into<ChannelSender<ReceiverBeacon>>,
+    ) -> Self {
+        self.receiver_sender = Some(receiver_sender.into());
+        self
+    }
+
+    /// Rejects beacons whose time is further than `max_clock_skew` from when they were
+    /// received, instead of the default 5 minutes.
+    #[must_use]
+    pub fn with_max_clock_skew(mut self, max_clock_skew: chrono::TimeDelta) -> Self {
+        self.max_clock_skew = max_clock_skew;
+        self
+    }
+
+    /// Counts into `monitor`, e.g. one shared by several workers, instead of its own. Build
+    /// it with [`ParserMonitor::with_sample_capacity`] to keep other than 20 failed lines
+    /// of each kind.
+    #[must_use]
+    pub fn with_monitor(mut self, monitor: ParserMonitor) -> Self {
+        self.monitor = monitor;
+        self
+    }
+
+    /// A handle reading how many beacons were parsed or rejected, and samples of the
+    /// lines that failed, usable from other threads.
+    #[must_use]
+    pub fn monitor(&self) -> ParserMonitor {
+        self.monitor.clone()
+    }
+
+    /// Turns the reports `decoder` accepts into aircraft. Decoders are tried in the order
+    /// they were added, before the OGN aircraft beacon parser.
+    #[must_use]
+    pub fn with_decoder(mut self, decoder: Box<dyn PositionDecoder>) -> Self {
+        self.decoders.push(decoder);
+        self
+    }
+
+    /// Records every line that fails to parse to `quarantine`, as JSON lines.
+    #[must_use]
+    pub fn with_quarantine(mut self, quarantine: JsonlLoggerHandle<ParseFailure>) -> Self {
+        self.quarantine = Some(quarantine);
+        self
+    }
+
+    /// Records every parsed aircraft to `logger`.
+    #[must_use]
+    pub fn with_logger(mut self, logger: AnyFormatLoggerHandle<PbAircraft, Aircraft>) -> Self {
+        self.logger = Some(logger);
+        self
+    }
+}
+
+impl AircraftParser {
+    fn forward_aircraft(&self, aircraft: Aircraft) {
+        // Dropped before anything records them, honouring the sender's opt-out.
+        if aircraft.no_tracking {
+            self.monitor.record_no_tracking();
+            return;
+        }
+        self.monitor.record_parsed();
+        if let Some(logger) = &self.logger
+            && let Err(err) = logger.send(aircraft.clone())
+        {
+            log::warn!("Failed to record aircraft: {err}");
+        }
+        if let Err(err) = self.sender.send(aircraft) {
+            log::error!("Failed to forward aircraft: {err}");
+        }
+    }
+
+    /// Decodes `aprs_packet` with the first decoder accepting it, returning `false` when
+    /// none does.
+    fn decode_position(&self, aprs_packet: &AprsPacket) -> bool {
+        if self.decoders.is_empty() {
+            return false;
+        }
+        let message = String::from_utf8_lossy(&aprs_packet.message);
+        let Some(header) = AprsHeader::parse(&message) else {
+            return false;
+        };
+        let Some(decoder) = self
+            .decoders
+            .iter()
+            .find(|decoder| decoder.accepts(&header))
+        else {
+            return false;
+        };
+        match decoder.decode(&header, aprs_packet.timestamp.into(), self.max_clock_skew) {
+            Ok(track_point) => self.forward_aircraft(track_point.into()),
+            Err(err) => match ParseFailureKind::of_decode_error(&err) {
+                Some(kind) => self.reject(ParseFailure::new(kind, aprs_packet, err)),
+                None => {
+                    self.monitor.record_clock_skew();
+                    log::debug!("{err}");
+                }
+            },
+        }
+        true
+    }
+
+    fn reject(&self, failure: ParseFailure) {
+        log::debug!("{}: {}", failure.kind, failure.error);
+        self.monitor.record_failure(&failure);
+        if let Some(quarantine) = &self.quarantine
+            && let Err(err) = quarantine.send(failure)
+        {
+            log::warn!("Failed to quarantine line: {err}");
+        }
+    }
+
+    fn forward_receiver_beacon(&self, aprs_packet: &AprsPacket) {
+        let Some(receiver_sender) = &self.receiver_sender else {
+            return;
+        };
+        match parse_receiver_beacon(
+            &aprs_packet.message,
+            aprs_packet.timestamp.into(),
+            self.max_clock_skew,
+        ) {
+            Ok(receiver_beacon) => {
+                self.monitor.record_receiver_beacon();
+                if let Err(err) = receiver_sender.send(receiver_beacon) {
+                    log::error!("Failed to forward receiver beacon: {err}");
+                }
+            }
+            Err(err @ ReceiverBeaconError::Conversion(BeaconConversionError::ClockSkew { .. })) => {
+                self.monitor.record_clock_skew();
+                log::debug!("{err}");
+            }
+            Err(err) => self.reject(ParseFailure::new(
+                ParseFailureKind::ReceiverBeacon,
+                aprs_packet,
+                err,
+            )),
         }
     }
 }
 
 impl SteppableTask for AircraftParser {
     fn step(&mut self) -> TaskState {
-        let Ok(aprs_packet) = self.receiver.recv() else {
-            log::info!("AircraftParser upstream disconnected. Task complete");
-            return TaskState::Completed;
+        let aprs_packet = match self.receiver.try_recv() {
+            Ok(aprs_packet) => aprs_packet,
+            Err(crossbeam_channel::TryRecvError::Empty) => return TaskState::Running,
+            Err(crossbeam_channel::TryRecvError::Disconnected) => {
+                log::info!("AircraftParser upstream disconnected. Task complete");
+                return TaskState::Completed;
+            }
         };
 
-        match parse_ogn_aprs_aircraft_beacon(&aprs_packet.message) {
-            Ok(aircraft_beacon) => {
-                let aircraft =
-                    convert_ogn_aprs_beacon_to_aircraft(aircraft_beacon, aprs_packet.timestamp);
-                if let Err(err) = self.sender.send(aircraft) {
-                    log::error!("Failed to forward aircraft: {err}");
-                }
+        if aprs_packet.message.first() == Some(&b'#') {
+            self.reject(ParseFailure::new(
+                ParseFailureKind::ServerComment,
+                &aprs_packet,
+                "server comment",
+            ));
+            return TaskState::Running;
+        }
+        // Before the decoders, as the generic APRS decoder would also take the positions
+        // of receivers logged in to APRS-IS themselves.
+        if is_receiver_beacon(&aprs_packet.message) {
+            self.forward_receiver_beacon(&aprs_packet);
+            return TaskState::Running;
+        }
+        if self.decode_position(&aprs_packet) {
+            return TaskState::Running;
+        }
+        let aircraft_beacon = match parse_ogn_aprs_aircraft_beacon(&aprs_packet.message) {
+            Ok(aircraft_beacon) => aircraft_beacon,
+            Err(err) => {
+                self.reject(ParseFailure::new(
+                    ParseFailureKind::of_aircraft_error(&err),
+                    &aprs_packet,
+                    err,
+                ));
+                return TaskState::Running;
+            }
+        };
+        match convert_ogn_aprs_beacon_to_aircraft(
+            aircraft_beacon,
+            &aprs_packet.message,
+            aprs_packet.timestamp,
+            self.max_clock_skew,
+        ) {
+            Ok(aircraft) => self.forward_aircraft(aircraft),
+            Err(err @ BeaconConversionError::ClockSkew { .. }) => {
+                self.monitor.record_clock_skew();
+                log::debug!("{err}");
             }
-            Err(err) => log::debug!("{err:?}"),
         }
 
         TaskState::Running
     }
+
+    fn wakeup(&self) -> Option<Wakeup<'_>> {
+        Some(Wakeup::on(&self.receiver))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::parser::decoders::TrackerKind;
+    use crate::core::parser::{ParseFailureCounts, ParserStats};
+
+    fn packet_received_at(seconds: u64) -> AprsPacket {
+        AprsPacket {
+            timestamp: std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(seconds),
+            message:
+                "FLRDDA5BA>OGFLR,qAS,LFNW:/163148h4559.85N/00348.19E'342/012/A=001345 !W06! id0ADDA5BA"
+                    .into(),
+            source: "test".into(),
+        }
+    }
+
+    #[test]
+    fn given_beacon_received_long_after_its_time_when_parsed_then_it_is_dropped_and_counted() {
+        let (packet_sender, packet_receiver) = crossbeam_channel::unbounded();
+        let (aircraft_sender, aircraft_receiver) = crossbeam_channel::unbounded();
+        let mut parser = AircraftParser::new(packet_receiver, aircraft_sender);
+        // 2024-06-01 at 16:31:50, then at 17:31:48.
+        packet_sender
+            .send(packet_received_at(1_717_259_510))
+            .unwrap();
+        packet_sender
+            .send(packet_received_at(1_717_263_108))
+            .unwrap();
+        drop(packet_sender);
+
+        while matches!(parser.step(), TaskState::Running) {}
+
+        assert_eq!(aircraft_receiver.try_iter().count(), 1);
+        assert_eq!(
+            parser.monitor().stats(),
+            ParserStats {
+                parsed: 1,
+                receiver_beacons: 0,
+                no_tracking: 0,
+                clock_skew: 1,
+                failures: ParseFailureCounts::default(),
+            }
+        );
+    }
+
+    #[test]
+    fn given_unparsable_lines_when_parsed_then_they_are_counted_sampled_and_quarantined() {
+        let (packet_sender, packet_receiver) = crossbeam_channel::unbounded();
+        let (aircraft_sender, _aircraft_receiver) = crossbeam_channel::unbounded();
+        let (log_sender, log_receiver) = crossbeam_channel::unbounded();
+        let mut parser = AircraftParser::new(packet_receiver, aircraft_sender)
+            .with_monitor(ParserMonitor::with_sample_capacity(1))
+            .with_quarantine(JsonlLoggerHandle::new(0, log_sender));
+        let monitor = parser.monitor();
+        for message in [
+            "# aprsc 2.1.4-g408ed49\r\n",
+            "FLRDDA5BA>OGFLR,qAS,LFNW:/163148h45X9.85N/00348.19E'342/012/A=001345 !W06! id0ADDA5BA",
+            "FLRDDA5BB>OGFLR,qAS,LFNW:/163148h45X9.85N/00348.19E'342/012/A=001345 !W06! id0ADDA5BB",
+        ] {
+            packet_sender
+                .send(AprsPacket {
+                    message: message.into(),
+                    ..packet_received_at(1_717_259_510)
+                })
+                .unwrap();
+        }
+        drop(packet_sender);
+
+        while matches!(parser.step(), TaskState::Running) {}
+
+        let failures = monitor.stats().failures;
+        assert_eq!(failures.get(ParseFailureKind::ServerComment), 1);
+        assert_eq!(failures.get(ParseFailureKind::MalformedPosition), 2);
+        assert_eq!(failures.total(), 3);
+        let samples = monitor.failure_samples(ParseFailureKind::MalformedPosition);
+        assert_eq!(samples.len(), 1);
+        assert!(samples[0].line.starts_with("FLRDDA5BB>"));
+        let quarantined: Vec<ParseFailure> = log_receiver
+            .try_iter()
+            .map(|message| serde_json::from_slice(&message.payload).unwrap())
+            .collect();
+        assert_eq!(quarantined.len(), 3);
+        assert_eq!(quarantined[0].line, "# aprsc 2.1.4-g408ed49");
+        assert_eq!(quarantined[0].source, "test");
+    }
+
+    #[test]
+    fn given_fanet_decoder_when_fanet_report_parsed_then_it_is_forwarded_as_aircraft() {
+        let (packet_sender, packet_receiver) = crossbeam_channel::unbounded();
+        let (aircraft_sender, aircraft_receiver) = crossbeam_channel::unbounded();
+        let mut parser = AircraftParser::new(packet_receiver, aircraft_sender)
+            .with_decoder(TrackerKind::Fanet.decoder());
+        packet_sender
+            .send(AprsPacket {
+                message: "FNT1103CE>OGNFNT,qAS,FNB1103CE:/163148h5057.94N/00801.00Eg355/002/A=001042 !W10! id1E1103CE +03fpm".into(),
+                ..packet_received_at(1_717_259_510)
+            })
+            .unwrap();
+        drop(packet_sender);
+
+        while matches!(parser.step(), TaskState::Running) {}
+
+        let aircraft: Vec<_> = aircraft_receiver.try_iter().collect();
+        assert_eq!(aircraft.len(), 1);
+        assert_eq!(
+            aircraft[0].icao_address.map(|address| address.value()),
+            Some(0x0011_03CE)
+        );
+        assert_eq!(aircraft[0].receiver, "FNB1103CE");
+        assert_eq!(parser.monitor().stats().parsed, 1);
+    }
+
+    #[test]
+    fn given_aprs_decoder_when_timestamped_station_position_parsed_then_it_is_an_unaddressed_aircraft()
+     {
+        let (packet_sender, packet_receiver) = crossbeam_channel::unbounded();
+        let (aircraft_sender, aircraft_receiver) = crossbeam_channel::unbounded();
+        let (receiver_sender, receiver_receiver) = crossbeam_channel::unbounded();
+        let mut parser = AircraftParser::new(packet_receiver, aircraft_sender)
+            .with_receiver_sender(receiver_sender)
+            .with_decoder(TrackerKind::Aprs.decoder());
+        packet_sender
+            .send(AprsPacket {
+                message:
+                    "N0CALL-10>APDR16,TCPIP*,qAC,T2POLAND:/163125h5050.73N/00413.19EO/A=000100"
+                        .into(),
+                ..packet_received_at(1_717_259_510)
+            })
+            .unwrap();
+        drop(packet_sender);
+
+        while matches!(parser.step(), TaskState::Running) {}
+
+        let aircraft: Vec<_> = aircraft_receiver.try_iter().collect();
+        assert_eq!(aircraft.len(), 1);
+        assert_eq!(aircraft[0].icao_address, None);
+        assert_eq!(
+            (aircraft[0].ground_track, aircraft[0].ground_speed_knots),
+            (None, None)
+        );
+        assert_eq!(receiver_receiver.try_iter().count(), 0);
+    }
+
+    #[test]
+    fn given_no_tracking_beacon_when_parsed_then_it_is_neither_recorded_nor_forwarded() {
+        let (packet_sender, packet_receiver) = crossbeam_channel::unbounded();
+        let (aircraft_sender, aircraft_receiver) = crossbeam_channel::unbounded();
+        let (log_sender, log_receiver) = crossbeam_channel::unbounded();
+        let mut parser = AircraftParser::new(packet_receiver, aircraft_sender).with_logger(
+            AnyFormatLoggerHandle::Jsonl(JsonlLoggerHandle::new(0, log_sender)),
+        );
+        // The 0x40 bit of the `id` prefix asks for no tracking.
+        for id in ["id0ADDA5BA", "id4ADDA5BA"] {
+            packet_sender
+                .send(AprsPacket {
+                    message: format!("FLRDDA5BA>OGFLR,qAS,LFNW:/163148h4559.85N/00348.19E'342/012/A=001345 !W06! {id}").into(),
+                    ..packet_received_at(1_717_259_510)
+                })
+                .unwrap();
+        }
+        drop(packet_sender);
+
+        while matches!(parser.step(), TaskState::Running) {}
+
+        let forwarded: Vec<Aircraft> = aircraft_receiver.try_iter().collect();
+        let recorded: Vec<Aircraft> = log_receiver
+            .try_iter()
+            .map(|message| serde_json::from_slice(&message.payload).unwrap())
+            .collect();
+        assert_eq!(forwarded.len(), 1);
+        assert!(!forwarded[0].no_tracking);
+        assert_eq!(recorded, forwarded);
+        let stats = parser.monitor().stats();
+        assert_eq!((stats.parsed, stats.no_tracking), (1, 1));
+    }
 }
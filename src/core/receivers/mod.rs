@@ -0,0 +1,5 @@
+mod registry;
+mod task;
+
+pub use registry::{Receiver, ReceiverRegistry};
+pub use task::{ReceiverStore, ReceiverViewer};
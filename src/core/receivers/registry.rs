@@ -0,0 +1,170 @@
+use crate::core::parser::{ReceiverBeacon, ReceiverPosition, ReceiverReport, ReceiverStatus};
+
+/// What is known about one ground station from its beacons.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Receiver {
+    pub name: String,
+    /// The APRS-IS server the station last reported through.
+    pub server: String,
+    /// Time of the latest beacon of either kind.
+    pub last_heard: chrono::DateTime<chrono::Utc>,
+    pub position: Option<ReceiverPosition>,
+    pub status: Option<ReceiverStatus>,
+    /// Time of the latest status beacon, telling how fresh `status` is.
+    pub status_heard: Option<chrono::DateTime<chrono::Utc>>,
+    pub beacons: u64,
+}
+
+/// Latest location and health of every ground station heard, by name.
+#[derive(Debug, Default)]
+pub struct ReceiverRegistry {
+    receivers: std::collections::BTreeMap<String, Receiver>,
+}
+impl ReceiverRegistry {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records `beacon`. A report older than the one already known for its station is
+    /// counted but does not replace it.
+    pub fn update(&mut self, beacon: ReceiverBeacon) {
+        let ReceiverBeacon {
+            name,
+            server,
+            datetime,
+            report,
+        } = beacon;
+        let receiver = self
+            .receivers
+            .entry(name)
+            .or_insert_with_key(|name| Receiver {
+                name: name.clone(),
+                server: server.clone(),
+                last_heard: datetime,
+                position: None,
+                status: None,
+                status_heard: None,
+                beacons: 0,
+            });
+        receiver.beacons += 1;
+        if datetime < receiver.last_heard {
+            return;
+        }
+        receiver.last_heard = datetime;
+        receiver.server = server;
+        match report {
+            ReceiverReport::Position(position) => receiver.position = Some(position),
+            ReceiverReport::Status(status) => {
+                receiver.status = Some(status);
+                receiver.status_heard = Some(datetime);
+            }
+        }
+    }
+
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&Receiver> {
+        self.receivers.get(name)
+    }
+
+    /// Every station, ordered by name.
+    pub fn receivers(&self) -> impl Iterator<Item = &Receiver> {
+        self.receivers.values()
+    }
+
+    /// Stations last heard before `datetime`, e.g. to spot those that went offline.
+    pub fn silent_since(
+        &self,
+        datetime: chrono::DateTime<chrono::Utc>,
+    ) -> impl Iterator<Item = &Receiver> {
+        self.receivers()
+            .filter(move |receiver| receiver.last_heard < datetime)
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.receivers.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.receivers.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(seconds: i64) -> chrono::DateTime<chrono::Utc> {
+        chrono::DateTime::from_timestamp(1_700_000_000 + seconds, 0).unwrap()
+    }
+
+    fn position_beacon(name: &str, seconds: i64, latitude: f64) -> ReceiverBeacon {
+        ReceiverBeacon {
+            name: name.into(),
+            server: "GLIDERN2".into(),
+            datetime: at(seconds),
+            report: ReceiverReport::Position(ReceiverPosition {
+                latitude,
+                longitude: 4.0,
+                altitude_metres: None,
+            }),
+        }
+    }
+
+    fn status_beacon(name: &str, seconds: i64, cpu_load: f64) -> ReceiverBeacon {
+        ReceiverBeacon {
+            name: name.into(),
+            server: "GLIDERN2".into(),
+            datetime: at(seconds),
+            report: ReceiverReport::Status(ReceiverStatus {
+                cpu_load: Some(cpu_load),
+                ..ReceiverStatus::default()
+            }),
+        }
+    }
+
+    #[test]
+    fn given_position_and_status_beacons_when_registered_then_station_keeps_both() {
+        let mut registry = ReceiverRegistry::new();
+
+        registry.update(position_beacon("LFNW", 0, 45.0));
+        registry.update(status_beacon("LFNW", 5, 0.7));
+
+        let receiver = registry.get("LFNW").unwrap();
+        assert_eq!(receiver.last_heard, at(5));
+        assert_eq!(receiver.position.unwrap().latitude, 45.0);
+        assert_eq!(receiver.status.as_ref().unwrap().cpu_load, Some(0.7));
+        assert_eq!(receiver.status_heard, Some(at(5)));
+        assert_eq!(receiver.beacons, 2);
+    }
+
+    #[test]
+    fn given_older_beacon_when_registered_then_newer_report_is_kept() {
+        let mut registry = ReceiverRegistry::new();
+
+        registry.update(status_beacon("LFNW", 10, 0.7));
+        registry.update(status_beacon("LFNW", 5, 0.1));
+
+        let receiver = registry.get("LFNW").unwrap();
+        assert_eq!(receiver.last_heard, at(10));
+        assert_eq!(receiver.status.as_ref().unwrap().cpu_load, Some(0.7));
+        assert_eq!(receiver.beacons, 2);
+    }
+
+    #[test]
+    fn given_several_stations_when_queried_then_silent_ones_are_listed() {
+        let mut registry = ReceiverRegistry::new();
+        registry.update(position_beacon("LFNW", 0, 45.0));
+        registry.update(position_beacon("EDER", 600, 50.0));
+
+        let silent: Vec<_> = registry
+            .silent_since(at(300))
+            .map(|receiver| receiver.name.as_str())
+            .collect();
+
+        assert_eq!(registry.len(), 2);
+        assert_eq!(silent, ["LFNW"]);
+    }
+}
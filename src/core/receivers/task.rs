@@ -0,0 +1,59 @@
+use crate::core::channel::ChannelReceiver;
+use crate::core::parser::ReceiverBeacon;
+use crate::core::receivers::ReceiverRegistry;
+use crate::core::thread_manager::{SteppableTask, TaskState, Wakeup};
+
+/// Applies the receiver beacons coming out of the parser to a [`ReceiverRegistry`].
+pub struct ReceiverStore {
+    inner: std::sync::Arc<std::sync::RwLock<ReceiverRegistry>>,
+    beacon_receiver: ChannelReceiver<ReceiverBeacon>,
+}
+impl ReceiverStore {
+    #[must_use]
+    pub fn new(beacon_receiver: impl Into<ChannelReceiver<ReceiverBeacon>>) -> Self {
+        Self {
+            inner: std::sync::Arc::default(),
+            beacon_receiver: beacon_receiver.into(),
+        }
+    }
+
+    #[must_use]
+    pub fn get_receiver_viewer(&self) -> ReceiverViewer {
+        ReceiverViewer {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl SteppableTask for ReceiverStore {
+    fn step(&mut self) -> TaskState {
+        let beacon = match self.beacon_receiver.try_recv() {
+            Ok(beacon) => beacon,
+            Err(crossbeam_channel::TryRecvError::Empty) => return TaskState::Running,
+            Err(crossbeam_channel::TryRecvError::Disconnected) => {
+                log::info!("ReceiverStore upstream disconnected. Task complete");
+                return TaskState::Completed;
+            }
+        };
+        if let Ok(mut registry) = self.inner.write() {
+            registry.update(beacon);
+        }
+        TaskState::Running
+    }
+
+    fn wakeup(&self) -> Option<Wakeup<'_>> {
+        Some(Wakeup::on(&self.beacon_receiver))
+    }
+}
+
+/// Reads the registry of a [`ReceiverStore`]; a default viewer reads an empty one.
+#[derive(Clone, Default)]
+pub struct ReceiverViewer {
+    inner: std::sync::Arc<std::sync::RwLock<ReceiverRegistry>>,
+}
+impl ReceiverViewer {
+    #[allow(clippy::missing_panics_doc)]
+    pub fn read(&self) -> std::sync::RwLockReadGuard<'_, ReceiverRegistry> {
+        self.inner.read().expect("Read lock poisoned")
+    }
+}
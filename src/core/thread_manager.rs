@@ -1,5 +1,15 @@
 use log;
 
+mod health;
+mod timing;
+mod wakeup;
+
+pub use health::{ScheduleReport, TaskReport, TaskStatus, TaskStatusMonitor};
+use health::{TaskRegistry, TaskShared};
+use timing::Schedule;
+pub use timing::{CatchUpPolicy, Histogram, HistogramBucket, TaskPeriod};
+pub use wakeup::{WakeSource, Wakeup};
+
 pub type ThreadID = i32;
 pub type TaskID = i32;
 
@@ -15,27 +25,141 @@ pub enum TaskState {
 
 pub trait SteppableTask: Send + 'static {
     fn step(&mut self) -> TaskState;
+
+    /// What a continuously run task waits on before its next step, so that an idle task
+    /// sleeps instead of spinning. `None`, the default, steps again straight away, which
+    /// suits tasks that block inside `step` themselves. Periodic tasks step on their ticks
+    /// and ignore this.
+    fn wakeup(&self) -> Option<Wakeup<'_>> {
+        None
+    }
+
+    /// Name the task is listed under in [`TaskReport`]s; its type name by default.
+    fn name(&self) -> String {
+        let type_name = std::any::type_name::<Self>();
+        type_name
+            .rsplit("::")
+            .next()
+            .unwrap_or(type_name)
+            .to_string()
+    }
 }
 
 impl<T: SteppableTask + ?Sized> SteppableTask for Box<T> {
     fn step(&mut self) -> TaskState {
         (**self).step()
     }
+
+    fn wakeup(&self) -> Option<Wakeup<'_>> {
+        (**self).wakeup()
+    }
+
+    fn name(&self) -> String {
+        (**self).name()
+    }
+}
+/// Which exits of a supervised task are followed by a fresh instance, as for the
+/// permanent, transient and temporary children of an OTP supervisor.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RestartPolicy {
+    #[default]
+    Never,
+    /// Restarts after the task errors or panics.
+    OnError,
+    /// Restarts after any exit other than being stopped, including completing.
+    Always,
+}
+
+/// How a supervised task is restarted, and what happens once it is restarted too often.
+///
+/// More than `max_restarts` restarts within `within_seconds` makes the supervisor give
+/// up on the task. With `escalate`, giving up, or an abnormal exit under
+/// [`RestartPolicy::Never`], is reported through [`ThreadManager::escalations`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct Supervisor {
+    pub policy: RestartPolicy,
+    pub max_restarts: u32,
+    pub within_seconds: u64,
+    pub escalate: bool,
+}
+impl Default for Supervisor {
+    fn default() -> Self {
+        Self {
+            policy: RestartPolicy::Never,
+            max_restarts: 3,
+            within_seconds: 60,
+            escalate: false,
+        }
+    }
+}
+
+/// Builds a fresh instance of a supervised task after the previous one exited.
+pub type TaskFactory<T> =
+    Box<dyn FnMut() -> Result<T, Box<dyn std::error::Error + Send + Sync>> + Send>;
+
+/// A supervised task the supervisor gave up on, asking for the whole pipeline to stop.
+#[derive(Debug, Clone)]
+pub struct Escalation {
+    pub task_id: TaskID,
+    pub task_name: String,
+    pub reason: String,
+}
+impl std::fmt::Display for Escalation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Task {} ({}) escalated: {}",
+            self.task_id, self.task_name, self.reason
+        )
+    }
 }
+
+/// How often a bounded wait checks whether a task has finished.
+const FINISH_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(2);
+
 pub struct ThreadManager {
     current_task_id: ThreadID,
     tasks: std::collections::HashMap<ThreadID, ManagedTask>,
+    registry: TaskRegistry,
+    escalation_sender: crossbeam_channel::Sender<Escalation>,
+    escalation_receiver: crossbeam_channel::Receiver<Escalation>,
 }
 
 impl ThreadManager {
     #[must_use]
     pub fn new() -> Self {
+        let (escalation_sender, escalation_receiver) = crossbeam_channel::unbounded();
         ThreadManager {
             current_task_id: 0,
             tasks: std::collections::HashMap::new(),
+            registry: TaskRegistry::default(),
+            escalation_sender,
+            escalation_receiver,
+        }
+    }
+
+    /// Receives an [`Escalation`] whenever a supervisor with `escalate` gives up on its task.
+    #[must_use]
+    pub fn escalations(&self) -> crossbeam_channel::Receiver<Escalation> {
+        self.escalation_receiver.clone()
+    }
+
+    /// A handle listing the status of every task, usable from other threads.
+    #[must_use]
+    pub fn status_monitor(&self) -> TaskStatusMonitor {
+        TaskStatusMonitor {
+            registry: self.registry.clone(),
         }
     }
 
+    /// Reports of every task added so far, without waiting on any of them.
+    #[must_use]
+    pub fn task_reports(&self) -> Vec<TaskReport> {
+        self.status_monitor().reports()
+    }
+
     #[must_use]
     pub fn current_task_id(&self) -> TaskID {
         self.current_task_id
@@ -48,7 +172,8 @@ impl ThreadManager {
     /// - `&mut self` (`undefined`) - Describe this parameter.
     /// - `mut task` (`T`) - Task to be added - this type must implement the `Runnable` trait.
     ///   This repeatedly tries to run the task at the specified `period` interval
-    /// - `period` (`std`) - Period between tasks running.
+    /// - `period` (`impl Into<TaskPeriod>`) - Period between tasks running, and what to do
+    ///   about ticks missed while a step overran. A zero period runs the task continuously.
     ///
     /// # Returns
     ///
@@ -57,30 +182,101 @@ impl ThreadManager {
     /// # Panics
     ///
     /// Will panic if thread does not spawn successfully.
-    pub fn add_task<T>(&mut self, task: T, period: std::time::Duration) -> TaskID
+    pub fn add_task<T>(&mut self, task: T, period: impl Into<TaskPeriod>) -> TaskID
+    where
+        T: SteppableTask,
+    {
+        let period = period.into();
+        self.spawn_task(task.name(), period, move |stop_receiver, worker_shared| {
+            run_task(task, period, &stop_receiver, &worker_shared);
+        })
+    }
+
+    /// Adds a task whose exits are handled by `supervisor`, which replaces the task with an
+    /// instance from `factory` when its restart policy asks for it.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if thread does not spawn successfully.
+    pub fn add_supervised_task<T>(
+        &mut self,
+        task: T,
+        factory: TaskFactory<T>,
+        period: impl Into<TaskPeriod>,
+        supervisor: Supervisor,
+    ) -> TaskID
+    where
+        T: SteppableTask,
+    {
+        let period = period.into();
+        let task_id = self.current_task_id;
+        let escalation_sender = self.escalation_sender.clone();
+        let task_name = task.name();
+        self.spawn_task(
+            task_name.clone(),
+            period,
+            move |stop_receiver, worker_shared| {
+                SupervisedRun {
+                    task_id,
+                    task_name,
+                    supervisor,
+                    factory,
+                    period,
+                    escalation_sender,
+                }
+                .run(task, &stop_receiver, &worker_shared);
+            },
+        )
+    }
+
+    /// Adds a task the pipeline cannot run without. It is not restarted, but an error or
+    /// panic ending it is reported through [`Self::escalations`].
+    ///
+    /// # Panics
+    ///
+    /// Will panic if thread does not spawn successfully.
+    pub fn add_escalating_task<T>(&mut self, task: T, period: impl Into<TaskPeriod>) -> TaskID
     where
         T: SteppableTask,
     {
+        let factory: TaskFactory<T> = Box::new(|| Err("task cannot be restarted".into()));
+        self.add_supervised_task(
+            task,
+            factory,
+            period,
+            Supervisor {
+                policy: RestartPolicy::Never,
+                escalate: true,
+                ..Supervisor::default()
+            },
+        )
+    }
+
+    fn spawn_task(
+        &mut self,
+        name: String,
+        period: TaskPeriod,
+        run: impl FnOnce(crossbeam_channel::Receiver<()>, std::sync::Arc<TaskShared>) + Send + 'static,
+    ) -> TaskID {
         let id = self.current_task_id;
 
-        let task_status = std::sync::Arc::new(std::sync::RwLock::new(ThreadStatus::Active));
-        let worker_status = task_status.clone();
+        let task_shared = std::sync::Arc::new(TaskShared::new(name.clone(), period));
+        let worker_shared = task_shared.clone();
         let (stop_sender, stop_receiver) = crossbeam_channel::bounded::<()>(1);
-
-        let thread_task: Box<dyn FnOnce() + Send> = if period.is_zero() {
-            Box::new(move || {
-                run_task_continuously(task, &stop_receiver, worker_status);
-            })
-        } else {
-            Box::new(move || {
-                run_task_with_period(task, period, &stop_receiver, worker_status);
-            })
-        };
+        self.registry
+            .write()
+            .unwrap()
+            .insert(id, task_shared.clone());
 
         let handle = std::thread::Builder::new()
-            .name(std::any::type_name::<T>().to_string())
+            .name(name)
             .spawn(move || {
-                thread_task();
+                let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    run(stop_receiver, worker_shared.clone());
+                }));
+                let panic = result.err();
+                worker_shared.finished(panic.as_deref().map(panic_message));
+                resume_panic(panic);
             })
             .expect("Failed to spawn thread");
         self.tasks.insert(
@@ -89,7 +285,7 @@ impl ThreadManager {
                 task_id: id,
                 handle,
                 stop_sender,
-                status: task_status,
+                shared: task_shared,
             },
         );
         self.current_task_id += 1;
@@ -117,6 +313,31 @@ impl ThreadManager {
         }
     }
 
+    /// Whether the task's thread has exited. Tasks already waited on count as finished.
+    #[must_use]
+    pub fn is_task_finished(&self, task_id: TaskID) -> bool {
+        self.tasks
+            .get(&task_id)
+            .is_none_or(|task| task.handle.is_finished())
+    }
+
+    /// Waits on the task like [`Self::wait_on_task_finish`], but only until `deadline`.
+    /// Returns whether the task finished; one still running is left in place.
+    pub fn wait_on_task_finish_until(
+        &mut self,
+        task_id: TaskID,
+        deadline: std::time::Instant,
+    ) -> bool {
+        while !self.is_task_finished(task_id) {
+            if std::time::Instant::now() >= deadline {
+                return false;
+            }
+            std::thread::sleep(FINISH_POLL_INTERVAL);
+        }
+        self.wait_on_task_finish(task_id);
+        true
+    }
+
     pub fn wait_on_all_tasks(&mut self) {
         if self.tasks.is_empty() {
             return;
@@ -198,82 +419,207 @@ struct ManagedTask {
     task_id: TaskID,
     handle: std::thread::JoinHandle<()>,
     stop_sender: crossbeam_channel::Sender<()>,
-    status: std::sync::Arc<std::sync::RwLock<ThreadStatus>>,
+    shared: std::sync::Arc<TaskShared>,
+}
+
+/// Runs one task instance until it stops, completes or errors.
+fn run_task<T: SteppableTask>(
+    task: T,
+    period: TaskPeriod,
+    stop_receiver: &crossbeam_channel::Receiver<()>,
+    task_shared: &std::sync::Arc<TaskShared>,
+) {
+    if period.interval.is_zero() {
+        run_task_continuously(task, stop_receiver, task_shared.clone());
+    } else {
+        run_task_with_period(task, period, stop_receiver, task_shared.clone());
+    }
+}
+
+struct SupervisedRun<T> {
+    task_id: TaskID,
+    task_name: String,
+    supervisor: Supervisor,
+    factory: TaskFactory<T>,
+    period: TaskPeriod,
+    escalation_sender: crossbeam_channel::Sender<Escalation>,
+}
+impl<T: SteppableTask> SupervisedRun<T> {
+    fn run(
+        mut self,
+        task: T,
+        stop_receiver: &crossbeam_channel::Receiver<()>,
+        task_shared: &std::sync::Arc<TaskShared>,
+    ) {
+        let window = std::time::Duration::from_secs(self.supervisor.within_seconds);
+        let mut restarts = std::collections::VecDeque::new();
+        let mut next_task = Ok(task);
+        loop {
+            let exit = match next_task {
+                Ok(task) => std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    run_task(task, self.period, stop_receiver, task_shared);
+                }))
+                .err(),
+                Err(error) => {
+                    task_shared.set_status(ThreadStatus::Errored(error));
+                    None
+                }
+            };
+            let reason = match (&exit, &*task_shared.status.read().unwrap()) {
+                (Some(panic), _) => Some(format!("panicked: {}", panic_message(panic.as_ref()))),
+                (None, ThreadStatus::Errored(error)) => Some(format!("errored: {error}")),
+                _ => None,
+            };
+            if exit.is_some()
+                && let Some(reason) = &reason
+            {
+                task_shared.set_last_error(reason.clone());
+            }
+            let restart = !matches!(
+                (&*task_shared.status.read().unwrap(), self.supervisor.policy),
+                (ThreadStatus::Interrupted, _)
+                    | (_, RestartPolicy::Never)
+                    | (ThreadStatus::Completed, RestartPolicy::OnError)
+            );
+            if !restart {
+                if let Some(reason) = reason {
+                    self.escalate(reason);
+                }
+                return resume_panic(exit);
+            }
+
+            let now = std::time::Instant::now();
+            while restarts
+                .front()
+                .is_some_and(|restart: &std::time::Instant| now.duration_since(*restart) > window)
+            {
+                restarts.pop_front();
+            }
+            if restarts.len() >= self.supervisor.max_restarts as usize {
+                self.escalate(format!(
+                    "restarted {} times within {window:?}, giving up after it {}",
+                    restarts.len(),
+                    reason.as_deref().unwrap_or("completed"),
+                ));
+                return resume_panic(exit);
+            }
+            restarts.push_back(now);
+            log::warn!(
+                "Task {} ({}) {}, restarting ({} of {} within {window:?})",
+                self.task_id,
+                self.task_name,
+                reason.as_deref().unwrap_or("completed"),
+                restarts.len(),
+                self.supervisor.max_restarts,
+            );
+            task_shared.restarted();
+            next_task = (self.factory)();
+        }
+    }
+
+    fn escalate(&self, reason: String) {
+        if !self.supervisor.escalate {
+            return;
+        }
+        let escalation = Escalation {
+            task_id: self.task_id,
+            task_name: self.task_name.clone(),
+            reason,
+        };
+        log::error!("{escalation}");
+        let _ = self.escalation_sender.send(escalation);
+    }
+}
+
+fn panic_message(panic: &(dyn std::any::Any + Send)) -> &str {
+    panic
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic")
+}
+
+/// Lets a panic the supervisor gave up on reach the thread's join handle.
+fn resume_panic(panic: Option<Box<dyn std::any::Any + Send>>) {
+    if let Some(panic) = panic {
+        std::panic::resume_unwind(panic);
+    }
 }
 
 fn run_task_continuously<T: SteppableTask>(
     mut task: T,
     stop_receiver: &crossbeam_channel::Receiver<()>,
-    task_status: std::sync::Arc<std::sync::RwLock<ThreadStatus>>,
+    task_shared: std::sync::Arc<TaskShared>,
 ) {
     loop {
         // Check if we are interrupted
         match stop_receiver.try_recv() {
             Ok(()) | Err(crossbeam_channel::TryRecvError::Disconnected) => {
-                *task_status.write().unwrap() = ThreadStatus::Interrupted;
+                task_shared.set_status(ThreadStatus::Interrupted);
                 break;
             }
             Err(crossbeam_channel::TryRecvError::Empty) => {}
         }
         // Check if we should still loop on the task
-        match task.step() {
+        match task_shared.step(&mut task) {
             TaskState::Running => {} // do nothing, task continuing
             TaskState::Completed => {
-                *task_status.write().unwrap() = ThreadStatus::Completed;
+                task_shared.set_status(ThreadStatus::Completed);
                 break;
             }
             TaskState::Errored(error) => {
-                *task_status.write().unwrap() = ThreadStatus::Errored(error);
+                task_shared.set_status(ThreadStatus::Errored(error));
                 break;
             }
         }
 
-        std::thread::yield_now();
+        // Sleep until there is work, or until asked to stop which the next loop picks up
+        match task.wakeup() {
+            Some(wakeup) => {
+                wakeup.wait(stop_receiver);
+            }
+            None => std::thread::yield_now(),
+        }
     }
 }
 
 fn run_task_with_period<T: SteppableTask>(
     mut task: T,
-    period: std::time::Duration,
+    period: TaskPeriod,
     stop_receiver: &crossbeam_channel::Receiver<()>,
-    task_status: std::sync::Arc<std::sync::RwLock<ThreadStatus>>,
+    task_shared: std::sync::Arc<TaskShared>,
 ) {
-    let mut next_iteration_time = std::time::Instant::now();
+    let mut schedule = Schedule::new(period, std::time::Instant::now());
     loop {
-        // Check if we are interrupted
-        match stop_receiver.try_recv() {
-            Ok(()) | Err(crossbeam_channel::TryRecvError::Disconnected) => {
-                *task_status.write().unwrap() = ThreadStatus::Interrupted;
+        // Sleep until the next tick, waking early if we are interrupted
+        let wait = schedule
+            .next_tick()
+            .saturating_duration_since(std::time::Instant::now());
+        match stop_receiver.recv_timeout(wait) {
+            Ok(()) | Err(crossbeam_channel::RecvTimeoutError::Disconnected) => {
+                task_shared.set_status(ThreadStatus::Interrupted);
                 break;
             }
-            Err(crossbeam_channel::TryRecvError::Empty) => {}
+            Err(crossbeam_channel::RecvTimeoutError::Timeout) => {}
         }
-
-        // Find what is the time for next iteration
-        next_iteration_time += period;
+        task_shared.record_jitter(
+            std::time::Instant::now().saturating_duration_since(schedule.next_tick()),
+        );
 
         // Run task & update the task status
-        match task.step() {
+        match task_shared.step(&mut task) {
             TaskState::Running => {} // do nothing here
             TaskState::Completed => {
-                *task_status.write().unwrap() = ThreadStatus::Completed;
+                task_shared.set_status(ThreadStatus::Completed);
                 break;
             }
             TaskState::Errored(error) => {
-                *task_status.write().unwrap() = ThreadStatus::Errored(error);
+                task_shared.set_status(ThreadStatus::Errored(error));
                 break;
             }
         }
 
-        // Check if sleep is needed if task completed within the window
-        let now = std::time::Instant::now();
-
-        if next_iteration_time < now {
-            let sleep_duration = next_iteration_time - now;
-            std::thread::sleep(sleep_duration);
-        } else {
-            next_iteration_time = now;
-        }
+        task_shared.record_tick(schedule.complete(std::time::Instant::now()));
     }
 }
 
@@ -281,12 +627,12 @@ fn log_task_finished_status(task: ManagedTask) {
     let ManagedTask {
         task_id,
         handle,
-        status,
+        shared,
         ..
     } = task;
     match handle.join() {
         Ok(_) => {
-            let final_status = status.read().unwrap();
+            let final_status = shared.status.read().unwrap();
             match &*final_status {
                 ThreadStatus::Active => {
                     log::warn!("Task {task_id} exited abnormally without updating its status.")
@@ -312,7 +658,10 @@ fn log_task_finished_status(task: ManagedTask) {
 mod tests {
     use crate::core::thread_manager::TaskState;
 
-    use super::{SteppableTask, ThreadManager};
+    use super::{
+        CatchUpPolicy, RestartPolicy, SteppableTask, Supervisor, TaskFactory, TaskPeriod,
+        TaskStatus, ThreadManager, Wakeup,
+    };
 
     // A simple runnable task for counting and self-stopping
     #[derive(Debug)]
@@ -370,6 +719,332 @@ mod tests {
         }
     }
 
+    /// Runs once, exiting as told by `exit`.
+    struct ExitingTask {
+        exit: fn() -> TaskState,
+    }
+
+    impl SteppableTask for ExitingTask {
+        fn step(&mut self) -> TaskState {
+            (self.exit)()
+        }
+    }
+
+    /// Blocks in its first step until `release` disconnects.
+    struct BlockingTask {
+        release: crossbeam_channel::Receiver<()>,
+    }
+
+    impl SteppableTask for BlockingTask {
+        fn step(&mut self) -> TaskState {
+            let _ = self.release.recv();
+            TaskState::Completed
+        }
+    }
+
+    /// Takes 25 ms per step, completing after `steps_left` steps.
+    struct SlowTask {
+        steps_left: u32,
+    }
+
+    impl SteppableTask for SlowTask {
+        fn step(&mut self) -> TaskState {
+            std::thread::sleep(std::time::Duration::from_millis(25));
+            self.steps_left -= 1;
+            if self.steps_left == 0 {
+                TaskState::Completed
+            } else {
+                TaskState::Running
+            }
+        }
+    }
+
+    /// Forwards every message it receives, sleeping on its channel in between.
+    struct ForwardingTask {
+        receiver: crossbeam_channel::Receiver<u32>,
+        sender: std::sync::mpsc::Sender<u32>,
+    }
+
+    impl SteppableTask for ForwardingTask {
+        fn step(&mut self) -> TaskState {
+            match self.receiver.try_recv() {
+                Ok(message) => {
+                    self.sender.send(message).unwrap();
+                    TaskState::Running
+                }
+                Err(crossbeam_channel::TryRecvError::Empty) => TaskState::Running,
+                Err(crossbeam_channel::TryRecvError::Disconnected) => TaskState::Completed,
+            }
+        }
+
+        fn wakeup(&self) -> Option<Wakeup<'_>> {
+            Some(Wakeup::on(&self.receiver))
+        }
+    }
+
+    fn counting_factory(
+        exit: fn() -> TaskState,
+        builds: &std::sync::Arc<std::sync::atomic::AtomicU32>,
+    ) -> TaskFactory<ExitingTask> {
+        let builds = builds.clone();
+        Box::new(move || {
+            builds.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            Ok(ExitingTask { exit })
+        })
+    }
+
+    fn errored() -> TaskState {
+        TaskState::Errored("boom".into())
+    }
+
+    fn panicked() -> TaskState {
+        panic!("boom")
+    }
+
+    fn completed() -> TaskState {
+        TaskState::Completed
+    }
+
+    #[rstest::rstest]
+    #[case::error_on_error(RestartPolicy::OnError, errored as fn() -> TaskState, 2)]
+    #[case::panic_on_error(RestartPolicy::OnError, panicked, 2)]
+    #[case::completion_on_error(RestartPolicy::OnError, completed, 0)]
+    #[case::completion_always(RestartPolicy::Always, completed, 2)]
+    #[case::error_never(RestartPolicy::Never, errored, 0)]
+    fn given_restart_policy_when_task_exits_then_factory_rebuilds_it_up_to_max_restarts(
+        #[case] policy: RestartPolicy,
+        #[case] exit: fn() -> TaskState,
+        #[case] expected_builds: u32,
+    ) {
+        let mut manager = ThreadManager::new();
+        let builds = std::sync::Arc::new(std::sync::atomic::AtomicU32::new(0));
+        let supervisor = Supervisor {
+            policy,
+            max_restarts: 2,
+            ..Supervisor::default()
+        };
+
+        let task_id = manager.add_supervised_task(
+            ExitingTask { exit },
+            counting_factory(exit, &builds),
+            std::time::Duration::ZERO,
+            supervisor,
+        );
+        manager.wait_on_task_finish(task_id);
+
+        assert_eq!(
+            builds.load(std::sync::atomic::Ordering::Relaxed),
+            expected_builds
+        );
+    }
+
+    #[rstest::rstest]
+    #[case::gave_up(RestartPolicy::OnError, errored as fn() -> TaskState, true)]
+    #[case::never_restarted(RestartPolicy::Never, panicked, true)]
+    #[case::completed(RestartPolicy::Never, completed, false)]
+    fn given_escalating_supervisor_when_task_exits_then_escalation_is_reported_only_if_abnormal(
+        #[case] policy: RestartPolicy,
+        #[case] exit: fn() -> TaskState,
+        #[case] expect_escalation: bool,
+    ) {
+        let mut manager = ThreadManager::new();
+        let escalations = manager.escalations();
+        let supervisor = Supervisor {
+            policy,
+            max_restarts: 1,
+            escalate: true,
+            ..Supervisor::default()
+        };
+
+        let task_id = manager.add_supervised_task(
+            ExitingTask { exit },
+            Box::new(move || Ok(ExitingTask { exit })),
+            std::time::Duration::ZERO,
+            supervisor,
+        );
+        manager.wait_on_task_finish(task_id);
+
+        let escalation = escalations.try_recv().ok();
+        assert_eq!(escalation.is_some(), expect_escalation);
+        assert!(escalation.is_none_or(
+            |escalation| escalation.task_id == task_id && escalation.task_name == "ExitingTask"
+        ));
+    }
+
+    #[rstest::rstest]
+    #[case::errored(errored as fn() -> TaskState, true)]
+    #[case::panicked(panicked, true)]
+    #[case::completed(completed, false)]
+    fn given_escalating_task_when_it_exits_then_only_abnormal_exits_are_escalated(
+        #[case] exit: fn() -> TaskState,
+        #[case] expect_escalation: bool,
+    ) {
+        let mut manager = ThreadManager::new();
+        let escalations = manager.escalations();
+
+        let task_id = manager.add_escalating_task(ExitingTask { exit }, std::time::Duration::ZERO);
+        manager.wait_on_task_finish(task_id);
+
+        assert_eq!(escalations.try_recv().is_ok(), expect_escalation);
+        assert_eq!(manager.task_reports().remove(0).restarts, 0);
+    }
+
+    #[test]
+    fn given_running_task_when_reports_taken_then_steps_and_durations_are_reported() {
+        let mut manager = ThreadManager::new();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let task_id = manager.add_task(LoopingTask::new(sender), std::time::Duration::ZERO);
+        // A step sends before it is counted, so the second send follows a counted step.
+        receiver.recv().unwrap();
+        receiver.recv().unwrap();
+
+        let report = manager.task_reports().remove(0);
+        manager.stop_all_tasks();
+        manager.wait_on_task_finish(task_id);
+
+        assert_eq!(report.task_id, task_id);
+        assert_eq!(report.name, "LoopingTask");
+        assert_eq!(report.status, TaskStatus::Running);
+        assert!(report.steps > 0);
+        assert!(report.mean_step_duration.is_some());
+        assert_eq!(report.last_error, None);
+        let final_report = manager.task_reports().remove(0);
+        assert_eq!(final_report.status, TaskStatus::Stopped);
+        assert!(final_report.steps >= report.steps);
+    }
+
+    #[rstest::rstest]
+    #[case::errored(errored as fn() -> TaskState, TaskStatus::Errored, "boom")]
+    #[case::panicked(panicked, TaskStatus::Panicked, "panicked: boom")]
+    #[case::completed(completed, TaskStatus::Completed, "")]
+    fn given_task_exits_when_reports_taken_then_final_status_and_last_error_are_reported(
+        #[case] exit: fn() -> TaskState,
+        #[case] expected_status: TaskStatus,
+        #[case] expected_error: &str,
+    ) {
+        let mut manager = ThreadManager::new();
+        let task_id = manager.add_task(ExitingTask { exit }, std::time::Duration::ZERO);
+        manager.wait_on_task_finish(task_id);
+
+        let report = manager.task_reports().remove(0);
+
+        assert_eq!(report.status, expected_status);
+        assert_eq!(report.last_error.unwrap_or_default(), expected_error);
+        assert_eq!(report.current_step_duration, None);
+    }
+
+    #[test]
+    fn given_task_blocked_in_step_when_reports_taken_then_current_step_duration_grows() {
+        let mut manager = ThreadManager::new();
+        let (release_sender, release_receiver) = crossbeam_channel::bounded::<()>(0);
+        let task_id = manager.add_task(
+            BlockingTask {
+                release: release_receiver,
+            },
+            std::time::Duration::ZERO,
+        );
+        std::thread::sleep(std::time::Duration::from_millis(50));
+
+        let report = manager.status_monitor().reports().remove(0);
+        drop(release_sender);
+        manager.wait_on_task_finish(task_id);
+
+        assert_eq!(report.steps, 0);
+        assert!(report.current_step_duration.unwrap() >= std::time::Duration::from_millis(50));
+    }
+
+    #[test]
+    fn given_task_blocked_in_step_when_waited_on_until_deadline_then_it_is_left_in_place() {
+        let mut manager = ThreadManager::new();
+        let (release_sender, release_receiver) = crossbeam_channel::bounded::<()>(0);
+        let task_id = manager.add_task(
+            BlockingTask {
+                release: release_receiver,
+            },
+            std::time::Duration::ZERO,
+        );
+        let deadline = std::time::Instant::now() + std::time::Duration::from_millis(20);
+
+        let finished_before_release = manager.wait_on_task_finish_until(task_id, deadline);
+        drop(release_sender);
+        let far_deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        let finished_after_release = manager.wait_on_task_finish_until(task_id, far_deadline);
+
+        assert!(!finished_before_release);
+        assert!(finished_after_release);
+        assert!(manager.tasks.is_empty());
+    }
+
+    #[test]
+    fn given_periodic_task_when_running_then_it_sleeps_between_ticks() {
+        let mut manager = ThreadManager::new();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let task_id = manager.add_task(
+            LoopingTask::new(sender),
+            std::time::Duration::from_millis(10),
+        );
+
+        std::thread::sleep(std::time::Duration::from_millis(105));
+        manager.stop_all_tasks();
+        manager.wait_on_task_finish(task_id);
+
+        // One step at start and one per 10 ms tick, rather than a busy loop
+        let steps = receiver.try_iter().count();
+        assert!((5..=13).contains(&steps), "{steps} steps");
+        let schedule = manager.task_reports().remove(0).schedule.unwrap();
+        assert_eq!(schedule.missed_deadlines, 0);
+        assert_eq!(schedule.jitter.count(), steps as u64);
+    }
+
+    #[rstest::rstest]
+    #[case::skip(CatchUpPolicy::Skip)]
+    #[case::burst(CatchUpPolicy::Burst)]
+    #[case::drift(CatchUpPolicy::Drift)]
+    fn given_step_longer_than_period_when_running_then_missed_deadlines_are_counted(
+        #[case] catch_up: CatchUpPolicy,
+    ) {
+        let mut manager = ThreadManager::new();
+        let task_id = manager.add_task(
+            SlowTask { steps_left: 3 },
+            TaskPeriod::new(std::time::Duration::from_millis(10)).with_catch_up(catch_up),
+        );
+        manager.wait_on_task_finish(task_id);
+
+        let report = manager.task_reports().remove(0);
+        let schedule = report.schedule.unwrap();
+        // The last step completes the task before its tick is accounted for
+        assert_eq!(schedule.missed_deadlines, 2);
+        assert_eq!(schedule.skipped_ticks > 0, catch_up == CatchUpPolicy::Skip);
+        assert!(report.step_latency.max >= std::time::Duration::from_millis(25));
+    }
+
+    #[test]
+    fn given_task_with_wakeup_when_idle_then_it_sleeps_until_a_message_or_stop() {
+        let mut manager = ThreadManager::new();
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let (forwarded_sender, forwarded_receiver) = std::sync::mpsc::channel();
+        let task_id = manager.add_task(
+            ForwardingTask {
+                receiver,
+                sender: forwarded_sender,
+            },
+            std::time::Duration::ZERO,
+        );
+
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        let idle_steps = manager.task_reports().remove(0).steps;
+        sender.send(7).unwrap();
+        let forwarded = forwarded_receiver.recv_timeout(std::time::Duration::from_millis(50));
+        let stop_requested = std::time::Instant::now();
+        manager.stop_all_tasks();
+        manager.wait_on_task_finish(task_id);
+
+        assert_eq!(idle_steps, 1);
+        assert_eq!(forwarded, Ok(7));
+        assert!(stop_requested.elapsed() < std::time::Duration::from_millis(50));
+        assert_eq!(manager.task_reports().remove(0).status, TaskStatus::Stopped);
+    }
+
     #[test]
     fn when_multiple_tasks_added_then_all_tasks_completed() {
         let mut manager = ThreadManager::new();
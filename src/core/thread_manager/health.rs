@@ -0,0 +1,264 @@
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::time::Duration;
+
+use super::timing::{CatchUpPolicy, Histogram, LatencyHistogram, TaskPeriod, TickOutcome};
+use super::{SteppableTask, TaskID, TaskState, ThreadStatus};
+
+/// Marks an unset timestamp, or a task that is between steps.
+const UNSET: u64 = u64::MAX;
+
+pub(super) type TaskRegistry = std::sync::Arc<
+    std::sync::RwLock<std::collections::BTreeMap<TaskID, std::sync::Arc<TaskShared>>>,
+>;
+
+/// State of a task written by its thread and read by a [`TaskStatusMonitor`].
+///
+/// Timestamps are nanoseconds since the task was spawned, so that they fit in atomics.
+pub(super) struct TaskShared {
+    pub(super) status: std::sync::RwLock<ThreadStatus>,
+    name: String,
+    period: TaskPeriod,
+    spawned: std::time::Instant,
+    instance_started: AtomicU64,
+    step_started: AtomicU64,
+    finished: AtomicU64,
+    steps: AtomicU64,
+    busy_nanos: AtomicU64,
+    last_step_nanos: AtomicU64,
+    step_latency: LatencyHistogram,
+    jitter: LatencyHistogram,
+    missed_deadlines: AtomicU64,
+    skipped_ticks: AtomicU64,
+    restarts: AtomicU32,
+    panicked: AtomicBool,
+    last_error: std::sync::Mutex<Option<String>>,
+}
+impl TaskShared {
+    pub(super) fn new(name: String, period: TaskPeriod) -> Self {
+        Self {
+            status: std::sync::RwLock::new(ThreadStatus::Active),
+            name,
+            period,
+            spawned: std::time::Instant::now(),
+            instance_started: AtomicU64::new(0),
+            step_started: AtomicU64::new(UNSET),
+            finished: AtomicU64::new(UNSET),
+            steps: AtomicU64::new(0),
+            busy_nanos: AtomicU64::new(0),
+            last_step_nanos: AtomicU64::new(UNSET),
+            step_latency: LatencyHistogram::default(),
+            jitter: LatencyHistogram::default(),
+            missed_deadlines: AtomicU64::new(0),
+            skipped_ticks: AtomicU64::new(0),
+            restarts: AtomicU32::new(0),
+            panicked: AtomicBool::new(false),
+            last_error: std::sync::Mutex::new(None),
+        }
+    }
+
+    fn elapsed_nanos(&self) -> u64 {
+        u64::try_from(self.spawned.elapsed().as_nanos()).unwrap_or(UNSET - 1)
+    }
+
+    /// Steps `task`, measuring how long the step takes.
+    pub(super) fn step<T: SteppableTask>(&self, task: &mut T) -> TaskState {
+        let started = self.elapsed_nanos();
+        self.step_started.store(started, Ordering::Relaxed);
+        let state = task.step();
+        let duration = self.elapsed_nanos().saturating_sub(started);
+        self.step_started.store(UNSET, Ordering::Relaxed);
+        self.last_step_nanos.store(duration, Ordering::Relaxed);
+        self.busy_nanos.fetch_add(duration, Ordering::Relaxed);
+        self.steps.fetch_add(1, Ordering::Relaxed);
+        self.step_latency.record(Duration::from_nanos(duration));
+        state
+    }
+
+    /// Records how late a periodic step started after its tick.
+    pub(super) fn record_jitter(&self, jitter: Duration) {
+        self.jitter.record(jitter);
+    }
+
+    pub(super) fn record_tick(&self, outcome: TickOutcome) {
+        if outcome.missed_deadline {
+            self.missed_deadlines.fetch_add(1, Ordering::Relaxed);
+        }
+        self.skipped_ticks
+            .fetch_add(outcome.skipped_ticks, Ordering::Relaxed);
+    }
+
+    pub(super) fn set_status(&self, status: ThreadStatus) {
+        if let ThreadStatus::Errored(error) = &status {
+            self.set_last_error(error.to_string());
+        }
+        *self.status.write().unwrap() = status;
+    }
+
+    pub(super) fn set_last_error(&self, error: String) {
+        *self.last_error.lock().unwrap() = Some(error);
+    }
+
+    /// Starts the uptime of a fresh instance of the task.
+    pub(super) fn restarted(&self) {
+        self.restarts.fetch_add(1, Ordering::Relaxed);
+        self.instance_started
+            .store(self.elapsed_nanos(), Ordering::Relaxed);
+        self.set_status(ThreadStatus::Active);
+    }
+
+    /// Called as the thread exits, `panic` holding the message it panicked with if any.
+    pub(super) fn finished(&self, panic: Option<&str>) {
+        if let Some(message) = panic {
+            self.set_last_error(format!("panicked: {message}"));
+            self.panicked.store(true, Ordering::Relaxed);
+        }
+        self.step_started.store(UNSET, Ordering::Relaxed);
+        self.finished.store(self.elapsed_nanos(), Ordering::Relaxed);
+    }
+
+    fn report(&self, task_id: TaskID) -> TaskReport {
+        let now = match self.finished.load(Ordering::Relaxed) {
+            UNSET => self.elapsed_nanos(),
+            finished => finished,
+        };
+        let status = if self.panicked.load(Ordering::Relaxed) {
+            TaskStatus::Panicked
+        } else {
+            match &*self.status.read().unwrap() {
+                ThreadStatus::Active => TaskStatus::Running,
+                ThreadStatus::Interrupted => TaskStatus::Stopped,
+                ThreadStatus::Completed => TaskStatus::Completed,
+                ThreadStatus::Errored(_) => TaskStatus::Errored,
+            }
+        };
+        let steps = self.steps.load(Ordering::Relaxed);
+        TaskReport {
+            task_id,
+            name: self.name.clone(),
+            status,
+            uptime: Duration::from_nanos(
+                now.saturating_sub(self.instance_started.load(Ordering::Relaxed)),
+            ),
+            steps,
+            restarts: self.restarts.load(Ordering::Relaxed),
+            last_error: self.last_error.lock().unwrap().clone(),
+            last_step_duration: match self.last_step_nanos.load(Ordering::Relaxed) {
+                UNSET => None,
+                nanos => Some(Duration::from_nanos(nanos)),
+            },
+            mean_step_duration: (steps > 0)
+                .then(|| Duration::from_nanos(self.busy_nanos.load(Ordering::Relaxed) / steps)),
+            current_step_duration: match self.step_started.load(Ordering::Relaxed) {
+                UNSET => None,
+                started => Some(Duration::from_nanos(now.saturating_sub(started))),
+            },
+            step_latency: self.step_latency.snapshot(),
+            schedule: (!self.period.interval.is_zero()).then(|| ScheduleReport {
+                period: self.period.interval,
+                catch_up: self.period.catch_up,
+                missed_deadlines: self.missed_deadlines.load(Ordering::Relaxed),
+                skipped_ticks: self.skipped_ticks.load(Ordering::Relaxed),
+                jitter: self.jitter.snapshot(),
+            }),
+        }
+    }
+}
+
+/// Public counterpart of a task's internal status.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStatus {
+    Running,
+    /// Stopped on request.
+    Stopped,
+    Completed,
+    Errored,
+    Panicked,
+}
+
+/// Health of one task at the time it was taken.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct TaskReport {
+    pub task_id: TaskID,
+    pub name: String,
+    pub status: TaskStatus,
+    /// Time the current instance has been running, or ran for once finished.
+    pub uptime: Duration,
+    pub steps: u64,
+    /// Times a supervisor replaced the task with a fresh instance.
+    pub restarts: u32,
+    pub last_error: Option<String>,
+    pub last_step_duration: Option<Duration>,
+    pub mean_step_duration: Option<Duration>,
+    /// How long the step in progress has been running. A task blocked in a step, like an
+    /// ingestor waiting on a silent connection, shows a growing value here.
+    pub current_step_duration: Option<Duration>,
+    pub step_latency: Histogram,
+    /// Timing against the schedule, for tasks stepped periodically.
+    pub schedule: Option<ScheduleReport>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct ScheduleReport {
+    pub period: Duration,
+    pub catch_up: CatchUpPolicy,
+    /// Steps that finished after the following tick was due.
+    pub missed_deadlines: u64,
+    pub skipped_ticks: u64,
+    /// How late each step started after its tick.
+    pub jitter: Histogram,
+}
+impl std::fmt::Display for TaskReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Task {} {}: {:?} for {:.1?}, {} steps",
+            self.task_id, self.name, self.status, self.uptime, self.steps
+        )?;
+        if let Some(mean_step_duration) = self.mean_step_duration {
+            write!(f, " (mean {mean_step_duration:.1?})")?;
+        }
+        if let Some(p99) = self.step_latency.quantile(0.99) {
+            write!(f, ", p99 {p99:.1?}")?;
+        }
+        if let Some(schedule) = &self.schedule {
+            write!(
+                f,
+                ", {} missed deadlines, {} skipped ticks",
+                schedule.missed_deadlines, schedule.skipped_ticks
+            )?;
+            if let Some(jitter) = schedule.jitter.quantile(0.99) {
+                write!(f, ", p99 jitter {jitter:.1?}")?;
+            }
+        }
+        if let Some(current_step_duration) = self.current_step_duration {
+            write!(f, ", in step for {current_step_duration:.1?}")?;
+        }
+        if self.restarts > 0 {
+            write!(f, ", {} restarts", self.restarts)?;
+        }
+        if let Some(last_error) = &self.last_error {
+            write!(f, ", last error: {last_error}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Lists the tasks of a [`ThreadManager`](super::ThreadManager) without blocking on them,
+/// including tasks added after the monitor was created and tasks that have finished.
+#[derive(Clone)]
+pub struct TaskStatusMonitor {
+    pub(super) registry: TaskRegistry,
+}
+impl TaskStatusMonitor {
+    /// Reports of every task, ordered by task ID.
+    #[must_use]
+    pub fn reports(&self) -> Vec<TaskReport> {
+        self.registry
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(task_id, shared)| shared.report(*task_id))
+            .collect()
+    }
+}
@@ -0,0 +1,270 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// What a periodic task does with the ticks it missed while a step overran.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CatchUpPolicy {
+    /// Drops the missed ticks and resumes on the next tick of the original schedule.
+    #[default]
+    Skip,
+    /// Runs the missed ticks back to back, without sleeping, until back on schedule.
+    Burst,
+    /// Runs once right away, then keeps the period from there, shifting the schedule.
+    Drift,
+}
+
+/// How often a task is stepped. A zero interval steps it continuously.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TaskPeriod {
+    pub interval: Duration,
+    pub catch_up: CatchUpPolicy,
+}
+impl TaskPeriod {
+    #[must_use]
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            catch_up: CatchUpPolicy::default(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_catch_up(mut self, catch_up: CatchUpPolicy) -> Self {
+        self.catch_up = catch_up;
+        self
+    }
+}
+impl From<Duration> for TaskPeriod {
+    fn from(interval: Duration) -> Self {
+        Self::new(interval)
+    }
+}
+
+/// Ticks of a periodic task, starting at the instant it was created.
+#[derive(Debug)]
+pub(super) struct Schedule {
+    period: TaskPeriod,
+    next_tick: Instant,
+}
+
+/// Outcome of one step against its tick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct TickOutcome {
+    /// The step finished after the following tick was due.
+    pub(super) missed_deadline: bool,
+    /// Ticks dropped by [`CatchUpPolicy::Skip`].
+    pub(super) skipped_ticks: u64,
+}
+
+impl Schedule {
+    pub(super) fn new(period: TaskPeriod, start: Instant) -> Self {
+        Self {
+            period,
+            next_tick: start,
+        }
+    }
+
+    /// When the next step is due; it runs straight away if this has passed.
+    pub(super) fn next_tick(&self) -> Instant {
+        self.next_tick
+    }
+
+    /// Moves to the tick after a step that finished at `finished`.
+    pub(super) fn complete(&mut self, finished: Instant) -> TickOutcome {
+        let interval = self.period.interval;
+        let deadline = self.next_tick + interval;
+        let missed_deadline = finished > deadline;
+        let mut skipped_ticks = 0;
+        self.next_tick = match self.period.catch_up {
+            CatchUpPolicy::Burst => deadline,
+            CatchUpPolicy::Drift => deadline.max(finished),
+            CatchUpPolicy::Skip if missed_deadline => {
+                let overrun = finished - deadline;
+                let ticks = overrun.as_nanos().div_ceil(interval.as_nanos());
+                skipped_ticks = u64::try_from(ticks).unwrap_or(u64::MAX);
+                deadline + interval * u32::try_from(ticks).unwrap_or(u32::MAX)
+            }
+            CatchUpPolicy::Skip => deadline,
+        };
+        TickOutcome {
+            missed_deadline,
+            skipped_ticks,
+        }
+    }
+}
+
+/// Upper bounds of the histogram buckets, doubling from 1 µs to about 8.4 s. Longer
+/// durations land in a final, unbounded bucket.
+const BUCKET_COUNT: usize = 24;
+
+fn bucket_bound(bucket: usize) -> Duration {
+    Duration::from_micros(1 << bucket)
+}
+
+/// Durations counted into power-of-two buckets, recorded without locking.
+#[derive(Debug)]
+pub(super) struct LatencyHistogram {
+    buckets: [AtomicU64; BUCKET_COUNT + 1],
+    max_nanos: AtomicU64,
+}
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self {
+            buckets: std::array::from_fn(|_| AtomicU64::new(0)),
+            max_nanos: AtomicU64::new(0),
+        }
+    }
+}
+impl LatencyHistogram {
+    pub(super) fn record(&self, duration: Duration) {
+        let bucket = (0..BUCKET_COUNT)
+            .find(|bucket| duration <= bucket_bound(*bucket))
+            .unwrap_or(BUCKET_COUNT);
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.max_nanos.fetch_max(
+            u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX),
+            Ordering::Relaxed,
+        );
+    }
+
+    pub(super) fn snapshot(&self) -> Histogram {
+        Histogram {
+            buckets: self
+                .buckets
+                .iter()
+                .enumerate()
+                .filter_map(|(bucket, count)| {
+                    let count = count.load(Ordering::Relaxed);
+                    (count > 0).then(|| HistogramBucket {
+                        le: (bucket < BUCKET_COUNT).then(|| bucket_bound(bucket)),
+                        count,
+                    })
+                })
+                .collect(),
+            max: Duration::from_nanos(self.max_nanos.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// Snapshot of a latency histogram, listing only the buckets that were hit.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize)]
+pub struct Histogram {
+    pub buckets: Vec<HistogramBucket>,
+    pub max: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub struct HistogramBucket {
+    /// Largest duration counted in this bucket; `None` for the bucket past the last bound.
+    pub le: Option<Duration>,
+    pub count: u64,
+}
+
+impl Histogram {
+    #[must_use]
+    pub fn count(&self) -> u64 {
+        self.buckets.iter().map(|bucket| bucket.count).sum()
+    }
+
+    /// Upper bound of the bucket holding the `quantile` (0 to 1) of the recorded
+    /// durations, or the maximum when that falls past the last bound.
+    #[must_use]
+    pub fn quantile(&self, quantile: f64) -> Option<Duration> {
+        let count = self.count();
+        if count == 0 {
+            return None;
+        }
+        #[allow(
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss,
+            clippy::cast_precision_loss
+        )]
+        let rank = ((quantile.clamp(0.0, 1.0) * count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        self.buckets.iter().find_map(|bucket| {
+            seen += bucket.count;
+            (seen >= rank).then(|| bucket.le.unwrap_or(self.max).min(self.max))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PERIOD: Duration = Duration::from_millis(10);
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    /// Steps finishing at the given offsets from the start, and the ticks that follow.
+    fn run_schedule(catch_up: CatchUpPolicy, finished_at: &[u64]) -> Vec<(u64, TickOutcome)> {
+        let start = Instant::now();
+        let mut schedule = Schedule::new(TaskPeriod::new(PERIOD).with_catch_up(catch_up), start);
+        finished_at
+            .iter()
+            .map(|finished| {
+                let outcome = schedule.complete(start + ms(*finished));
+                let next_tick = schedule.next_tick() - start;
+                (u64::try_from(next_tick.as_millis()).unwrap(), outcome)
+            })
+            .collect()
+    }
+
+    fn on_time() -> TickOutcome {
+        TickOutcome {
+            missed_deadline: false,
+            skipped_ticks: 0,
+        }
+    }
+
+    fn missed(skipped_ticks: u64) -> TickOutcome {
+        TickOutcome {
+            missed_deadline: true,
+            skipped_ticks,
+        }
+    }
+
+    // Each case runs a step at 0 ms finishing at 2 ms, then one overrunning to 35 ms.
+    #[rstest::rstest]
+    #[case::skip(CatchUpPolicy::Skip, [(10, on_time()), (40, missed(2)), (50, on_time())])]
+    #[case::burst(CatchUpPolicy::Burst, [(10, on_time()), (20, missed(0)), (30, missed(0))])]
+    #[case::drift(CatchUpPolicy::Drift, [(10, on_time()), (35, missed(0)), (45, on_time())])]
+    fn given_catch_up_policy_when_step_overruns_then_next_ticks_follow_policy(
+        #[case] catch_up: CatchUpPolicy,
+        #[case] expected: [(u64, TickOutcome); 3],
+    ) {
+        // The third step starts when its tick is due (or straight away when already late),
+        // and takes 1 ms.
+        let third_finish = match catch_up {
+            CatchUpPolicy::Skip => 41,
+            CatchUpPolicy::Burst | CatchUpPolicy::Drift => 36,
+        };
+
+        assert_eq!(run_schedule(catch_up, &[2, 35, third_finish]), expected);
+    }
+
+    #[test]
+    fn given_step_ending_exactly_on_deadline_when_completed_then_deadline_is_met() {
+        assert_eq!(run_schedule(CatchUpPolicy::Skip, &[10]), [(10, on_time())]);
+    }
+
+    #[test]
+    fn given_recorded_durations_when_snapshot_taken_then_quantiles_use_bucket_bounds() {
+        let histogram = LatencyHistogram::default();
+        for micros in [3, 3, 3, 100, 20_000_000] {
+            histogram.record(Duration::from_micros(micros));
+        }
+
+        let snapshot = histogram.snapshot();
+
+        assert_eq!(snapshot.count(), 5);
+        assert_eq!(snapshot.max, Duration::from_secs(20));
+        assert_eq!(snapshot.quantile(0.5), Some(Duration::from_micros(4)));
+        assert_eq!(snapshot.quantile(0.8), Some(Duration::from_micros(128)));
+        assert_eq!(snapshot.quantile(1.0), Some(Duration::from_secs(20)));
+        assert_eq!(Histogram::default().quantile(0.5), None);
+    }
+}
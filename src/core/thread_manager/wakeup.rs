@@ -0,0 +1,111 @@
+/// Something a task can wait on through a [`crossbeam_channel::Select`].
+pub trait WakeSource {
+    fn register<'a>(&'a self, select: &mut crossbeam_channel::Select<'a>);
+}
+
+impl<T> WakeSource for crossbeam_channel::Receiver<T> {
+    fn register<'a>(&'a self, select: &mut crossbeam_channel::Select<'a>) {
+        select.recv(self);
+    }
+}
+
+/// What a task waits on between steps: until a source has a message or is disconnected,
+/// until `deadline`, or until the task is asked to stop, whichever comes first.
+#[derive(Default)]
+pub struct Wakeup<'a> {
+    sources: Vec<&'a dyn WakeSource>,
+    deadline: Option<std::time::Instant>,
+}
+
+impl<'a> Wakeup<'a> {
+    #[must_use]
+    pub fn on(source: &'a dyn WakeSource) -> Self {
+        Self::default().or_on(source)
+    }
+
+    #[must_use]
+    pub fn or_on(mut self, source: &'a dyn WakeSource) -> Self {
+        self.sources.push(source);
+        self
+    }
+
+    /// Also wakes at `deadline`, when set.
+    #[must_use]
+    pub fn or_at(mut self, deadline: Option<std::time::Instant>) -> Self {
+        self.deadline = match (self.deadline, deadline) {
+            (Some(current), Some(deadline)) => Some(current.min(deadline)),
+            (current, deadline) => current.or(deadline),
+        };
+        self
+    }
+
+    /// Blocks until the task should step. Returns `false` when woken by `stop` instead.
+    pub(super) fn wait(&self, stop: &crossbeam_channel::Receiver<()>) -> bool {
+        let mut select = crossbeam_channel::Select::new();
+        let stop_index = select.recv(stop);
+        for source in &self.sources {
+            source.register(&mut select);
+        }
+        let ready = match self.deadline {
+            Some(deadline) => select.ready_deadline(deadline).ok(),
+            None => Some(select.ready()),
+        };
+        ready != Some(stop_index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rstest::rstest]
+    #[case::message(true, false, true)]
+    #[case::stop(false, true, false)]
+    #[case::deadline(false, false, true)]
+    fn given_wakeup_when_waiting_then_it_returns_on_first_event(
+        #[case] send_message: bool,
+        #[case] send_stop: bool,
+        #[case] expect_step: bool,
+    ) {
+        let (sender, receiver) = crossbeam_channel::unbounded::<u32>();
+        let (stop_sender, stop_receiver) = crossbeam_channel::bounded(1);
+        if send_message {
+            sender.send(1).unwrap();
+        }
+        if send_stop {
+            stop_sender.send(()).unwrap();
+        }
+        let deadline = std::time::Instant::now() + std::time::Duration::from_millis(20);
+
+        let started = std::time::Instant::now();
+        let step = Wakeup::on(&receiver)
+            .or_at(Some(deadline))
+            .wait(&stop_receiver);
+
+        assert_eq!(step, expect_step);
+        let waited_for_deadline = started.elapsed() >= std::time::Duration::from_millis(20);
+        assert_eq!(waited_for_deadline, !send_message && !send_stop);
+    }
+
+    #[test]
+    fn given_several_deadlines_when_combined_then_earliest_is_kept() {
+        let now = std::time::Instant::now();
+        let later = now + std::time::Duration::from_secs(1);
+
+        assert_eq!(
+            Wakeup::default()
+                .or_at(Some(later))
+                .or_at(Some(now))
+                .deadline,
+            Some(now)
+        );
+        assert_eq!(
+            Wakeup::default().or_at(None).or_at(Some(later)).deadline,
+            Some(later)
+        );
+        assert_eq!(
+            Wakeup::default().or_at(Some(later)).or_at(None).deadline,
+            Some(later)
+        );
+    }
+}
@@ -2,13 +2,21 @@ use eframe::{egui, epaint};
 use walkers;
 
 use crate::core::airspace::AirspaceViewer;
+use crate::core::channel::ChannelMonitor;
+use crate::core::ingestor::ReconnectMonitor;
 use crate::core::parser::Aircraft;
+use crate::core::receivers::{Receiver, ReceiverViewer};
+use crate::core::thread_manager::TaskStatusMonitor;
 use crate::gui::constants::AIRCRAFT_REFERENCE_SHAPE;
 
 pub struct RadarApp {
     airspace_viewer: AirspaceViewer,
     tiles: walkers::HttpTiles,
     map_memory: walkers::MapMemory,
+    channel_monitors: Vec<ChannelMonitor>,
+    task_status_monitor: Option<TaskStatusMonitor>,
+    reconnect_monitors: Vec<ReconnectMonitor>,
+    receiver_viewer: Option<ReceiverViewer>,
 }
 
 impl RadarApp {
@@ -18,8 +26,40 @@ impl RadarApp {
             tiles: walkers::HttpTiles::new(walkers::sources::OpenStreetMap, egui_ctx),
             map_memory: walkers::MapMemory::default(),
             airspace_viewer,
+            channel_monitors: Vec::new(),
+            task_status_monitor: None,
+            reconnect_monitors: Vec::new(),
+            receiver_viewer: None,
         }
     }
+
+    /// Shows the queued and dropped counts of these channels in a window.
+    #[must_use]
+    pub fn with_channel_monitors(mut self, channel_monitors: Vec<ChannelMonitor>) -> Self {
+        self.channel_monitors = channel_monitors;
+        self
+    }
+
+    /// Shows the status of every pipeline task in a window.
+    #[must_use]
+    pub fn with_task_status_monitor(mut self, task_status_monitor: TaskStatusMonitor) -> Self {
+        self.task_status_monitor = Some(task_status_monitor);
+        self
+    }
+
+    /// Shows how often each live source reconnected, below the tasks.
+    #[must_use]
+    pub fn with_reconnect_monitors(mut self, reconnect_monitors: Vec<ReconnectMonitor>) -> Self {
+        self.reconnect_monitors = reconnect_monitors;
+        self
+    }
+
+    /// Lists the ground stations heard, with their health, in a window.
+    #[must_use]
+    pub fn with_receiver_viewer(mut self, receiver_viewer: ReceiverViewer) -> Self {
+        self.receiver_viewer = Some(receiver_viewer);
+        self
+    }
 }
 
 impl eframe::App for RadarApp {
@@ -43,6 +83,38 @@ impl eframe::App for RadarApp {
 
                 map.show(ui, |_ui, _response, _projector, _map_memory| {})
             });
+        if !self.channel_monitors.is_empty() {
+            egui::Window::new("Channels")
+                .default_open(false)
+                .show(ctx, |ui| {
+                    for monitor in &self.channel_monitors {
+                        ui.label(monitor.stats().to_string());
+                    }
+                });
+        }
+        if let Some(task_status_monitor) = &self.task_status_monitor {
+            egui::Window::new("Tasks")
+                .default_open(false)
+                .show(ctx, |ui| {
+                    for report in task_status_monitor.reports() {
+                        ui.label(report.to_string());
+                    }
+                    for monitor in &self.reconnect_monitors {
+                        ui.label(monitor.to_string());
+                    }
+                });
+        }
+        if let Some(receiver_viewer) = &self.receiver_viewer {
+            egui::Window::new("Receivers")
+                .default_open(false)
+                .show(ctx, |ui| {
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        for receiver in receiver_viewer.read().receivers() {
+                            ui.label(describe_receiver(receiver));
+                        }
+                    });
+                });
+        }
     }
 }
 
@@ -72,7 +144,7 @@ impl walkers::Plugin for AirspacePlugin {
         // read from airspace and render information on screen.
         let airspace = self.viewer.read();
 
-        for aircraft_queue in airspace.icao_to_aircraft_mapping().values() {
+        for aircraft_queue in airspace.track_to_aircraft_mapping().values() {
             if aircraft_queue.is_empty() {
                 continue;
             }
@@ -154,18 +226,58 @@ fn draw_aircraft(
     scale_factor: f32,
     color: epaint::Color32,
 ) {
-    // calculate shape of aircraft drawn on screen based on the actual point
+    // calculate shape of aircraft drawn on screen based on the actual point, pointing
+    // north when no track is reported
     #[allow(clippy::cast_possible_truncation)]
-    let aircraft_bearing = aircraft.ground_track.to_radians() as f32;
+    let aircraft_bearing = aircraft.ground_track.unwrap_or_default().to_radians() as f32;
     let shape = build_aircraft_path_shape(current_position, scale_factor, aircraft_bearing, color);
 
     ui.painter().add(shape);
 
+    // stealth aircraft are drawn without identification
+    if aircraft.stealth {
+        return;
+    }
+    let identity = aircraft.icao_address.map_or_else(
+        || aircraft.callsign.clone(),
+        |icao_address| icao_address.to_string(),
+    );
+    let label = match aircraft.climb_rate_metres_per_second {
+        Some(climb_rate) => format!("{identity} {climb_rate:+.1} m/s"),
+        None => identity,
+    };
     ui.painter().text(
         current_position,
         egui::Align2::LEFT_BOTTOM,
-        aircraft.icao_address.to_string(),
+        label,
         egui::FontId::default(),
         color,
     );
 }
+
+fn describe_receiver(receiver: &Receiver) -> String {
+    let mut description = format!(
+        "{} ({}): heard {}",
+        receiver.name,
+        receiver.server,
+        receiver.last_heard.format("%H:%M:%S")
+    );
+    if let Some(status) = &receiver.status {
+        if let Some(version) = &status.version {
+            description += &format!(", v{version}");
+        }
+        if let Some(cpu_load) = status.cpu_load {
+            description += &format!(", CPU {cpu_load}");
+        }
+        if let Some(temperature) = status.cpu_temperature_celsius {
+            description += &format!(", {temperature}°C");
+        }
+        if let Some(voltage) = status.voltage_volts {
+            description += &format!(", {voltage}V");
+        }
+        if let Some(noise) = status.rf_noise_db {
+            description += &format!(", noise {noise}dB");
+        }
+    }
+    description
+}
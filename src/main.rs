@@ -3,17 +3,38 @@ use clap::Parser;
 use flights::AirspaceDataPipeline;
 use flights::Cli;
 use flights::RadarApp;
+use flights::cli::Command;
+use flights::core::ingestor::recording::rebuild_time_index;
+use flights::core::ingestor::session::SessionEvent;
 use flights::logging::setup_logging;
 use flights::pipeline::config::PipelineConfig;
 
 fn main() {
     let cli = Cli::parse();
     setup_logging(cli.logging_level);
-    let pipeline_config =
-        PipelineConfig::construct_from_path(&cli.config_file).unwrap_or_else(|e| {
-            log::error!("{e}");
-            panic!("Config error. Exiting.")
-        });
+
+    if let Some(Command::RebuildIndex {
+        recording,
+        interval_seconds,
+    }) = &cli.command
+    {
+        match rebuild_time_index(recording, std::time::Duration::from_secs(*interval_seconds)) {
+            Ok(entries) => log::info!("Wrote {entries} index entries for {}", recording.display()),
+            Err(err) => {
+                log::error!("Failed to rebuild index for {}: {err}", recording.display());
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    let config_file = cli
+        .config_file
+        .expect("clap requires --config-file without a subcommand");
+    let pipeline_config = PipelineConfig::construct_from_path(&config_file).unwrap_or_else(|e| {
+        log::error!("{e}");
+        panic!("Config error. Exiting.")
+    });
 
     log::info!("Main: Application started.");
 
@@ -24,6 +45,8 @@ fn main() {
         });
 
     let run_duration = cli.duration.map(std::time::Duration::from_secs);
+    let escalations = data_pipeline.escalations();
+    log_session_events(data_pipeline.session_events());
 
     if cli.gui {
         let options = eframe::NativeOptions::default();
@@ -31,27 +54,90 @@ fn main() {
             "Airspace Radar",
             options,
             Box::new(|cc| {
-                if let Some(duration) = run_duration {
-                    let ctx = cc.egui_ctx.clone();
-                    std::thread::spawn(move || {
-                        std::thread::sleep(duration);
-                        log::info!("Duration reached. Requesting GUI close.");
-                        ctx.send_viewport_cmd(eframe::egui::ViewportCommand::Close);
-                    });
-                }
-                Ok(Box::new(RadarApp::new(
-                    cc.egui_ctx.clone(),
-                    data_pipeline.get_airspace_viewer(),
-                )))
+                let ctx = cc.egui_ctx.clone();
+                std::thread::spawn(move || {
+                    match run_duration {
+                        Some(duration) => match escalations.recv_timeout(duration) {
+                            Ok(escalation) => log::error!("{escalation}. Requesting GUI close."),
+                            Err(_) => log::info!("Duration reached. Requesting GUI close."),
+                        },
+                        None => match escalations.recv() {
+                            Ok(escalation) => log::error!("{escalation}. Requesting GUI close."),
+                            Err(_) => return,
+                        },
+                    }
+                    ctx.send_viewport_cmd(eframe::egui::ViewportCommand::Close);
+                });
+                Ok(Box::new(
+                    RadarApp::new(cc.egui_ctx.clone(), data_pipeline.get_airspace_viewer())
+                        .with_channel_monitors(data_pipeline.channel_monitors().to_vec())
+                        .with_task_status_monitor(data_pipeline.task_status_monitor())
+                        .with_reconnect_monitors(data_pipeline.reconnect_monitors().to_vec())
+                        .with_receiver_viewer(data_pipeline.get_receiver_viewer()),
+                ))
             }),
         )
         .unwrap();
     } else if let Some(duration) = run_duration {
-        std::thread::sleep(duration);
+        log_pipeline_health_periodically(&data_pipeline);
+        if let Ok(escalation) = escalations.recv_timeout(duration) {
+            log::error!("{escalation}. Shutting down pipeline.");
+        }
     } else {
-        // run indefinitely until something breaks the chain
-        std::thread::park();
+        log_pipeline_health_periodically(&data_pipeline);
+        // run indefinitely until a supervisor escalates
+        if let Ok(escalation) = escalations.recv() {
+            log::error!("{escalation}. Shutting down pipeline.");
+        }
     }
     data_pipeline.shutdown();
     log::info!("Shutting down application.");
 }
+
+/// Without the GUI the operator only sees channel counters, task health, source reconnects
+/// and parser counts in the log.
+fn log_pipeline_health_periodically(data_pipeline: &AirspaceDataPipeline) {
+    const INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+    let monitors = data_pipeline.channel_monitors().to_vec();
+    let task_status_monitor = data_pipeline.task_status_monitor();
+    let reconnect_monitors = data_pipeline.reconnect_monitors().to_vec();
+    let parser_monitor = data_pipeline.parser_monitor();
+    std::thread::spawn(move || {
+        loop {
+            std::thread::sleep(INTERVAL);
+            for monitor in &monitors {
+                log::info!("Channel {}", monitor.stats());
+            }
+            for report in task_status_monitor.reports() {
+                log::info!("{report}");
+            }
+            for monitor in &reconnect_monitors {
+                log::info!("{monitor}");
+            }
+            log::info!("Parser: {}", parser_monitor.stats());
+        }
+    });
+}
+
+/// Logs what the APRS-IS servers say until every live source is gone.
+fn log_session_events(session_events: crossbeam_channel::Receiver<SessionEvent>) {
+    std::thread::spawn(move || {
+        for event in session_events {
+            match event {
+                SessionEvent::LoginResponse(response) => log::info!(
+                    "Logged in to APRS-IS server {} as {} ({})",
+                    response.server.as_deref().unwrap_or("<unknown>"),
+                    response.callsign,
+                    if response.verified {
+                        "verified"
+                    } else {
+                        "unverified"
+                    }
+                ),
+                SessionEvent::ServerBanner(text) | SessionEvent::ServerKeepalive(text) => {
+                    log::debug!("APRS-IS server: {text}");
+                }
+            }
+        }
+    });
+}
@@ -1,14 +1,27 @@
+use std::num::NonZeroUsize;
 use std::path::PathBuf;
 
 use serde;
 use toml;
 
-use crate::core::ingestor::config::GliderNetConfig;
+use crate::core::central_disk_logger::compression::Compression;
+use crate::core::central_disk_logger::rotation::RotationPolicy;
+use crate::core::channel::ChannelConfig;
+use crate::core::ingestor::config::{GliderNetConfig, ReplaySpeed};
+use crate::core::ingestor::errors::FilterError;
+use crate::core::parser::decoders::TrackerKind;
+use crate::core::thread_manager::CatchUpPolicy;
 
 #[derive(serde::Deserialize)]
 pub struct PipelineConfig {
     pub ingestor: IngestorConfig,
+    #[serde(default)]
+    pub parser: ParserConfig,
     pub airspace: AirspaceConfig,
+    #[serde(default)]
+    pub channels: ChannelsConfig,
+    #[serde(default)]
+    pub shutdown: ShutdownConfig,
 }
 
 impl PipelineConfig {
@@ -21,23 +34,154 @@ impl PipelineConfig {
                 path: path.to_path_buf(),
             })?;
 
-        let config: Result<PipelineConfig, errors::PipelineConfigError> = toml::from_str(&string)
-            .map_err(|error| errors::PipelineConfigError::Parse {
+        let config: PipelineConfig =
+            toml::from_str(&string).map_err(|error| errors::PipelineConfigError::Parse {
                 source: error,
                 path: path.to_path_buf(),
-            });
+            })?;
+
         config
+            .validate()
+            .map_err(|error| errors::PipelineConfigError::InvalidFilter {
+                source: error,
+                path: path.to_path_buf(),
+            })?;
+        Ok(config)
+    }
+
+    /// Checks values that deserialize fine but would be rejected or misbehave at runtime.
+    pub fn validate(&self) -> Result<(), FilterError> {
+        for source in &self.ingestor.sources {
+            if let IngestorSource::GliderNet(config) = source {
+                config.filter.filters()?;
+            }
+        }
+        Ok(())
     }
 }
 
 #[derive(serde::Deserialize)]
 pub struct IngestorConfig {
-    pub source: IngestorSource,
+    /// Either a single `[ingestor.source]` table or several `[[ingestor.source]]` tables,
+    /// whose packets are merged into one stream.
+    #[serde(rename = "source", deserialize_with = "one_or_many")]
+    pub sources: Vec<IngestorSource>,
+    pub write_path: Option<PathBuf>,
+    /// Compresses the recording at `write_path`, adding the matching extension. A path
+    /// already ending in `.pb.gz` or `.pb.zst` is compressed without this.
+    #[serde(default)]
+    pub compression: Compression,
+    /// Rolls the recording over to new, timestamped files. `write_path` may then hold
+    /// `strftime` specifiers, e.g. `./data/ingestor-%Y%m%d-%H.pb`.
+    #[serde(default)]
+    pub rotation: RotationPolicy,
+    /// Packets repeated by more than one source within this window are dropped.
+    #[serde(default = "default_dedup_window_millis")]
+    pub dedup_window_millis: u64,
+}
+
+fn default_dedup_window_millis() -> u64 {
+    5000
+}
+
+fn one_or_many<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: serde::Deserialize<'de>,
+{
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany<T> {
+        One(T),
+        Many(Vec<T>),
+    }
+    Ok(
+        match <OneOrMany<T> as serde::Deserialize>::deserialize(deserializer)? {
+            OneOrMany::One(item) => vec![item],
+            OneOrMany::Many(items) => items,
+        },
+    )
+}
+#[derive(serde::Deserialize, Default)]
+pub struct ParserConfig {
+    /// Records every parsed aircraft, as protobuf for a `.pb` path or JSON lines for a
+    /// `.jsonl` path, either optionally followed by `.gz` or `.zst`.
     pub write_path: Option<PathBuf>,
+    #[serde(default)]
+    pub rotation: RotationPolicy,
+    /// Beacons whose time is further than this from their receive time are dropped and
+    /// counted. 300 when unset.
+    pub max_clock_skew_seconds: Option<u32>,
+    /// Records every line that fails to parse, with why, to this `.jsonl` path.
+    pub quarantine_path: Option<PathBuf>,
+    #[serde(default)]
+    pub quarantine_rotation: RotationPolicy,
+    /// Lines kept in memory per kind of failure, for the parser statistics. 20 when unset.
+    pub failure_samples: Option<usize>,
+    /// Parser threads, each parsing the beacons of its share of the senders. 1 when unset.
+    pub workers: Option<NonZeroUsize>,
+    /// Other trackers whose position reports are shown alongside OGN aircraft.
+    #[serde(default)]
+    pub decoders: Vec<TrackerKind>,
 }
+
 #[derive(serde::Deserialize)]
 pub struct AirspaceConfig {
     pub time_buffer_seconds: u8,
+    /// Most aircraft applied to the airspace per tick; all pending aircraft when unset.
+    pub batch_budget: Option<usize>,
+    /// What the airspace update does with ticks missed while an update overran.
+    #[serde(default)]
+    pub catch_up: CatchUpPolicy,
+    /// Records a snapshot of the latest state of every aircraft, in the format given by
+    /// the extension as for [`ParserConfig::write_path`].
+    pub snapshot_path: Option<PathBuf>,
+    /// Airspace time between snapshots.
+    #[serde(default = "default_snapshot_interval_seconds")]
+    pub snapshot_interval_seconds: u32,
+    #[serde(default)]
+    pub snapshot_rotation: RotationPolicy,
+}
+
+fn default_snapshot_interval_seconds() -> u32 {
+    10
+}
+
+/// Capacity and overflow policy of each pipeline edge, named after the stage sending into it.
+#[derive(serde::Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChannelsConfig {
+    pub ingestor: ChannelConfig,
+    /// Only used with more than one ingestor source.
+    pub deduplicator: ChannelConfig,
+    pub parser: ChannelConfig,
+    /// Receiver beacons, also sent by the parser.
+    pub receivers: ChannelConfig,
+    /// The input of each parser worker, when there are several.
+    pub parser_shards: ChannelConfig,
+    /// Shared by every recording.
+    pub disk_logger: ChannelConfig,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(default)]
+pub struct ShutdownConfig {
+    /// Time given to the stages to drain their queues and stop, after which what is
+    /// still queued is lost.
+    pub timeout_seconds: u64,
+}
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self {
+            timeout_seconds: 10,
+        }
+    }
+}
+impl ShutdownConfig {
+    #[must_use]
+    pub fn timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.timeout_seconds)
+    }
 }
 
 pub mod errors {
@@ -55,6 +199,12 @@ pub mod errors {
             source: std::io::Error,
             path: std::path::PathBuf,
         },
+        #[error("Invalid APRS-IS filter in config file: {path}\n {source}")]
+        InvalidFilter {
+            #[source]
+            source: crate::core::ingestor::errors::FilterError,
+            path: std::path::PathBuf,
+        },
     }
 }
 
@@ -64,9 +214,183 @@ pub enum IngestorSource {
     GliderNet(GliderNetConfig),
     FilePath(FilePathConfig),
 }
+impl IngestorSource {
+    /// Name packets from this source are tagged with.
+    #[must_use]
+    pub fn name(&self) -> String {
+        match self {
+            IngestorSource::GliderNet(config) => config
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("{}:{}", config.host, config.port)),
+            IngestorSource::FilePath(config) => config
+                .name
+                .clone()
+                .unwrap_or_else(|| config.read_path.display().to_string()),
+        }
+    }
+}
 
 #[derive(serde::Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct FilePathConfig {
+    /// Tag attached to packets from this source; defaults to the file path.
+    pub name: Option<String>,
     pub read_path: PathBuf,
+    #[serde(default)]
+    pub speed: ReplaySpeed,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utilities::{TestPath, test_path};
+
+    fn write_config(test_path: &TestPath, filter: &str) -> PathBuf {
+        let path = test_path.path.join("pipeline.toml");
+        let contents = format!(
+            "[ingestor.source]\nhost = \"aprs.glidernet.org\"\nport = 14580\nfilter = \"{filter}\"\n\n[airspace]\ntime_buffer_seconds = 60\n"
+        );
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn given_recording_paths_when_parsed_then_parser_and_snapshot_recorders_are_configured() {
+        let config: PipelineConfig = toml::from_str(
+            r#"
+            [ingestor.source]
+            read_path = "./data/ingestor.pb"
+
+            [parser]
+            write_path = "./data/aircraft.jsonl.zst"
+
+            [airspace]
+            time_buffer_seconds = 60
+            snapshot_path = "./data/airspace.pb"
+            snapshot_interval_seconds = 30
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.parser.write_path,
+            Some(PathBuf::from("./data/aircraft.jsonl.zst"))
+        );
+        assert_eq!(
+            config.airspace.snapshot_path,
+            Some(PathBuf::from("./data/airspace.pb"))
+        );
+        assert_eq!(config.airspace.snapshot_interval_seconds, 30);
+    }
+
+    #[test]
+    fn given_multiple_source_tables_when_parsed_then_all_sources_are_loaded_in_order() {
+        let config: PipelineConfig = toml::from_str(
+            r#"
+            [[ingestor.source]]
+            name = "europe"
+            host = "aprs.glidernet.org"
+            port = 14580
+            filter = "a/72/-25/35/45"
+
+            [[ingestor.source]]
+            host = "aprs.glidernet.org"
+            port = 10152
+            filter = "r/-33/151/500"
+
+            [[ingestor.source]]
+            read_path = "./data/ingestor.pb"
+
+            [airspace]
+            time_buffer_seconds = 60
+            "#,
+        )
+        .unwrap();
+
+        let names: Vec<String> = config
+            .ingestor
+            .sources
+            .iter()
+            .map(IngestorSource::name)
+            .collect();
+        assert_eq!(
+            names,
+            ["europe", "aprs.glidernet.org:10152", "./data/ingestor.pb"]
+        );
+        assert_eq!(config.ingestor.dedup_window_millis, 5000);
+    }
+
+    #[rstest::rstest]
+    #[case("[parser]\ncapacity = 1000\npolicy = \"drop_oldest\"", true)]
+    #[case("[parser]\ncapacity = 0\npolicy = \"drop_oldest\"", false)]
+    #[case("[parsre]\ncapacity = 1000", false)]
+    #[case("[parser]\ncapcity = 1000", false)]
+    fn given_channels_table_when_parsed_then_zero_capacities_and_unknown_names_are_rejected(
+        #[case] channels: &str,
+        #[case] accepted: bool,
+    ) {
+        let config = toml::from_str::<ChannelsConfig>(channels);
+
+        assert_eq!(config.is_ok(), accepted);
+    }
+
+    #[rstest::rstest]
+    #[case("speed = 4", Some(ReplaySpeed::Multiplier(4.0)))]
+    #[case("speed = 0.5", Some(ReplaySpeed::Multiplier(0.5)))]
+    #[case("speed = \"unthrottled\"", Some(ReplaySpeed::Unthrottled))]
+    #[case("", Some(ReplaySpeed::Multiplier(1.0)))]
+    #[case("speed = 0", None)]
+    #[case("speed = \"ludicrous\"", None)]
+    fn given_replay_speed_when_parsed_then_only_positive_multipliers_and_unthrottled_are_accepted(
+        #[case] speed: &str,
+        #[case] expected: Option<ReplaySpeed>,
+    ) {
+        let config = toml::from_str::<FilePathConfig>(&format!(
+            "read_path = \"./data/ingestor.pb\"\n{speed}"
+        ));
+
+        assert_eq!(config.ok().map(|config| config.speed), expected);
+    }
+
+    #[rstest::rstest]
+    fn given_valid_filter_when_config_constructed_then_succeeds(test_path: TestPath) {
+        let path = write_config(&test_path, "r/0/0/20000");
+
+        assert!(PipelineConfig::construct_from_path(&path).is_ok());
+    }
+
+    #[rstest::rstest]
+    fn given_filter_radius_beyond_half_the_globe_when_config_constructed_then_returns_invalid_filter_error(
+        test_path: TestPath,
+    ) {
+        let path = write_config(&test_path, "r/0/0/250000000000");
+
+        let result = PipelineConfig::construct_from_path(&path);
+
+        assert!(matches!(
+            result,
+            Err(errors::PipelineConfigError::InvalidFilter {
+                source: FilterError::InvalidDistance(_),
+                ..
+            })
+        ));
+    }
+
+    #[rstest::rstest]
+    fn given_out_of_range_filter_when_config_constructed_then_returns_invalid_filter_error(
+        test_path: TestPath,
+    ) {
+        let path = write_config(&test_path, "r/95/0/100");
+
+        let result = PipelineConfig::construct_from_path(&path);
+
+        assert!(matches!(
+            result,
+            Err(errors::PipelineConfigError::InvalidFilter {
+                source: FilterError::LatitudeOutOfRange(_),
+                ..
+            })
+        ));
+    }
 }
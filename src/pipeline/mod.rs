@@ -1,4 +1,5 @@
 pub mod config;
 pub mod setup;
+pub mod shutdown;
 
 pub use setup::AirspaceDataPipeline;
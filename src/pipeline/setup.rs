@@ -1,96 +1,399 @@
+use std::num::NonZeroUsize;
+
 use crate::core::airspace::{AirspaceStore, AirspaceViewer};
 use crate::core::central_disk_logger::DiskLoggerRegistry;
 use crate::core::central_disk_logger::errors::DiskloggerRegistryError;
-use crate::core::ingestor::{AprsPacket, Ingestor, PbAprsPacket};
-use crate::core::parser::{Aircraft, AircraftParser};
-use crate::core::thread_manager::{SteppableTask, TaskID, ThreadManager};
+use crate::core::channel::{self, ChannelMonitor, ChannelReceiver, ChannelStats};
+use crate::core::ingestor::{
+    AprsPacket, Ingestor, PacketDeduplicator, PbAprsPacket, ReconnectMonitor, ReplayHandle,
+    session::SessionEvent,
+};
+use crate::core::parser::{AircraftParser, ParserDispatcher, ParserMonitor};
+use crate::core::receivers::{ReceiverStore, ReceiverViewer};
+use crate::core::thread_manager::{
+    Escalation, Supervisor, TaskFactory, TaskPeriod, TaskReport, TaskStatusMonitor, ThreadManager,
+};
 use crate::pipeline::config::{FilePathConfig, IngestorSource, PipelineConfig};
+use crate::pipeline::shutdown::errors::StageGraphError;
+use crate::pipeline::shutdown::{ShutdownReport, Stage, StageGraph};
 
 pub struct AirspaceDataPipeline {
     thread_manager: ThreadManager,
-    end_chain_task_id: TaskID,
+    stages: StageGraph,
+    shutdown_timeout: std::time::Duration,
     renderer_viewer: AirspaceViewer,
+    receiver_viewer: ReceiverViewer,
+    replay_handles: Vec<ReplayHandle>,
+    channel_monitors: Vec<ChannelMonitor>,
+    reconnect_monitors: Vec<ReconnectMonitor>,
+    session_events: crossbeam_channel::Receiver<SessionEvent>,
+    parser_monitor: ParserMonitor,
 }
 impl AirspaceDataPipeline {
-    #[must_use]
-    pub fn new(
-        task_order: Vec<(Box<dyn SteppableTask>, std::time::Duration)>,
-        airspace_store: AirspaceStore,
-        update_tick: std::time::Duration,
-    ) -> Self {
-        let mut thread_manager = ThreadManager::new();
-
-        for (task, duration) in task_order {
-            thread_manager.add_task(task, duration);
-        }
-        let renderer_viewer = airspace_store.get_airspace_viewer();
-        let end_chain_task_id = thread_manager.add_task(airspace_store, update_tick);
-        Self {
-            thread_manager,
-            end_chain_task_id,
-            renderer_viewer,
-        }
-    }
-
     pub fn setup_pipeline(
         pipeline_config: PipelineConfig,
     ) -> Result<Self, AircraftDataPipelineError> {
-        let mut disk_logger_registry = DiskLoggerRegistry::new();
+        let channels = &pipeline_config.channels;
+        let mut disk_logger_registry = DiskLoggerRegistry::with_channel(channels.disk_logger);
 
-        let (ingestor_sender, ingestor_receiver): (
-            crossbeam_channel::Sender<AprsPacket>,
-            crossbeam_channel::Receiver<AprsPacket>,
-        ) = crossbeam_channel::unbounded();
+        let (ingestor_sender, ingestor_receiver) = channel::channel("ingestor", channels.ingestor);
+        let mut channel_monitors = vec![ingestor_sender.monitor()];
 
+        let compression = pipeline_config.ingestor.compression;
+        let rotation = pipeline_config.ingestor.rotation;
         let ingestor_logger_handle = pipeline_config
             .ingestor
             .write_path
-            .map(|path| disk_logger_registry.register_proto::<PbAprsPacket>(path))
+            .map(|path| {
+                disk_logger_registry.register_proto_with_rotation::<PbAprsPacket>(
+                    compression.apply_to(path),
+                    rotation,
+                )
+            })
             .transpose()?;
 
-        let ingestor = match pipeline_config.ingestor.source {
-            IngestorSource::FilePath(FilePathConfig { read_path }) => {
-                Ingestor::read_data_from_file(&read_path, ingestor_sender, ingestor_logger_handle)
+        let mut thread_manager = ThreadManager::new();
+        let mut replay_handles = Vec::new();
+        let mut ingestor_tasks = Vec::new();
+        let mut reconnect_monitors = Vec::new();
+        let (session_event_sender, session_events) = crossbeam_channel::unbounded();
+        let source_count = pipeline_config.ingestor.sources.len();
+
+        for source in &pipeline_config.ingestor.sources {
+            let sender = ingestor_sender.clone();
+            let logger_handle = ingestor_logger_handle.clone();
+            let reconnect_monitor = ReconnectMonitor::new(&source.name());
+            let ingestor = match source {
+                IngestorSource::FilePath(FilePathConfig {
+                    read_path, speed, ..
+                }) => Ingestor::read_data_from_file(read_path, *speed, sender, logger_handle).map(
+                    |(ingestor, handle)| {
+                        replay_handles.push(handle);
+                        ingestor
+                    },
+                ),
+                IngestorSource::GliderNet(config) => {
+                    reconnect_monitors.push(reconnect_monitor.clone());
+                    Ingestor::connect_glidernet(
+                        config,
+                        sender,
+                        logger_handle,
+                        reconnect_monitor.clone(),
+                        session_event_sender.clone(),
+                    )
+                }
             }
-            IngestorSource::GliderNet(config) => {
-                Ingestor::connect_glidernet(&config, ingestor_sender, ingestor_logger_handle)
+            .map_err(|err| AircraftDataPipelineError::PipelineComponentSetup {
+                struct_name: std::any::type_name::<Ingestor>(),
+                source: err,
+            })?
+            .with_name(&source.name());
+
+            if let IngestorSource::GliderNet(config) = source {
+                // A live source that is not restarted leaves the pipeline without its
+                // input, so the pipeline stops with it.
+                let supervisor = Supervisor {
+                    escalate: true,
+                    ..config.supervision
+                };
+                let config = config.clone();
+                let sender = ingestor_sender.clone();
+                let logger_handle = ingestor_logger_handle.clone();
+                let name = source.name();
+                let session_event_sender = session_event_sender.clone();
+                let factory: TaskFactory<Ingestor> = Box::new(move || {
+                    Ok(Ingestor::connect_glidernet(
+                        &config,
+                        sender.clone(),
+                        logger_handle.clone(),
+                        reconnect_monitor.clone(),
+                        session_event_sender.clone(),
+                    )?
+                    .with_name(&name))
+                });
+                ingestor_tasks.push(thread_manager.add_supervised_task(
+                    ingestor,
+                    factory,
+                    std::time::Duration::ZERO,
+                    supervisor,
+                ));
+            } else {
+                ingestor_tasks.push(thread_manager.add_task(ingestor, std::time::Duration::ZERO));
             }
         }
-        .map_err(|err| AircraftDataPipelineError::PipelineComponentSetup {
-            struct_name: std::any::type_name::<Ingestor>(),
-            source: err,
-        })?;
+        // Only the ingestors hold senders now, so the channel disconnects once they all finish.
+        let ingestor_monitor = ingestor_sender.monitor();
+        drop(ingestor_sender);
+        let mut stages = vec![Stage::new("ingestor").with_tasks(ingestor_tasks)];
+        // The stages below hold channel ends that cannot be handed to a fresh instance, so
+        // rather than being restarted, their failures escalate and stop the pipeline.
+
+        let (parser_input, parser_upstream) = if source_count > 1 {
+            let (deduplicator_sender, deduplicator_receiver) =
+                channel::channel("deduplicator", channels.deduplicator);
+            channel_monitors.push(deduplicator_sender.monitor());
+            let deduplicator = PacketDeduplicator::new(
+                ingestor_receiver,
+                deduplicator_sender,
+                std::time::Duration::from_millis(pipeline_config.ingestor.dedup_window_millis),
+            );
+            stages.push(
+                Stage::new("deduplicator")
+                    .with_tasks([
+                        thread_manager.add_escalating_task(deduplicator, std::time::Duration::ZERO)
+                    ])
+                    .with_input(ingestor_monitor)
+                    .with_upstream(&["ingestor"]),
+            );
+            (deduplicator_receiver, "deduplicator")
+        } else {
+            (ingestor_receiver, "ingestor")
+        };
+        let parser_input_monitor = parser_input.monitor();
 
-        let (parser_sender, parser_receiver): (
-            crossbeam_channel::Sender<Aircraft>,
-            crossbeam_channel::Receiver<Aircraft>,
-        ) = crossbeam_channel::unbounded();
+        let (parser_sender, parser_receiver) = channel::channel("parser", channels.parser);
+        let parser_output_monitor = parser_sender.monitor();
+        channel_monitors.push(parser_output_monitor.clone());
 
-        let parser = AircraftParser::new(ingestor_receiver, parser_sender);
+        let (receiver_beacon_sender, receiver_beacon_receiver) =
+            channel::channel("receivers", channels.receivers);
+        let receiver_beacon_monitor = receiver_beacon_sender.monitor();
+        channel_monitors.push(receiver_beacon_monitor.clone());
 
-        let airspace_store = AirspaceStore::new(
+        let parser_config = &pipeline_config.parser;
+        let parser_monitor = parser_config
+            .failure_samples
+            .map_or_else(ParserMonitor::default, ParserMonitor::with_sample_capacity);
+        let quarantine = parser_config
+            .quarantine_path
+            .clone()
+            .map(|path| {
+                disk_logger_registry
+                    .register_jsonl_with_rotation(path, parser_config.quarantine_rotation)
+            })
+            .transpose()?;
+        let aircraft_logger = parser_config
+            .write_path
+            .clone()
+            .map(|path| disk_logger_registry.register_any_format(path, parser_config.rotation))
+            .transpose()?;
+        let build_parser = |input: ChannelReceiver<AprsPacket>| {
+            let mut parser = AircraftParser::new(input, parser_sender.clone())
+                .with_receiver_sender(receiver_beacon_sender.clone())
+                .with_monitor(parser_monitor.clone());
+            if let Some(max_clock_skew_seconds) = parser_config.max_clock_skew_seconds {
+                parser = parser
+                    .with_max_clock_skew(chrono::TimeDelta::seconds(max_clock_skew_seconds.into()));
+            }
+            if let Some(quarantine) = &quarantine {
+                parser = parser.with_quarantine(quarantine.clone());
+            }
+            if let Some(aircraft_logger) = &aircraft_logger {
+                parser = parser.with_logger(aircraft_logger.clone());
+            }
+            for kind in &parser_config.decoders {
+                parser = parser.with_decoder(kind.decoder());
+            }
+            parser
+        };
+        let workers = parser_config.workers.map_or(1, NonZeroUsize::get);
+        let parser_stage = if workers > 1 {
+            let (shard_senders, shard_receivers): (Vec<_>, Vec<_>) = (0..workers)
+                .map(|worker| {
+                    channel::channel(format!("parser_shard_{worker}"), channels.parser_shards)
+                })
+                .unzip();
+            stages.push(
+                Stage::new("parser_dispatcher")
+                    .with_tasks([thread_manager.add_escalating_task(
+                        ParserDispatcher::new(parser_input, shard_senders),
+                        std::time::Duration::ZERO,
+                    )])
+                    .with_input(parser_input_monitor)
+                    .with_upstream(&[parser_upstream]),
+            );
+            let mut parser_stage = Stage::new("parser").with_upstream(&["parser_dispatcher"]);
+            for shard_receiver in shard_receivers {
+                let shard_monitor = shard_receiver.monitor();
+                channel_monitors.push(shard_monitor.clone());
+                parser_stage = parser_stage
+                    .with_input(shard_monitor)
+                    .with_tasks([thread_manager.add_escalating_task(
+                        build_parser(shard_receiver),
+                        std::time::Duration::ZERO,
+                    )]);
+            }
+            parser_stage
+        } else {
+            Stage::new("parser")
+                .with_tasks([thread_manager
+                    .add_escalating_task(build_parser(parser_input), std::time::Duration::ZERO)])
+                .with_input(parser_input_monitor)
+                .with_upstream(&[parser_upstream])
+        };
+        // Only the parsers hold senders now, so the channels disconnect once they all finish.
+        drop(parser_sender);
+        drop(receiver_beacon_sender);
+
+        let mut airspace_store = AirspaceStore::new(
             parser_receiver,
             chrono::TimeDelta::seconds(pipeline_config.airspace.time_buffer_seconds.into()),
         );
-        let task_order: Vec<(Box<dyn SteppableTask>, std::time::Duration)> = vec![
-            (Box::new(ingestor), std::time::Duration::ZERO),
-            (Box::new(parser), std::time::Duration::ZERO),
-        ];
-        Ok(Self::new(
-            task_order,
-            airspace_store,
-            std::time::Duration::from_micros(16667),
-        ))
+        if let Some(batch_budget) = pipeline_config.airspace.batch_budget {
+            airspace_store = airspace_store.with_batch_budget(batch_budget);
+        }
+        if let Some(path) = pipeline_config.airspace.snapshot_path {
+            airspace_store = airspace_store.with_snapshot_logger(
+                disk_logger_registry
+                    .register_any_format(path, pipeline_config.airspace.snapshot_rotation)?,
+                chrono::TimeDelta::seconds(
+                    pipeline_config.airspace.snapshot_interval_seconds.into(),
+                ),
+            );
+        }
+        stages.push(parser_stage);
+        let renderer_viewer = airspace_store.get_airspace_viewer();
+        stages.push(
+            Stage::new("airspace_store")
+                .with_tasks([thread_manager.add_escalating_task(
+                    airspace_store,
+                    TaskPeriod::new(std::time::Duration::from_micros(16667))
+                        .with_catch_up(pipeline_config.airspace.catch_up),
+                )])
+                .with_input(parser_output_monitor)
+                .with_upstream(&["parser"]),
+        );
+        let receiver_store = ReceiverStore::new(receiver_beacon_receiver);
+        let receiver_viewer = receiver_store.get_receiver_viewer();
+        stages.push(
+            Stage::new("receiver_store")
+                .with_tasks([
+                    thread_manager.add_escalating_task(receiver_store, std::time::Duration::ZERO)
+                ])
+                .with_input(receiver_beacon_monitor)
+                .with_upstream(&["parser"]),
+        );
+        if !disk_logger_registry.is_empty() {
+            let disk_logger_monitor = disk_logger_registry.channel_monitor();
+            channel_monitors.push(disk_logger_monitor.clone());
+            // Stopped last, as every stage recording to disk holds a handle to it. It writes
+            // what is still queued and syncs its files as it stops.
+            stages.push(
+                Stage::new("disk_logger")
+                    .with_tasks([thread_manager.add_escalating_task(
+                        disk_logger_registry.build(),
+                        std::time::Duration::ZERO,
+                    )])
+                    .with_input(disk_logger_monitor)
+                    .with_upstream(&["ingestor", "parser", "airspace_store"]),
+            );
+        }
+        Ok(Self {
+            thread_manager,
+            stages: StageGraph::new(stages)?,
+            shutdown_timeout: pipeline_config.shutdown.timeout(),
+            renderer_viewer,
+            receiver_viewer,
+            replay_handles,
+            channel_monitors,
+            reconnect_monitors,
+            session_events,
+            parser_monitor,
+        })
     }
     #[must_use]
     pub fn get_airspace_viewer(&self) -> AirspaceViewer {
         self.renderer_viewer.clone()
     }
 
-    pub fn shutdown(&mut self) {
-        self.thread_manager.stop_all_tasks();
-        self.thread_manager
-            .wait_on_task_finish(self.end_chain_task_id);
+    /// Location and health of every ground station heard.
+    #[must_use]
+    pub fn get_receiver_viewer(&self) -> ReceiverViewer {
+        self.receiver_viewer.clone()
+    }
+
+    /// Handles controlling each file source, in the order the sources are configured.
+    #[must_use]
+    pub fn replay_handles(&self) -> &[ReplayHandle] {
+        &self.replay_handles
+    }
+
+    /// Receives an [`Escalation`] when a supervised task fails for good; the pipeline
+    /// should then be shut down.
+    #[must_use]
+    pub fn escalations(&self) -> crossbeam_channel::Receiver<Escalation> {
+        self.thread_manager.escalations()
+    }
+
+    /// Receives the server comments of every live source: banners, login responses and
+    /// keepalives. Nothing else drains it, so it should be consumed while the pipeline runs.
+    #[must_use]
+    pub fn session_events(&self) -> crossbeam_channel::Receiver<SessionEvent> {
+        self.session_events.clone()
+    }
+
+    /// A handle listing every task with its status, usable from the GUI or a health check.
+    #[must_use]
+    pub fn task_status_monitor(&self) -> TaskStatusMonitor {
+        self.thread_manager.status_monitor()
+    }
+
+    #[must_use]
+    pub fn task_reports(&self) -> Vec<TaskReport> {
+        self.thread_manager.task_reports()
+    }
+
+    /// Monitors of every channel between stages, in pipeline order.
+    #[must_use]
+    pub fn channel_monitors(&self) -> &[ChannelMonitor] {
+        &self.channel_monitors
+    }
+
+    /// Monitors of the reconnects of every live source, in the order they are configured.
+    #[must_use]
+    pub fn reconnect_monitors(&self) -> &[ReconnectMonitor] {
+        &self.reconnect_monitors
+    }
+
+    #[must_use]
+    pub fn channel_stats(&self) -> Vec<ChannelStats> {
+        self.channel_monitors
+            .iter()
+            .map(ChannelMonitor::stats)
+            .collect()
+    }
+
+    /// Shuts the stages down from the ingestors to the disk logger, letting each drain
+    /// its input, within the configured [`ShutdownConfig::timeout_seconds`].
+    pub fn shutdown(&mut self) -> ShutdownReport {
+        self.shutdown_until(std::time::Instant::now() + self.shutdown_timeout)
+    }
+
+    /// Like [`Self::shutdown`], giving up on stages still running at `deadline`.
+    pub fn shutdown_until(&mut self, deadline: std::time::Instant) -> ShutdownReport {
+        let report = self.stages.shutdown(&mut self.thread_manager, deadline);
+        if report.is_clean() {
+            log::info!("Pipeline {report}");
+        } else {
+            log::warn!("Pipeline {report}");
+        }
+        for stats in self.channel_stats() {
+            log::info!("Channel {stats}");
+        }
+        log::info!("Parser: {}", self.parser_monitor.stats());
+        report
+    }
+
+    /// Counts of beacons parsed and rejected by the parser.
+    #[must_use]
+    pub fn parser_monitor(&self) -> ParserMonitor {
+        self.parser_monitor.clone()
+    }
+
+    /// Names of the stages in the order [`Self::shutdown`] stops them.
+    #[must_use]
+    pub fn stages(&self) -> Vec<&'static str> {
+        self.stages.shutdown_order()
     }
 }
 #[derive(Debug, thiserror::Error)]
@@ -105,14 +408,25 @@ pub enum AircraftDataPipelineError {
     },
     #[error("Failed to register to disk_logger : {0}")]
     CentralDiskLogger(#[from] DiskloggerRegistryError),
+    #[error("Invalid pipeline stages: {0}")]
+    StageGraph(#[from] StageGraphError),
 }
 
 #[cfg(test)]
 mod test {
+    use prost::Message;
+
     use super::*;
+    use crate::core::central_disk_logger::compression::Compression;
+    use crate::core::central_disk_logger::rotation::RotationPolicy;
     use crate::core::ingestor::PbAprsPacket;
+    use crate::core::ingestor::config::ReplaySpeed;
+    use crate::core::parser::Aircraft;
+    use crate::core::thread_manager::{CatchUpPolicy, TaskStatus};
     use crate::pipeline::AirspaceDataPipeline;
-    use crate::pipeline::config::{AirspaceConfig, IngestorConfig};
+    use crate::pipeline::config::{
+        AirspaceConfig, ChannelsConfig, IngestorConfig, ParserConfig, ShutdownConfig,
+    };
     use crate::test_utilities::{TestPath, test_path, write_pb_message_to_disk};
 
     #[rstest::rstest]
@@ -131,17 +445,309 @@ mod test {
         let mut writer = std::io::BufWriter::new(std::fs::File::create(&read_path).unwrap());
         let _ = write_pb_message_to_disk(&mut writer, &packet);
         let ingestor_config = IngestorConfig {
-            source: IngestorSource::FilePath(FilePathConfig { read_path }),
+            sources: vec![IngestorSource::FilePath(FilePathConfig {
+                name: None,
+                read_path,
+                speed: ReplaySpeed::default(),
+            })],
             write_path: None,
+            compression: Compression::None,
+            rotation: RotationPolicy::default(),
+            dedup_window_millis: 5000,
         };
         let airspace_config = AirspaceConfig {
             time_buffer_seconds: 1,
+            batch_budget: None,
+            catch_up: CatchUpPolicy::default(),
+            snapshot_path: None,
+            snapshot_interval_seconds: 10,
+            snapshot_rotation: RotationPolicy::default(),
         };
         let pipeline_config = PipelineConfig {
             ingestor: ingestor_config,
+            parser: ParserConfig::default(),
             airspace: airspace_config,
+            channels: ChannelsConfig::default(),
+            shutdown: ShutdownConfig::default(),
         };
         let pipeline = AirspaceDataPipeline::setup_pipeline(pipeline_config);
         drop(pipeline);
     }
+
+    #[rstest::rstest]
+    #[test_log::test]
+    fn given_pipeline_with_write_path_when_replay_finishes_and_pipeline_shuts_down_then_recording_holds_packet(
+        test_path: TestPath,
+    ) {
+        let packet = PbAprsPacket {
+            timestamp: Some(prost_types::Timestamp::from(std::time::SystemTime::now())),
+            message:
+                "FLRDDA5BA>OGFLR,qAS,LFNW:/163148h4559.85N/00348.19E'/342/A=001345 !W06! id0ADDA5BA"
+                    .into(),
+        };
+        let read_path = test_path.path.join("replayed.pb");
+        let mut writer = std::io::BufWriter::new(std::fs::File::create(&read_path).unwrap());
+        write_pb_message_to_disk(&mut writer, &packet).unwrap();
+        drop(writer);
+        let write_path = test_path.path.join("recorded.pb");
+        let pipeline_config = PipelineConfig {
+            ingestor: IngestorConfig {
+                sources: vec![IngestorSource::FilePath(FilePathConfig {
+                    name: None,
+                    read_path: read_path.clone(),
+                    speed: ReplaySpeed::Unthrottled,
+                })],
+                write_path: Some(write_path.clone()),
+                compression: Compression::None,
+                rotation: RotationPolicy::default(),
+                dedup_window_millis: 5000,
+            },
+            parser: ParserConfig::default(),
+            airspace: AirspaceConfig {
+                time_buffer_seconds: 1,
+                batch_budget: None,
+                catch_up: CatchUpPolicy::default(),
+                snapshot_path: None,
+                snapshot_interval_seconds: 10,
+                snapshot_rotation: RotationPolicy::default(),
+            },
+            channels: ChannelsConfig::default(),
+            shutdown: ShutdownConfig::default(),
+        };
+
+        let mut pipeline = AirspaceDataPipeline::setup_pipeline(pipeline_config).unwrap();
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while std::fs::metadata(&write_path).unwrap().len() == 0
+            && std::time::Instant::now() < deadline
+        {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        let report = pipeline.shutdown();
+
+        assert!(report.is_clean(), "{report}");
+        let stages: Vec<_> = report.stages.iter().map(|stage| stage.name).collect();
+        assert_eq!(
+            stages,
+            [
+                "ingestor",
+                "parser",
+                "airspace_store",
+                "receiver_store",
+                "disk_logger"
+            ]
+        );
+        assert_eq!(
+            std::fs::read(&write_path).unwrap(),
+            std::fs::read(&read_path).unwrap()
+        );
+        let stats = pipeline.channel_stats();
+        let names: Vec<_> = stats.iter().map(|stats| stats.name.as_ref()).collect();
+        assert_eq!(names, ["ingestor", "parser", "receivers", "disk_logger"]);
+        assert_eq!((stats[0].sent, stats[0].dropped), (1, 0));
+        let reports = pipeline.task_reports();
+        let task_names: Vec<_> = reports.iter().map(|report| report.name.as_str()).collect();
+        let ingestor_name = format!("Ingestor({})", read_path.display());
+        assert_eq!(
+            task_names,
+            [
+                ingestor_name.as_str(),
+                "AircraftParser",
+                "AirspaceStore",
+                "ReceiverStore",
+                "CentralDiskLogger"
+            ]
+        );
+        assert!(reports.iter().all(|report| report.last_error.is_none()));
+    }
+
+    #[rstest::rstest]
+    #[test_log::test]
+    fn given_pipeline_with_aircraft_and_snapshot_paths_when_packet_replayed_then_both_are_recorded(
+        test_path: TestPath,
+        #[values(None, NonZeroUsize::new(3))] workers: Option<NonZeroUsize>,
+    ) {
+        let packet = PbAprsPacket {
+            // Received at 2024-06-01T19:06:00Z, just after the beacon's 19:05:58.
+            timestamp: Some(prost_types::Timestamp {
+                seconds: 1_717_268_760,
+                nanos: 0,
+            }),
+            message: "ICA020113>OGADSB,qAS,AVX1081:/190558h5050.73N/00413.19E^222/262/A=007246 !W06! id25020113 +2880fpm FL079.69 A3:RAM831F Sq7122".into(),
+        };
+        let read_path = test_path.path.join("replayed.pb");
+        let mut writer = std::io::BufWriter::new(std::fs::File::create(&read_path).unwrap());
+        write_pb_message_to_disk(&mut writer, &packet).unwrap();
+        drop(writer);
+        let aircraft_path = test_path.path.join("aircraft.jsonl");
+        let snapshot_path = test_path.path.join("snapshots.pb");
+        let pipeline_config = PipelineConfig {
+            ingestor: IngestorConfig {
+                sources: vec![IngestorSource::FilePath(FilePathConfig {
+                    name: None,
+                    read_path,
+                    speed: ReplaySpeed::Unthrottled,
+                })],
+                write_path: None,
+                compression: Compression::None,
+                rotation: RotationPolicy::default(),
+                dedup_window_millis: 5000,
+            },
+            parser: ParserConfig {
+                write_path: Some(aircraft_path.clone()),
+                rotation: RotationPolicy::default(),
+                max_clock_skew_seconds: None,
+                quarantine_path: None,
+                quarantine_rotation: RotationPolicy::default(),
+                failure_samples: None,
+                workers,
+                decoders: Vec::new(),
+            },
+            airspace: AirspaceConfig {
+                time_buffer_seconds: 1,
+                batch_budget: None,
+                catch_up: CatchUpPolicy::default(),
+                snapshot_path: Some(snapshot_path.clone()),
+                snapshot_interval_seconds: 10,
+                snapshot_rotation: RotationPolicy::default(),
+            },
+            channels: ChannelsConfig::default(),
+            shutdown: ShutdownConfig::default(),
+        };
+
+        let mut pipeline = AirspaceDataPipeline::setup_pipeline(pipeline_config).unwrap();
+        // Both recordings are flushed periodically while the airspace store keeps running.
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while [&aircraft_path, &snapshot_path]
+            .iter()
+            .any(|path| std::fs::metadata(path).unwrap().len() == 0)
+            && std::time::Instant::now() < deadline
+        {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        let report = pipeline.shutdown();
+
+        let has_dispatcher = report
+            .stages
+            .iter()
+            .any(|stage| stage.name == "parser_dispatcher");
+        assert_eq!(has_dispatcher, workers.is_some(), "{report}");
+        let shard_channels: Vec<_> = pipeline
+            .channel_stats()
+            .into_iter()
+            .map(|stats| stats.name)
+            .filter(|name| name.starts_with("parser_shard"))
+            .collect();
+        let expected_shard_channels: Vec<_> = (0..workers.map_or(0, NonZeroUsize::get))
+            .map(|worker| format!("parser_shard_{worker}"))
+            .collect();
+        assert_eq!(shard_channels, expected_shard_channels);
+        let aircraft: Aircraft =
+            serde_json::from_str(std::fs::read_to_string(&aircraft_path).unwrap().trim()).unwrap();
+        assert_eq!(
+            aircraft.icao_address.map(|address| address.value()),
+            Some(0x0002_0113)
+        );
+        let snapshot = crate::core::airspace::PbAirspaceSnapshot::decode_length_delimited(
+            std::fs::read(&snapshot_path).unwrap().as_slice(),
+        )
+        .unwrap();
+        assert_eq!(snapshot.aircraft, [aircraft.into()]);
+    }
+
+    /// A pipeline reading from a live source named `local` on `port` that reconnects only once.
+    fn live_source_config(port: u16) -> PipelineConfig {
+        toml::from_str(&format!(
+            "[ingestor.source]\n\
+             name = \"local\"\n\
+             host = \"127.0.0.1\"\n\
+             port = {port}\n\
+             filter = \"r/0/0/100\"\n\
+             [ingestor.source.reconnect]\n\
+             initial_backoff_millis = 1\n\
+             max_backoff_seconds = 0\n\
+             max_attempts = 1\n\
+             [airspace]\n\
+             time_buffer_seconds = 60\n"
+        ))
+        .unwrap()
+    }
+
+    #[test_log::test]
+    fn given_live_source_when_server_hangs_up_and_reconnect_succeeds_then_pipeline_reports_the_reconnect()
+     {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let pipeline_config = live_source_config(listener.local_addr().unwrap().port());
+
+        let mut pipeline = AirspaceDataPipeline::setup_pipeline(pipeline_config).unwrap();
+        let reconnect_monitor = pipeline.reconnect_monitors()[0].clone();
+        drop(listener.accept().unwrap());
+        let (_reconnected, _) = listener.accept().unwrap();
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while reconnect_monitor.reconnects() == 0 && std::time::Instant::now() < deadline {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        pipeline.shutdown();
+
+        assert_eq!(reconnect_monitor.source(), "local");
+        assert_eq!(reconnect_monitor.reconnects(), 1);
+    }
+
+    #[test_log::test]
+    fn given_live_source_when_server_logs_it_in_then_session_events_are_received_from_pipeline() {
+        use std::io::Write;
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let pipeline_config = live_source_config(listener.local_addr().unwrap().port());
+
+        let mut pipeline = AirspaceDataPipeline::setup_pipeline(pipeline_config).unwrap();
+        let session_events = pipeline.session_events();
+        let (mut server, _) = listener.accept().unwrap();
+        server
+            .write_all(
+                b"# aprsc 2.1.4-g408ed49\r\n# logresp N0CALL unverified, server GLIDERN2\r\n",
+            )
+            .unwrap();
+        let timeout = std::time::Duration::from_secs(5);
+        let banner = session_events.recv_timeout(timeout).unwrap();
+        let login = session_events.recv_timeout(timeout).unwrap();
+        // Hanging up lets the source stop instead of waiting on the server.
+        drop(server);
+        drop(listener);
+        pipeline.shutdown();
+
+        assert!(
+            matches!(banner, SessionEvent::ServerBanner(_)),
+            "{banner:?}"
+        );
+        assert!(
+            matches!(&login, SessionEvent::LoginResponse(response) if response.server.as_deref() == Some("GLIDERN2")),
+            "{login:?}"
+        );
+    }
+
+    #[test_log::test]
+    fn given_live_source_when_retry_budget_is_exhausted_then_escalation_is_received_and_pipeline_shuts_down()
+     {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let pipeline_config = live_source_config(listener.local_addr().unwrap().port());
+
+        let mut pipeline = AirspaceDataPipeline::setup_pipeline(pipeline_config).unwrap();
+        let escalations = pipeline.escalations();
+        // The server hangs up and goes away, so the one reconnect attempt is refused.
+        drop(listener.accept().unwrap());
+        drop(listener);
+        let escalation = escalations
+            .recv_timeout(std::time::Duration::from_secs(10))
+            .expect("exhausting the retry budget escalates");
+        let report = pipeline.shutdown();
+
+        assert_eq!(escalation.task_name, "Ingestor(local)");
+        assert!(
+            escalation.reason.contains("1 failed reconnect attempts"),
+            "{escalation}"
+        );
+        assert!(report.stages.iter().all(|stage| stage.stopped), "{report}");
+        let ingestor = pipeline.task_reports().remove(0);
+        assert_eq!(ingestor.status, TaskStatus::Errored);
+    }
 }
@@ -0,0 +1,376 @@
+use std::time::{Duration, Instant};
+
+use crate::core::channel::ChannelMonitor;
+use crate::core::thread_manager::{TaskID, ThreadManager};
+
+/// How often a stage's input queue is checked while it drains.
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(2);
+
+/// One stage of the pipeline: the tasks running it, the channels it consumes and the stages
+/// feeding those channels.
+#[derive(Debug, Clone)]
+pub struct Stage {
+    name: &'static str,
+    tasks: Vec<TaskID>,
+    inputs: Vec<ChannelMonitor>,
+    upstream: Vec<&'static str>,
+}
+impl Stage {
+    /// A stage without input is a source, stopped first on shutdown.
+    #[must_use]
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            tasks: Vec::new(),
+            inputs: Vec::new(),
+            upstream: Vec::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_tasks(mut self, tasks: impl IntoIterator<Item = TaskID>) -> Self {
+        self.tasks.extend(tasks);
+        self
+    }
+
+    /// Adds a channel the stage consumes, e.g. one per worker.
+    #[must_use]
+    pub fn with_input(mut self, input: ChannelMonitor) -> Self {
+        self.inputs.push(input);
+        self
+    }
+
+    /// Stages that must be stopped before this one.
+    #[must_use]
+    pub fn with_upstream(mut self, upstream: &[&'static str]) -> Self {
+        self.upstream.extend_from_slice(upstream);
+        self
+    }
+}
+
+/// The stages of a pipeline and how data flows between them, used to shut it down
+/// from the sources to the sinks.
+#[derive(Debug, Clone)]
+pub struct StageGraph {
+    /// In topological order, each stage after all of its upstream stages.
+    stages: Vec<Stage>,
+}
+impl StageGraph {
+    /// Orders `stages`, which may be listed in any order. Stages with no ordering between
+    /// them keep the order they were listed in.
+    pub fn new(stages: Vec<Stage>) -> Result<Self, errors::StageGraphError> {
+        for stage in &stages {
+            if let Some(upstream) = stage
+                .upstream
+                .iter()
+                .find(|upstream| !stages.iter().any(|other| other.name == **upstream))
+            {
+                return Err(errors::StageGraphError::UnknownUpstream {
+                    stage: stage.name,
+                    upstream,
+                });
+            }
+        }
+        let mut pending = stages;
+        let mut ordered: Vec<Stage> = Vec::with_capacity(pending.len());
+        while !pending.is_empty() {
+            let Some(next) = pending.iter().position(|stage| {
+                stage
+                    .upstream
+                    .iter()
+                    .all(|upstream| ordered.iter().any(|done| done.name == *upstream))
+            }) else {
+                return Err(errors::StageGraphError::Cycle(
+                    pending.iter().map(|stage| stage.name).collect(),
+                ));
+            };
+            ordered.push(pending.remove(next));
+        }
+        Ok(Self { stages: ordered })
+    }
+
+    /// Names of the stages in the order they are shut down.
+    #[must_use]
+    pub fn shutdown_order(&self) -> Vec<&'static str> {
+        self.stages.iter().map(|stage| stage.name).collect()
+    }
+
+    /// Stops the stages in topological order. Sources are stopped straight away; every
+    /// other stage first consumes what is left in its input channels, since nothing feeds
+    /// it any more, then is stopped. Waiting ends for every stage at `deadline`, and the
+    /// messages still queued then are reported lost.
+    pub fn shutdown(
+        &self,
+        thread_manager: &mut ThreadManager,
+        deadline: Instant,
+    ) -> ShutdownReport {
+        let started = Instant::now();
+        let stages = self
+            .stages
+            .iter()
+            .map(|stage| stage.shut_down(thread_manager, deadline))
+            .collect();
+        ShutdownReport {
+            stages,
+            elapsed: started.elapsed(),
+        }
+    }
+}
+
+impl Stage {
+    fn shut_down(&self, thread_manager: &mut ThreadManager, deadline: Instant) -> StageShutdown {
+        let queued = || {
+            self.inputs
+                .iter()
+                .map(|input| input.stats().queued)
+                .sum::<usize>()
+        };
+        while queued() > 0
+            && Instant::now() < deadline
+            && !self
+                .tasks
+                .iter()
+                .all(|task_id| thread_manager.is_task_finished(*task_id))
+        {
+            std::thread::sleep(DRAIN_POLL_INTERVAL);
+        }
+        let drained = queued() == 0;
+        for task_id in &self.tasks {
+            // Fails only for a task already waited on.
+            let _ = thread_manager.stop_task(*task_id);
+        }
+        let mut stopped = true;
+        for task_id in &self.tasks {
+            stopped &= thread_manager.wait_on_task_finish_until(*task_id, deadline);
+        }
+        StageShutdown {
+            name: self.name,
+            drained,
+            stopped,
+            lost: queued(),
+        }
+    }
+}
+
+/// How each stage went down, in the order they were stopped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShutdownReport {
+    pub stages: Vec<StageShutdown>,
+    pub elapsed: Duration,
+}
+impl ShutdownReport {
+    /// Every stage drained its input and stopped before the deadline.
+    #[must_use]
+    pub fn is_clean(&self) -> bool {
+        self.stages.iter().all(StageShutdown::is_clean)
+    }
+
+    /// In-flight messages left in the channels when their consumers stopped.
+    #[must_use]
+    pub fn lost(&self) -> usize {
+        self.stages.iter().map(|stage| stage.lost).sum()
+    }
+}
+impl std::fmt::Display for ShutdownReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "shutdown {} in {:?}, {} messages lost",
+            if self.is_clean() { "clean" } else { "unclean" },
+            self.elapsed,
+            self.lost()
+        )?;
+        for stage in &self.stages {
+            write!(f, "; {stage}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StageShutdown {
+    pub name: &'static str,
+    /// The input channels were empty when the stage was stopped.
+    pub drained: bool,
+    /// Every task of the stage finished before the deadline.
+    pub stopped: bool,
+    /// Messages left in the input channels once the stage stopped, or at the deadline.
+    pub lost: usize,
+}
+impl StageShutdown {
+    #[must_use]
+    pub fn is_clean(&self) -> bool {
+        self.drained && self.stopped && self.lost == 0
+    }
+}
+impl std::fmt::Display for StageShutdown {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {}, {}, {} lost",
+            self.name,
+            if self.drained {
+                "drained"
+            } else {
+                "not drained"
+            },
+            if self.stopped {
+                "stopped"
+            } else {
+                "still running"
+            },
+            self.lost
+        )
+    }
+}
+
+pub mod errors {
+    #[derive(Debug, thiserror::Error)]
+    pub enum StageGraphError {
+        #[error("Stage {stage} lists unknown upstream stage {upstream}")]
+        UnknownUpstream {
+            stage: &'static str,
+            upstream: &'static str,
+        },
+        #[error("Stages {0:?} depend on each other")]
+        Cycle(Vec<&'static str>),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::channel::{
+        self, ChannelConfig, ChannelReceiver, ChannelSender, OverflowPolicy,
+    };
+    use crate::core::thread_manager::{SteppableTask, TaskState, Wakeup};
+
+    /// Sends increasing numbers until stopped.
+    struct Source {
+        sender: ChannelSender<u32>,
+        next: u32,
+    }
+
+    impl SteppableTask for Source {
+        fn step(&mut self) -> TaskState {
+            self.next += 1;
+            match self.sender.send(self.next) {
+                Ok(()) => TaskState::Running,
+                Err(_) => TaskState::Completed,
+            }
+        }
+    }
+
+    /// Consumes one message per step, taking `per_message` for each.
+    struct Sink {
+        receiver: ChannelReceiver<u32>,
+        per_message: Duration,
+    }
+
+    impl SteppableTask for Sink {
+        fn step(&mut self) -> TaskState {
+            match self.receiver.try_recv() {
+                Ok(_) => {
+                    std::thread::sleep(self.per_message);
+                    TaskState::Running
+                }
+                Err(crossbeam_channel::TryRecvError::Empty) => TaskState::Running,
+                Err(crossbeam_channel::TryRecvError::Disconnected) => TaskState::Completed,
+            }
+        }
+
+        fn wakeup(&self) -> Option<Wakeup<'_>> {
+            Some(Wakeup::on(&self.receiver))
+        }
+    }
+
+    fn source_and_sink(thread_manager: &mut ThreadManager, per_message: Duration) -> StageGraph {
+        let config = ChannelConfig {
+            capacity: std::num::NonZeroUsize::new(100),
+            policy: OverflowPolicy::Block,
+        };
+        let (sender, receiver) = channel::channel("sink", config);
+        let input = receiver.monitor();
+        let sink = thread_manager.add_task(
+            Sink {
+                receiver,
+                per_message,
+            },
+            Duration::ZERO,
+        );
+        let source = thread_manager.add_task(Source { sender, next: 0 }, Duration::ZERO);
+        StageGraph::new(vec![
+            Stage::new("sink")
+                .with_tasks([sink])
+                .with_input(input)
+                .with_upstream(&["source"]),
+            Stage::new("source").with_tasks([source]),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn given_stages_listed_out_of_order_when_graph_built_then_upstream_stages_come_first() {
+        let graph = StageGraph::new(vec![
+            Stage::new("store").with_upstream(&["parser"]),
+            Stage::new("logger").with_upstream(&["ingestor", "store"]),
+            Stage::new("parser").with_upstream(&["ingestor"]),
+            Stage::new("ingestor"),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            graph.shutdown_order(),
+            ["ingestor", "parser", "store", "logger"]
+        );
+    }
+
+    #[rstest::rstest]
+    #[case::cycle(&["b"], &["a"], "Stages [\"a\", \"b\"] depend on each other")]
+    #[case::unknown(&[], &["c"], "Stage b lists unknown upstream stage c")]
+    fn given_invalid_stages_when_graph_built_then_error_names_stages(
+        #[case] upstream_of_a: &[&'static str],
+        #[case] upstream_of_b: &[&'static str],
+        #[case] expected: &str,
+    ) {
+        let error = StageGraph::new(vec![
+            Stage::new("a").with_upstream(upstream_of_a),
+            Stage::new("b").with_upstream(upstream_of_b),
+        ])
+        .unwrap_err();
+
+        assert_eq!(error.to_string(), expected);
+    }
+
+    #[test]
+    fn given_fast_sink_when_shut_down_then_it_drains_its_input_cleanly() {
+        let mut thread_manager = ThreadManager::new();
+        let graph = source_and_sink(&mut thread_manager, Duration::ZERO);
+        std::thread::sleep(Duration::from_millis(20));
+
+        let report = graph.shutdown(&mut thread_manager, Instant::now() + Duration::from_secs(5));
+
+        let names: Vec<_> = report.stages.iter().map(|stage| stage.name).collect();
+        assert_eq!(names, ["source", "sink"]);
+        assert!(report.is_clean(), "{report}");
+        assert!(thread_manager.is_task_finished(0) && thread_manager.is_task_finished(1));
+    }
+
+    #[test]
+    fn given_sink_slower_than_deadline_when_shut_down_then_queued_messages_are_lost() {
+        let mut thread_manager = ThreadManager::new();
+        let graph = source_and_sink(&mut thread_manager, Duration::from_millis(5));
+        std::thread::sleep(Duration::from_millis(20));
+
+        let report = graph.shutdown(
+            &mut thread_manager,
+            Instant::now() + Duration::from_millis(30),
+        );
+
+        let sink = report.stages[1];
+        assert!(!sink.drained);
+        assert!(sink.lost > 0);
+        assert_eq!(report.lost(), sink.lost);
+        assert!(report.elapsed < Duration::from_secs(1));
+    }
+}
@@ -2,7 +2,7 @@ use std::io::Write;
 
 use ogn_aprs_parser::ICAOAddress;
 
-use crate::core::parser::Aircraft;
+use crate::core::parser::{AddressType, Aircraft, AircraftType};
 
 pub struct TestPath {
     _guard: tempfile::TempDir,
@@ -32,13 +32,23 @@ pub fn create_dummy_aircraft_at_time(
 ) -> Aircraft {
     Aircraft {
         callsign: String::from("dummy"),
-        icao_address,
+        icao_address: Some(icao_address),
         datetime,
         latitude: 0.0,
         longitude: 0.0,
-        ground_track: 0.0,
-        ground_speed: 0.0,
-        gps_altitude: 0.0,
+        ground_track: Some(0.0),
+        ground_speed_knots: Some(0.0),
+        gps_altitude_feet: Some(0.0),
+        receiver: String::from("dummy"),
+        aircraft_type: AircraftType::default(),
+        address_type: AddressType::default(),
+        stealth: false,
+        no_tracking: false,
+        climb_rate_metres_per_second: None,
+        turn_rate_degrees_per_second: None,
+        signal_quality_db: None,
+        frequency_offset_khz: None,
+        gps_accuracy: None,
     }
 }
 